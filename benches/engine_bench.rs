@@ -1,14 +1,24 @@
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_decimal::Decimal;
+use dblentry::auth::Role;
 use dblentry::evaluator::{ExpressionEvaluator, QueryVariables};
+use dblentry::models::{DataValue, DimensionFilter};
 use dblentry::function_registry::{Function, FunctionRegistry};
 use dblentry::functions::{Balance, Statement, TrialBalance};
 use dblentry::lexer;
 use dblentry::statement_executor::{ExecutionContext, StatementExecutor};
-use dblentry::storage::StorageBackend;
+use dblentry::storage::{StorageBackend, DEFAULT_ENTITY};
 use dblentry_memory::InMemoryStorage;
 
+/// Context for statements that post or set up the ledger, which readers cannot run.
+fn admin_context(effective_date: time::Date) -> ExecutionContext {
+    let mut ctx = ExecutionContext::new(effective_date, QueryVariables::new());
+    ctx.role = Role::Admin;
+    ctx
+}
+
 fn setup() -> (Arc<dyn StorageBackend>, StatementExecutor) {
     let storage: Arc<dyn StorageBackend> = Arc::new(InMemoryStorage::new());
     let registry = FunctionRegistry::new();
@@ -49,7 +59,7 @@ fn seed_data(exec: &StatementExecutor) {
     .unwrap();
 
     let eff = time::Date::from_calendar_date(2023, time::Month::January, 1).unwrap();
-    let mut ctx = ExecutionContext::new(eff, QueryVariables::new());
+    let mut ctx = admin_context(eff);
     for s in &stmts {
         exec.execute(&mut ctx, s).unwrap();
     }
@@ -122,7 +132,7 @@ fn bench_journal_creation(c: &mut Criterion) {
     )
     .unwrap();
     let eff = time::Date::from_calendar_date(2023, time::Month::January, 1).unwrap();
-    let mut ctx = ExecutionContext::new(eff, QueryVariables::new());
+    let mut ctx = admin_context(eff);
     for s in &setup_stmts {
         exec.execute(&mut ctx, s).unwrap();
     }
@@ -134,10 +144,91 @@ fn bench_journal_creation(c: &mut Criterion) {
 
     c.bench_function("journal_creation", |b| {
         b.iter(|| {
-            let mut ctx = ExecutionContext::new(eff, QueryVariables::new());
+            let mut ctx = admin_context(eff);
+            exec.execute(&mut ctx, black_box(&stmts[0])).unwrap()
+        })
+    });
+}
+
+const ACCRUAL_CUSTOMERS: usize = 200;
+
+/// Loans drawn down on a different day for each customer, so the ledger has a year of history.
+fn seed_accrual_data(exec: &StatementExecutor) {
+    let stmts = lexer::parse(
+        "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+
+        CREATE RATE prime;
+        SET RATE prime 0.05 2023-01-01;
+        SET RATE prime 0.06 2023-07-01;
+    ",
+    )
+    .unwrap();
+
+    let eff = time::Date::from_calendar_date(2023, time::Month::January, 1).unwrap();
+    let mut ctx = admin_context(eff);
+    for s in &stmts {
+        exec.execute(&mut ctx, s).unwrap();
+    }
+
+    for i in 0..ACCRUAL_CUSTOMERS {
+        let date = eff + time::Duration::days((i % 365) as i64);
+        let fql = format!(
+            "CREATE JOURNAL {}, 1000, 'Loan {}' FOR Customer='C{}' DEBIT @loans, CREDIT @bank",
+            date, i, i
+        );
+        let stmts = lexer::parse(&fql).unwrap();
+        exec.execute(&mut ctx, &stmts[0]).unwrap();
+    }
+}
+
+/// Daily compounding the way ACCRUE used to do it: a rate lookup per day and a balance
+/// query per day for every customer.
+fn accrue_per_day_queries(storage: &dyn StorageBackend, from: time::Date, to: time::Date) -> HashMap<Arc<DataValue>, Decimal> {
+    let key: Arc<str> = Arc::from("Customer");
+    let customers = storage.get_dimension_values(DEFAULT_ENTITY, "loans", key.clone(), from, to).unwrap();
+    let mut amounts = HashMap::new();
+    let mut dt = from;
+    while dt <= to {
+        let rate = storage.get_rate(DEFAULT_ENTITY, "prime", dt).unwrap();
+        for customer in &customers {
+            let dim = DimensionFilter::from((key.clone(), customer.clone()));
+            let open = storage.get_balance(DEFAULT_ENTITY, "loans", dt, &dim).unwrap();
+            let accrued = amounts.entry(customer.clone()).or_insert(Decimal::ZERO);
+            *accrued += (open + *accrued) * rate / Decimal::from(365);
+        }
+        dt = dt.next_day().unwrap();
+    }
+    amounts
+}
+
+fn bench_accrue(c: &mut Criterion) {
+    let (storage, exec) = setup();
+    seed_accrual_data(&exec);
+
+    let from = time::Date::from_calendar_date(2023, time::Month::January, 1).unwrap();
+    let to = time::Date::from_calendar_date(2023, time::Month::December, 31).unwrap();
+    let stmts = lexer::parse(
+        "ACCRUE @loans FROM 2023-01-01 TO 2023-12-31 WITH RATE prime COMPOUND DAILY BY Customer
+         INTO JOURNAL 2023-12-31, 'Interest' DEBIT @interest_receivable, CREDIT @interest_income",
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("accrue_year");
+    group.sample_size(10);
+    group.bench_function("per_day_queries", |b| {
+        b.iter(|| accrue_per_day_queries(storage.as_ref(), black_box(from), black_box(to)))
+    });
+    group.bench_function("engine", |b| {
+        b.iter(|| {
+            let mut ctx = admin_context(to);
             exec.execute(&mut ctx, black_box(&stmts[0])).unwrap()
         })
     });
+    group.finish();
 }
 
 criterion_group!(
@@ -145,6 +236,7 @@ criterion_group!(
     bench_parse,
     bench_balance_query,
     bench_trial_balance,
-    bench_journal_creation
+    bench_journal_creation,
+    bench_accrue
 );
 criterion_main!(benches);
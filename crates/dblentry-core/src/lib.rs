@@ -4,11 +4,13 @@
 //! enabling pluggable storage implementations in separate crates.
 
 pub mod models;
+pub mod params;
 pub mod storage;
 
 // Re-export key types at crate root for convenience
-pub use models::{DataValue, StatementTxn, TrialBalanceItem, AccountType, AccountExpression, Lot, LotItem, CostMethod};
+pub use models::{DataValue, DimensionFilter, RateChange, StatementTxn, TrialBalanceItem, DimensionBalanceItem, BalanceSeriesItem, AmortizationItem, DepreciationItem, BalanceSheet, BalanceSheetSection, BalanceSheetLine, CashFlowStatement, CashFlowSection, CashFlowLine, AccountType, AccountClass, CashFlowCategory, AccountExpression, Lot, LotItem, CostMethod, FixedAsset, DepreciationMethod};
 pub use models::write::{CreateJournalCommand, LedgerEntryCommand, CreateRateCommand, SetRateCommand};
-pub use models::read::JournalEntry;
-pub use storage::{StorageBackend, StorageError, TransactionId};
+pub use models::read::{JournalEntry, JournalLine};
+pub use params::{FqlParam, FqlParams};
+pub use storage::{DimensionMovement, StorageBackend, StorageError, TransactionId};
 
@@ -13,6 +13,28 @@ pub enum AccountType {
     Equity,
     Income,
     Expense,
+    /// Non-monetary quantities such as headcount or floor space. Postings are single-sided
+    /// and stay out of the balancing check and the financial statements.
+    Statistical,
+}
+
+/// Balance sheet classification of asset and liability accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountClass {
+    Current,
+    NonCurrent,
+}
+
+/// Cash flow statement category. `Cash` marks the accounts the statement reconciles to;
+/// the others say where changes in an account's balance are reported.
+#[derive(Debug, Clone, Copy, PartialEq, Hash, Eq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CashFlowCategory {
+    Cash,
+    Operating,
+    Investing,
+    Financing,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -20,6 +42,15 @@ pub struct AccountExpression {
     pub id: Arc<str>,
     pub account_type: AccountType,
     pub unit_rate_id: Option<Arc<str>>,
+    /// Parent in the chart of accounts; balances roll up into it.
+    pub parent: Option<Arc<str>>,
+    /// Current/non-current split used by `balance_sheet()`; inherited from the parent when absent.
+    pub class: Option<AccountClass>,
+    /// Category used by `cash_flow_statement()`; inherited from the parent when absent.
+    pub cash_flow: Option<CashFlowCategory>,
+    /// Transaction currency, named by the rate that converts it into the functional currency;
+    /// inherited from the parent when absent. `None` means the functional currency.
+    pub currency: Option<Arc<str>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -38,6 +69,35 @@ pub struct Lot {
     pub dimensions: BTreeMap<Arc<str>, Arc<DataValue>>,
 }
 
+/// How a fixed asset's depreciable amount is spread over its useful life.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepreciationMethod {
+    StraightLine,
+    /// Book value × factor ÷ life each year (2 for double-declining), switching to straight
+    /// line once that depreciates more.
+    DecliningBalance(Decimal),
+    SumOfYears,
+}
+
+/// A fixed asset in the register and how far its depreciation has been posted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FixedAsset {
+    pub id: Arc<str>,
+    pub cost: Decimal,
+    pub in_service: Date,
+    pub life_years: u32,
+    pub salvage: Decimal,
+    pub method: DepreciationMethod,
+    /// Account carrying the asset's cost.
+    pub asset_account: Arc<str>,
+    /// Contra-asset account credited with the depreciation.
+    pub accumulated_account: Arc<str>,
+    pub expense_account: Arc<str>,
+    /// Last period end whose depreciation has been posted.
+    pub depreciated_through: Option<Date>,
+    pub disposed_on: Option<Date>,
+}
+
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct LotItem {
     pub date: Date,
@@ -63,6 +123,13 @@ pub enum DataValue {
     Statement(Vec<StatementTxn>),
     TrialBalance(Vec<TrialBalanceItem>),
     Lots(Vec<LotItem>),
+    Journals(Vec<read::JournalEntry>),
+    DimensionBalances(Vec<DimensionBalanceItem>),
+    BalanceSheet(BalanceSheet),
+    CashFlowStatement(CashFlowStatement),
+    BalanceSeries(Vec<BalanceSeriesItem>),
+    Amortization(Vec<AmortizationItem>),
+    DepreciationSchedule(Vec<DepreciationItem>),
 }
 
 impl DataValue {
@@ -71,6 +138,76 @@ impl DataValue {
     }
 }
 
+/// A conjunction of dimension constraints, e.g. `Customer='Acme' AND Region='US'`.
+/// Each value matches itself and its `/`-separated descendants ("Americas" matches "Americas/US").
+/// An empty filter matches everything.
+#[derive(Debug, Clone, Default, PartialEq, Hash, Eq)]
+pub struct DimensionFilter(Vec<(Arc<str>, Arc<DataValue>)>);
+
+impl DimensionFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, key: Arc<str>, value: Arc<DataValue>) {
+        self.0.push((key, value));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &(Arc<str>, Arc<DataValue>)> {
+        self.0.iter()
+    }
+
+    /// True if `dimensions` satisfies every constraint in the filter.
+    pub fn matches(&self, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> bool {
+        self.0.iter().all(|(key, value)| {
+            dimensions
+                .get(key)
+                .is_some_and(|actual| dimension_value_matches(value, actual))
+        })
+    }
+}
+
+impl From<(Arc<str>, Arc<DataValue>)> for DimensionFilter {
+    fn from(dimension: (Arc<str>, Arc<DataValue>)) -> Self {
+        Self(vec![dimension])
+    }
+}
+
+impl FromIterator<(Arc<str>, Arc<DataValue>)> for DimensionFilter {
+    fn from_iter<I: IntoIterator<Item = (Arc<str>, Arc<DataValue>)>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+/// Hierarchical match: `filter` equals `actual`, or is a `/`-separated ancestor of it.
+pub fn dimension_value_matches(filter: &DataValue, actual: &DataValue) -> bool {
+    if filter == actual {
+        return true;
+    }
+    match (filter, actual) {
+        (DataValue::String(prefix), DataValue::String(value)) => {
+            value.starts_with(prefix.as_ref()) && value.as_bytes().get(prefix.len()) == Some(&b'/')
+        }
+        _ => false,
+    }
+}
+
+/// A rate value taking effect on `date`, for every entry or only those tagged with `dimension`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateChange {
+    pub date: Date,
+    pub rate: Decimal,
+    pub dimension: Option<(Arc<str>, Arc<DataValue>)>,
+}
+
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct StatementTxn {
     pub journal_id: u128,
@@ -85,4 +222,114 @@ pub struct TrialBalanceItem {
     pub account_id: Arc<str>,
     pub account_type: AccountType,    
     pub balance: Decimal,
+    /// Nesting level in the account tree (0 for top-level accounts).
+    pub depth: usize,
+    /// True for parent accounts whose balance is the sum of their descendants.
+    pub subtotal: bool,
+}
+
+impl TrialBalanceItem {
+    /// The balance placed in the debit or credit column by its sign, so that a credit
+    /// balance on an asset (an overdraft) shows as a credit. Zero goes to the normal side.
+    pub fn debit_credit(&self) -> (Option<Decimal>, Option<Decimal>) {
+        let debit_normal = matches!(self.account_type, AccountType::Asset | AccountType::Expense | AccountType::Statistical);
+        let debit = if debit_normal { self.balance } else { -self.balance };
+        if debit > Decimal::ZERO || (debit.is_zero() && debit_normal) {
+            (Some(debit), None)
+        } else {
+            (None, Some(-debit))
+        }
+    }
+}
+
+/// Balance at the end of one period and its change since the previous period end.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct BalanceSeriesItem {
+    pub period_end: Date,
+    pub balance: Decimal,
+    pub change: Decimal,
+}
+
+/// Depreciation for one month of a fixed asset's life and the totals after it.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct DepreciationItem {
+    pub period_end: Date,
+    pub depreciation: Decimal,
+    pub accumulated: Decimal,
+    pub book_value: Decimal,
+}
+
+/// One payment of an amortization schedule and the balance left after it.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct AmortizationItem {
+    pub number: u32,
+    pub date: Date,
+    pub payment: Decimal,
+    pub interest: Decimal,
+    pub principal: Decimal,
+    pub balance: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct DimensionBalanceItem {
+    pub dimension: Arc<str>,
+    pub value: Arc<DataValue>,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct BalanceSheet {
+    pub date: Date,
+    /// Asset sections first, then liabilities, then equity.
+    pub sections: Vec<BalanceSheetSection>,
+    pub total_assets: Decimal,
+    pub total_liabilities: Decimal,
+    /// Equity accounts plus net income not yet closed into equity.
+    pub total_equity: Decimal,
+    /// True when assets equal liabilities plus equity.
+    pub balanced: bool,
+}
+
+/// Accounts of one type and, for classified statements, one class.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct BalanceSheetSection {
+    pub account_type: AccountType,
+    pub class: Option<AccountClass>,
+    pub lines: Vec<BalanceSheetLine>,
+    pub subtotal: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct BalanceSheetLine {
+    pub account_id: Arc<str>,
+    pub balance: Decimal,
+}
+
+/// Indirect-method statement of cash flows for the period `(from, to]`.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct CashFlowStatement {
+    pub from: Date,
+    pub to: Date,
+    pub net_income: Decimal,
+    /// Operating, investing and financing, in that order. Operating starts with net income.
+    pub sections: Vec<CashFlowSection>,
+    pub net_change: Decimal,
+    pub opening_cash: Decimal,
+    pub closing_cash: Decimal,
+    /// True when the net change equals the change in cash account balances.
+    pub reconciled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct CashFlowSection {
+    pub category: CashFlowCategory,
+    pub lines: Vec<CashFlowLine>,
+    pub subtotal: Decimal,
+}
+
+/// Cash effect of the change in one account's balance: positive is an inflow.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct CashFlowLine {
+    pub account_id: Arc<str>,
+    pub amount: Decimal,
 }
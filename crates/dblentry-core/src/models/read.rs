@@ -5,7 +5,7 @@ use time::{Date, OffsetDateTime};
 
 use super::DataValue;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
 pub struct JournalEntry {
     pub id: u128,
     pub sequence: u64,
@@ -14,4 +14,21 @@ pub struct JournalEntry {
     pub amount: Decimal,
     pub dimensions: BTreeMap<Arc<str>, Arc<DataValue>>,
     pub created_at: OffsetDateTime,
+    pub lines: Vec<JournalLine>,
+    /// The journal this one reverses, if it is a reversal.
+    pub reverses: Option<u128>,
+    /// The reversal posted against this journal, if any.
+    pub reversed_by: Option<u128>,
+}
+
+/// One leg of a posted journal. Exactly one of `debit` and `credit` is non-zero.
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub struct JournalLine {
+    pub account_id: Arc<str>,
+    pub debit: Decimal,
+    pub credit: Decimal,
+    /// The leg in the account's transaction currency, for currency accounts.
+    pub currency_amount: Option<Decimal>,
+    /// The leg's dimensions, when they differ from the journal's.
+    pub dimensions: Option<BTreeMap<Arc<str>, Arc<DataValue>>>,
 }
@@ -12,12 +12,25 @@ pub struct CreateJournalCommand {
     pub amount: Decimal,
     pub ledger_entries: Vec<LedgerEntryCommand>,
     pub dimensions: BTreeMap<Arc<str>, Arc<DataValue>>,
+    /// Dimensions of the legs, by index into `ledger_entries`, that differ from the journal's.
+    pub leg_dimensions: BTreeMap<usize, BTreeMap<Arc<str>, Arc<DataValue>>>,
+    /// Set when this journal reverses an earlier one.
+    pub reverses: Option<u128>,
 }
 
+impl CreateJournalCommand {
+    /// Dimensions recorded on the leg at `index` of `ledger_entries`.
+    pub fn entry_dimensions(&self, index: usize) -> &BTreeMap<Arc<str>, Arc<DataValue>> {
+        self.leg_dimensions.get(&index).unwrap_or(&self.dimensions)
+    }
+}
+
+/// `amount` is in the functional currency. Legs on currency accounts also carry
+/// `currency_amount`, the same leg in the account's transaction currency.
 #[derive(Debug, Clone, PartialEq)]
 pub enum LedgerEntryCommand {
-    Debit {account_id: Arc<str>, amount: Decimal, units: Option<Decimal>},
-    Credit {account_id: Arc<str>, amount: Decimal, units: Option<Decimal>},
+    Debit {account_id: Arc<str>, amount: Decimal, units: Option<Decimal>, currency_amount: Option<Decimal>},
+    Credit {account_id: Arc<str>, amount: Decimal, units: Option<Decimal>, currency_amount: Option<Decimal>},
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,4 +43,6 @@ pub struct SetRateCommand {
     pub id: Arc<str>,
     pub date: Date,
     pub rate: Decimal,
+    /// Restricts the value to entries tagged with this dimension value.
+    pub dimension: Option<(Arc<str>, Arc<DataValue>)>,
 }
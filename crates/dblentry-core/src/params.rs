@@ -0,0 +1,88 @@
+//! Typed parameters bound to `$name` references in FQL queries.
+
+use std::{collections::BTreeMap, str::FromStr, sync::Arc};
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use time::{Date, Month};
+
+use crate::models::DataValue;
+
+/// A typed FQL parameter. Serialized as `{"type": "money", "value": "100.00"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "lowercase")]
+pub enum FqlParam {
+    Money(String),
+    Percentage(String),
+    String(String),
+    Date(String),
+    Account(String),
+}
+
+/// Named parameters for a query, keyed without the `$` prefix.
+pub type FqlParams = BTreeMap<String, FqlParam>;
+
+impl FqlParam {
+    /// Build a parameter from a type name and raw value (as sent over gRPC or MCP).
+    pub fn from_parts(kind: &str, value: impl Into<String>) -> Result<Self, String> {
+        let value = value.into();
+        match kind.to_ascii_lowercase().as_str() {
+            "money" => Ok(FqlParam::Money(value)),
+            "percentage" => Ok(FqlParam::Percentage(value)),
+            "string" => Ok(FqlParam::String(value)),
+            "date" => Ok(FqlParam::Date(value)),
+            "account" => Ok(FqlParam::Account(value)),
+            other => Err(format!(
+                "unknown parameter type '{}': expected money, percentage, string, date or account",
+                other
+            )),
+        }
+    }
+
+    /// Convert to the value seen by the evaluator.
+    pub fn to_data_value(&self) -> Result<DataValue, String> {
+        match self {
+            FqlParam::Money(v) => Decimal::from_str(v.trim())
+                .map(DataValue::Money)
+                .map_err(|_| format!("invalid money value '{}'", v)),
+            // "5" and "5%" are both five percent, held as a fraction like a `5%` literal
+            FqlParam::Percentage(v) => Decimal::from_str(v.trim().trim_end_matches('%'))
+                .map(|percent| DataValue::Percentage(percent / Decimal::ONE_HUNDRED))
+                .map_err(|_| format!("invalid percentage value '{}'", v)),
+            FqlParam::String(v) => Ok(DataValue::String(Arc::from(v.as_str()))),
+            FqlParam::Date(v) => parse_date(v)
+                .map(DataValue::Date)
+                .ok_or_else(|| format!("invalid date '{}': expected YYYY-MM-DD", v)),
+            FqlParam::Account(v) => {
+                let id = v.strip_prefix('@').unwrap_or(v);
+                if !id.is_empty() && id.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                    Ok(DataValue::AccountId(Arc::from(id)))
+                } else {
+                    Err(format!("invalid account id '{}'", v))
+                }
+            }
+        }
+    }
+}
+
+/// Convert all parameters into query variables. Errors name the offending parameter.
+pub fn bind_params(params: &FqlParams) -> Result<BTreeMap<Arc<str>, DataValue>, String> {
+    params
+        .iter()
+        .map(|(name, param)| {
+            let name = name.strip_prefix('$').unwrap_or(name);
+            param
+                .to_data_value()
+                .map(|value| (Arc::from(name), value))
+                .map_err(|e| format!("parameter ${}: {}", name, e))
+        })
+        .collect()
+}
+
+fn parse_date(s: &str) -> Option<Date> {
+    let mut parts = s.trim().splitn(3, '-');
+    let year = parts.next()?.parse::<i32>().ok()?;
+    let month = parts.next()?.parse::<u8>().ok()?;
+    let day = parts.next()?.parse::<u8>().ok()?;
+    Date::from_calendar_date(year, Month::try_from(month).ok()?, day).ok()
+}
@@ -5,7 +5,8 @@ use time::Date;
 
 use crate::models::{
     write::{CreateJournalCommand, CreateRateCommand, SetRateCommand},
-    AccountExpression, AccountType, DataValue, LotItem, CostMethod,
+    read::JournalEntry,
+    AccountClass, AccountExpression, CashFlowCategory, AccountType, DataValue, DimensionFilter, FixedAsset, LotItem, CostMethod, RateChange,
 };
 
 use thiserror::Error;
@@ -32,25 +33,91 @@ pub enum StorageError {
     EntityAlreadyExists(String),
     #[error("account already exists: {0}")]
     DuplicateAccount(String),
+    #[error("journal not found: {0}")]
+    JournalNotFound(String),
+    #[error("invalid parent account: {0}")]
+    InvalidParentAccount(String),
+    #[error("cannot post to parent account: {0}")]
+    ParentAccountPosting(String),
+    #[error("period is locked through {0}")]
+    PeriodLocked(Date),
+    #[error("fixed asset not found: {0}")]
+    FixedAssetNotFound(String),
+    #[error("fixed asset already exists: {0}")]
+    DuplicateFixedAsset(String),
+}
+
+/// Reject changes dated on or before the entity's lock date.
+pub fn ensure_period_open(locked_through: Option<Date>, date: Date) -> Result<(), StorageError> {
+    match locked_through {
+        Some(lock) if date <= lock => Err(StorageError::PeriodLocked(lock)),
+        _ => Ok(()),
+    }
 }
 
 pub type TransactionId = u64;
 
+/// Net movement on one day for one combination of dimension values.
+pub type DimensionMovement = (Date, Vec<Arc<DataValue>>, Decimal);
+
 pub trait StorageBackend: Send + Sync {
     // Entity management
     fn create_entity(&self, entity_id: &str) -> Result<(), StorageError>;
     fn list_entities(&self) -> Vec<Arc<str>>;
     fn entity_exists(&self, entity_id: &str) -> bool;
 
+    // Period locks — journals, rates and lot changes dated on or before the lock date are
+    // rejected with `PeriodLocked`. The lock only moves forward; `unlock_period` clears it.
+    fn lock_period(&self, entity_id: &str, through: Date) -> Result<(), StorageError>;
+    fn unlock_period(&self, entity_id: &str) -> Result<(), StorageError>;
+    fn get_period_lock(&self, entity_id: &str) -> Result<Option<Date>, StorageError>;
+
+    // Year-end closes
+    /// Record that the year ending `year_end` was closed by the given journals.
+    fn record_year_close(&self, entity_id: &str, year_end: Date, journal_ids: &[u128]) -> Result<(), StorageError>;
+    /// Closed year ends with their closing journals, oldest first.
+    fn list_year_closes(&self, entity_id: &str) -> Result<Vec<(Date, Vec<u128>)>, StorageError>;
+
     // All data operations scoped by entity_id
     fn create_account(&self, entity_id: &str, account: &AccountExpression) -> Result<(), StorageError>;
     fn create_rate(&self, entity_id: &str, rate: &CreateRateCommand) -> Result<(), StorageError>;
     fn set_rate(&self, entity_id: &str, command: &SetRateCommand) -> Result<(), StorageError>;
+    /// The undimensioned value in effect on `date`.
     fn get_rate(&self, entity_id: &str, id: &str, date: Date) -> Result<Decimal, StorageError>;
-    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<(), StorageError>;
-    fn get_balance(&self, entity_id: &str, account_id: &str, date: Date, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Decimal, StorageError>;
-    fn get_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<DataValue, StorageError>;
+    /// Values of the rate over `from..=to`, ordered by date: for the undimensioned rate and each
+    /// dimension value it is set for, the value in effect on `from` (dated `from`) followed by
+    /// each change up to `to`.
+    fn get_rate_changes(&self, entity_id: &str, id: &str, from: Date, to: Date) -> Result<Vec<RateChange>, StorageError>;
+    /// Post a journal and return its id.
+    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<u128, StorageError>;
+    /// Fetch a posted journal with its ledger legs.
+    fn get_journal(&self, entity_id: &str, journal_id: u128) -> Result<JournalEntry, StorageError>;
+    /// Journals dated within `from..=to`, ordered by date then sequence, matching every dimension in the filter.
+    fn list_journals(&self, entity_id: &str, from: Date, to: Date, dimensions: &DimensionFilter) -> Result<Vec<JournalEntry>, StorageError>;
+    /// Balances and statements of a parent account include all of its descendants.
+    fn get_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError>;
+    fn get_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError>;
+    /// Like `get_balance` and `get_statement`, but summing the transaction-currency amounts of a
+    /// currency account and its descendants, which share its currency.
+    fn get_currency_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError>;
+    fn get_currency_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError>;
     fn get_dimension_values(&self, entity_id: &str, account_id: &str, dimension_key: Arc<str>, from: Date, to: Date) -> Result<HashSet<Arc<DataValue>>, StorageError>;
+    /// Distinct value combinations, in `dimension_keys` order, of entries on the account or its
+    /// descendants dated within `from..=to` that carry every key. No keys yields the single
+    /// empty combination.
+    fn get_dimension_combinations(&self, entity_id: &str, account_id: &str, dimension_keys: &[Arc<str>], from: Date, to: Date) -> Result<HashSet<Vec<Arc<DataValue>>>, StorageError>;
+    /// Net movement of the account and its descendants per day over `from..=to` and exact value
+    /// combination of `dimension_keys`, ordered by date. Everything posted before `from` is
+    /// folded into the `from` movement, so it opens at the balance then. Entries missing a key
+    /// are left out; no keys gives one total per day.
+    fn get_dimension_movements(&self, entity_id: &str, account_id: &str, dimension_keys: &[Arc<str>], from: Date, to: Date) -> Result<Vec<DimensionMovement>, StorageError>;
+    /// Balances at each of the ascending `dates`, read in a single pass over the ledger.
+    fn get_balance_series(&self, entity_id: &str, account_id: &str, dates: &[Date], dimensions: &DimensionFilter) -> Result<Vec<Decimal>, StorageError>;
+    /// Total debits and credits posted to the account and its descendants between `from` and `to`.
+    fn get_turnover(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Date, dimensions: &DimensionFilter) -> Result<(Decimal, Decimal), StorageError>;
+    /// Net movement per exact value of `dimension_key` over `from..=to`, ordered by value.
+    /// Entries without the dimension are left out.
+    fn get_balances_by_dimension(&self, entity_id: &str, account_id: &str, dimension_key: &str, from: Bound<Date>, to: Date) -> Result<Vec<(Arc<DataValue>, Decimal)>, StorageError>;
     fn list_accounts(&self, entity_id: &str) -> Vec<(Arc<str>, AccountType)>;
     fn list_rates(&self, entity_id: &str) -> Vec<Arc<str>>;
 
@@ -58,11 +125,24 @@ pub trait StorageBackend: Send + Sync {
     fn commit_transaction(&self, tx_id: TransactionId) -> Result<(), StorageError>;
     fn rollback_transaction(&self, tx_id: TransactionId) -> Result<(), StorageError>;
 
-    // Unit/lot operations — dimension parameter enables filtering by dimension prefix
-    fn get_lots(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Vec<LotItem>, StorageError>;
-    fn get_total_units(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Decimal, StorageError>;
-    fn deplete_lots(&self, entity_id: &str, account_id: &str, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError>;
-    fn split_lots(&self, entity_id: &str, account_id: &str, new_per_old: Decimal, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<(), StorageError>;
+    // Fixed asset register
+    fn create_fixed_asset(&self, entity_id: &str, asset: &FixedAsset) -> Result<(), StorageError>;
+    fn get_fixed_asset(&self, entity_id: &str, asset_id: &str) -> Result<FixedAsset, StorageError>;
+    /// Every registered asset, disposed ones included, ordered by id.
+    fn list_fixed_assets(&self, entity_id: &str) -> Result<Vec<FixedAsset>, StorageError>;
+    /// Record how far the asset's depreciation has been posted and when it was disposed of.
+    fn update_fixed_asset(&self, entity_id: &str, asset_id: &str, depreciated_through: Option<Date>, disposed_on: Option<Date>) -> Result<(), StorageError>;
+
+    // Unit/lot operations — dimension filters match hierarchically ("Americas" matches "Americas/US")
+    fn get_lots(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Vec<LotItem>, StorageError>;
+    fn get_total_units(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Decimal, StorageError>;
+    fn deplete_lots(&self, entity_id: &str, account_id: &str, date: Date, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError>;
+    fn split_lots(&self, entity_id: &str, account_id: &str, date: Date, new_per_old: Decimal, dimensions: &DimensionFilter) -> Result<(), StorageError>;
     fn get_unit_rate_id(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>>;
+    fn get_parent_account(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>>;
+    fn get_account_type(&self, entity_id: &str, account_id: &str) -> Option<AccountType>;
+    fn get_account_class(&self, entity_id: &str, account_id: &str) -> Option<AccountClass>;
+    fn get_cash_flow_category(&self, entity_id: &str, account_id: &str) -> Option<CashFlowCategory>;
+    fn get_account_currency(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>>;
     fn is_unit_account(&self, entity_id: &str, account_id: &str) -> bool;
 }
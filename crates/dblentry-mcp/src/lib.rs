@@ -1,5 +1,6 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, sync::Arc};
 
+use dblentry_core::params::{FqlParam, FqlParams};
 use dblentry_core::storage::StorageBackend;
 use rmcp::handler::server::tool::ToolRouter;
 use rmcp::handler::server::wrapper::Parameters;
@@ -20,7 +21,7 @@ pub struct FqlResult {
 /// Trait for executing FQL queries. Implemented by the main dblentry crate
 /// to avoid a cyclic dependency.
 pub trait FqlEngine: Send + Sync {
-    fn execute_fql(&self, fql: &str, entity: Option<&str>) -> Result<Vec<FqlResult>, String>;
+    fn execute_fql(&self, fql: &str, entity: Option<&str>, params: &FqlParams) -> Result<Vec<FqlResult>, String>;
 }
 
 #[derive(Clone)]
@@ -47,6 +48,18 @@ struct ExecuteFqlInput {
     /// Optional entity to execute against (defaults to "default")
     #[serde(default)]
     entity: Option<String>,
+    /// Optional named parameters, referenced as $name in the FQL
+    #[serde(default)]
+    params: BTreeMap<String, ParamInput>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+struct ParamInput {
+    /// One of "money", "percentage", "string", "date" (YYYY-MM-DD) or "account"
+    #[serde(rename = "type")]
+    kind: String,
+    /// The value, as a string (e.g. "100.50", "2024-01-31", "bank")
+    value: String,
 }
 
 #[derive(Deserialize, JsonSchema)]
@@ -140,7 +153,16 @@ impl DblEntryMcp {
         fql: &str,
         entity: Option<&str>,
     ) -> Result<CallToolResult, ErrorData> {
-        match self.engine.execute_fql(fql, entity) {
+        self.run_fql_with_params(fql, entity, &FqlParams::new())
+    }
+
+    fn run_fql_with_params(
+        &self,
+        fql: &str,
+        entity: Option<&str>,
+        params: &FqlParams,
+    ) -> Result<CallToolResult, ErrorData> {
+        match self.engine.execute_fql(fql, entity, params) {
             Ok(results) => {
                 let mut output = String::new();
                 let mut total_journals = 0;
@@ -167,12 +189,23 @@ impl DblEntryMcp {
 
 #[tool_router]
 impl DblEntryMcp {
-    #[tool(description = "Execute one or more FQL (Financial Query Language) statements against the DblEntry ledger. Use this for creating accounts, recording journals, querying balances, and all other bookkeeping operations. Separate multiple statements with semicolons.")]
+    #[tool(description = "Execute one or more FQL (Financial Query Language) statements against the DblEntry ledger. Use this for creating accounts, recording journals, querying balances, and all other bookkeeping operations. Separate multiple statements with semicolons. Pass values as typed params and reference them as $name instead of quoting them into the FQL.")]
     fn execute_fql(
         &self,
         Parameters(input): Parameters<ExecuteFqlInput>,
     ) -> Result<CallToolResult, ErrorData> {
-        self.run_fql_sync(&input.fql, input.entity.as_deref())
+        let mut params = FqlParams::new();
+        for (name, param) in input.params {
+            match FqlParam::from_parts(&param.kind, param.value) {
+                Ok(p) => {
+                    params.insert(name, p);
+                }
+                Err(e) => {
+                    return Ok(CallToolResult::error(vec![Content::text(format!("parameter ${}: {}", name, e))]));
+                }
+            }
+        }
+        self.run_fql_with_params(&input.fql, input.entity.as_deref(), &params)
     }
 
     #[tool(description = "Get the balance of an account at a specific date.")]
@@ -346,12 +379,12 @@ mod tests {
     struct MockEngine;
 
     impl FqlEngine for MockEngine {
-        fn execute_fql(&self, fql: &str, _entity: Option<&str>) -> Result<Vec<FqlResult>, String> {
+        fn execute_fql(&self, fql: &str, _entity: Option<&str>, params: &FqlParams) -> Result<Vec<FqlResult>, String> {
             if fql.contains("ERROR") {
                 return Err("Test error".to_string());
             }
             Ok(vec![FqlResult {
-                output: format!("Executed: {}", fql),
+                output: format!("Executed: {} ({} params)", fql, params.len()),
                 journals_created: 1,
             }])
         }
@@ -360,7 +393,7 @@ mod tests {
     #[test]
     fn test_fql_engine_success() {
         let engine = MockEngine;
-        let result = engine.execute_fql("CREATE ACCOUNT @bank ASSET", None);
+        let result = engine.execute_fql("CREATE ACCOUNT @bank ASSET", None, &FqlParams::new());
         assert!(result.is_ok());
         let results = result.unwrap();
         assert_eq!(results.len(), 1);
@@ -371,7 +404,7 @@ mod tests {
     #[test]
     fn test_fql_engine_error() {
         let engine = MockEngine;
-        let result = engine.execute_fql("ERROR", None);
+        let result = engine.execute_fql("ERROR", None, &FqlParams::new());
         assert!(result.is_err());
         assert_eq!(result.unwrap_err(), "Test error");
     }
@@ -379,10 +412,34 @@ mod tests {
     #[test]
     fn test_fql_engine_with_entity() {
         let engine = MockEngine;
-        let result = engine.execute_fql("GET balance(@bank, 2024-01-01) AS b", Some("corp"));
+        let result = engine.execute_fql("GET balance(@bank, 2024-01-01) AS b", Some("corp"), &FqlParams::new());
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_fql_engine_with_params() {
+        let engine = MockEngine;
+        let params = FqlParams::from([
+            ("account".to_string(), FqlParam::Account("bank".to_string())),
+            ("date".to_string(), FqlParam::Date("2024-01-01".to_string())),
+        ]);
+        let result = engine.execute_fql("GET balance($account, $date) AS b", None, &params).unwrap();
+        assert!(result[0].output.contains("2 params"));
+    }
+
+    #[test]
+    fn test_param_input_deserialize() {
+        let input: ExecuteFqlInput = serde_json::from_value(serde_json::json!({
+            "fql": "GET balance($acct, $d) AS b",
+            "params": {
+                "acct": { "type": "account", "value": "bank" },
+                "d": { "type": "date", "value": "2024-01-31" }
+            }
+        })).unwrap();
+        assert_eq!(input.params.len(), 2);
+        assert_eq!(input.params["acct"].kind, "account");
+    }
+
     #[test]
     fn test_fql_reference_included() {
         assert!(!FQL_REFERENCE.is_empty());
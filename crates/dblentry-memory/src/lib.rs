@@ -5,33 +5,79 @@ use time::Date;
 use uuid::Uuid;
 
 use dblentry_core::{
-    AccountExpression, AccountType,
+    AccountClass, AccountExpression, CashFlowCategory, AccountType,
     CreateJournalCommand, LedgerEntryCommand, CreateRateCommand, SetRateCommand,
-    DataValue, JournalEntry, StatementTxn, Lot, LotItem, CostMethod,
+    DataValue, DimensionFilter, FixedAsset, JournalEntry, JournalLine, RateChange, StatementTxn, Lot, LotItem, CostMethod,
 };
-use dblentry_core::storage::{StorageBackend, StorageError, TransactionId};
+use dblentry_core::storage::{ensure_period_open, DimensionMovement, StorageBackend, StorageError, TransactionId};
 
 const DEFAULT_ENTITY: &str = "default";
 
 #[derive(Clone)]
 struct EntityData {
     ledger_accounts: BTreeMap<Arc<str>, LedgerStore>,
+    /// Transaction-currency ledgers of currency accounts, alongside their functional ledgers.
+    currency_ledgers: BTreeMap<Arc<str>, LedgerStore>,
     rates: BTreeMap<Arc<str>, RateStore>,
     journals: BTreeMap<u128, JournalEntry>,
     lot_stores: BTreeMap<Arc<str>, LotStoreData>,
     unit_rate_links: BTreeMap<Arc<str>, Arc<str>>,
+    account_parents: BTreeMap<Arc<str>, Arc<str>>,
+    account_children: BTreeMap<Arc<str>, Vec<Arc<str>>>,
+    account_classes: BTreeMap<Arc<str>, AccountClass>,
+    cash_flow_categories: BTreeMap<Arc<str>, CashFlowCategory>,
+    account_currencies: BTreeMap<Arc<str>, Arc<str>>,
+    locked_through: Option<Date>,
+    year_closes: BTreeMap<Date, Vec<u128>>,
+    fixed_assets: BTreeMap<Arc<str>, FixedAsset>,
 }
 
 impl EntityData {
     fn new() -> Self {
         Self {
             ledger_accounts: BTreeMap::new(),
+            currency_ledgers: BTreeMap::new(),
             rates: BTreeMap::new(),
             journals: BTreeMap::new(),
             lot_stores: BTreeMap::new(),
             unit_rate_links: BTreeMap::new(),
+            account_parents: BTreeMap::new(),
+            account_children: BTreeMap::new(),
+            account_classes: BTreeMap::new(),
+            cash_flow_categories: BTreeMap::new(),
+            account_currencies: BTreeMap::new(),
+            locked_through: None,
+            year_closes: BTreeMap::new(),
+            fixed_assets: BTreeMap::new(),
         }
     }
+
+    /// Ledgers of the account and all of its descendants.
+    fn subtree_ledgers(&self, account_id: &str) -> Result<Vec<&LedgerStore>, StorageError> {
+        self.subtree_ledgers_in(&self.ledger_accounts, account_id)
+    }
+
+    /// Transaction-currency ledgers of the account and all of its descendants.
+    fn subtree_currency_ledgers(&self, account_id: &str) -> Result<Vec<&LedgerStore>, StorageError> {
+        self.subtree_ledgers_in(&self.currency_ledgers, account_id)
+    }
+
+    fn subtree_ledgers_in<'a>(&'a self, ledgers_by_account: &'a BTreeMap<Arc<str>, LedgerStore>, account_id: &str) -> Result<Vec<&'a LedgerStore>, StorageError> {
+        if !self.ledger_accounts.contains_key(account_id) {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+        let mut ledgers = Vec::new();
+        let mut pending = vec![account_id];
+        while let Some(id) = pending.pop() {
+            if let Some(ledger) = ledgers_by_account.get(id) {
+                ledgers.push(ledger);
+            }
+            if let Some(children) = self.account_children.get(id) {
+                pending.extend(children.iter().map(|c| c.as_ref()));
+            }
+        }
+        Ok(ledgers)
+    }
 }
 
 struct Snapshot {
@@ -88,6 +134,45 @@ impl StorageBackend for InMemoryStorage {
         self.entities.read().unwrap().contains_key(entity_id)
     }
 
+    fn lock_period(&self, entity_id: &str, through: Date) -> Result<(), StorageError> {
+        let mut entities = self.entities.write().unwrap();
+        let entity = entities.get_mut(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        ensure_period_open(entity.locked_through, through)?;
+        entity.locked_through = Some(through);
+        Ok(())
+    }
+
+    fn unlock_period(&self, entity_id: &str) -> Result<(), StorageError> {
+        let mut entities = self.entities.write().unwrap();
+        let entity = entities.get_mut(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        entity.locked_through = None;
+        Ok(())
+    }
+
+    fn get_period_lock(&self, entity_id: &str) -> Result<Option<Date>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        Ok(entity.locked_through)
+    }
+
+    fn record_year_close(&self, entity_id: &str, year_end: Date, journal_ids: &[u128]) -> Result<(), StorageError> {
+        let mut entities = self.entities.write().unwrap();
+        let entity = entities.get_mut(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        entity.year_closes.insert(year_end, journal_ids.to_vec());
+        Ok(())
+    }
+
+    fn list_year_closes(&self, entity_id: &str) -> Result<Vec<(Date, Vec<u128>)>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        Ok(entity.year_closes.iter().map(|(date, ids)| (*date, ids.clone())).collect())
+    }
+
     fn create_account(&self, entity_id: &str, account: &AccountExpression) -> Result<(), StorageError> {
         let mut entities = self.entities.write().unwrap();
         let entity = entities.get_mut(entity_id)
@@ -95,7 +180,34 @@ impl StorageBackend for InMemoryStorage {
         if entity.ledger_accounts.contains_key(&account.id) {
             return Err(StorageError::DuplicateAccount(account.id.to_string()));
         }
+        if let Some(ref parent) = account.parent {
+            let parent_ledger = entity.ledger_accounts.get(parent)
+                .ok_or_else(|| StorageError::AccountNotFound(parent.to_string()))?;
+            if parent_ledger.account_type != account.account_type {
+                return Err(StorageError::InvalidParentAccount(format!("@{} must have the same account type as @{}", account.id, parent)));
+            }
+            if entity.lot_stores.contains_key(parent) {
+                return Err(StorageError::InvalidParentAccount(format!("@{} is a unit account", parent)));
+            }
+            if !parent_ledger.days.is_empty() {
+                return Err(StorageError::InvalidParentAccount(format!("@{} already has ledger entries", parent)));
+            }
+            entity.account_parents.insert(account.id.clone(), parent.clone());
+            entity.account_children.entry(parent.clone()).or_default().push(account.id.clone());
+        }
+        let class = account.class.or_else(|| account.parent.as_ref().and_then(|p| entity.account_classes.get(p).copied()));
+        if let Some(class) = class {
+            entity.account_classes.insert(account.id.clone(), class);
+        }
+        let cash_flow = account.cash_flow.or_else(|| account.parent.as_ref().and_then(|p| entity.cash_flow_categories.get(p).copied()));
+        if let Some(cash_flow) = cash_flow {
+            entity.cash_flow_categories.insert(account.id.clone(), cash_flow);
+        }
         entity.ledger_accounts.insert(account.id.clone(), LedgerStore::new(account.account_type.clone()));
+        if let Some(ref currency) = account.currency {
+            entity.currency_ledgers.insert(account.id.clone(), LedgerStore::new(account.account_type.clone()));
+            entity.account_currencies.insert(account.id.clone(), currency.clone());
+        }
         if let Some(ref rate_id) = account.unit_rate_id {
             entity.lot_stores.insert(account.id.clone(), LotStoreData::new());
             entity.unit_rate_links.insert(account.id.clone(), rate_id.clone());
@@ -115,9 +227,10 @@ impl StorageBackend for InMemoryStorage {
         let mut entities = self.entities.write().unwrap();
         let entity = entities.get_mut(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        ensure_period_open(entity.locked_through, command.date)?;
         let rate_store = entity.rates.get_mut(&command.id)
             .ok_or_else(|| StorageError::RateNotFound(command.id.to_string()))?;
-        rate_store.add_rate(command.date, command.rate);
+        rate_store.add_rate(command.date, command.rate, command.dimension.clone());
         Ok(())
     }
 
@@ -130,7 +243,16 @@ impl StorageBackend for InMemoryStorage {
         rate_store.get_rate(date)
     }
 
-    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<(), StorageError> {
+    fn get_rate_changes(&self, entity_id: &str, id: &str, from: Date, to: Date) -> Result<Vec<RateChange>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let rate_store = entity.rates.get(id)
+            .ok_or_else(|| StorageError::RateNotFound(id.to_string()))?;
+        Ok(rate_store.get_changes(from, to))
+    }
+
+    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<u128, StorageError> {
         let jid = Uuid::new_v4().as_u128();
         let seq = self.next_sequence();
 
@@ -142,20 +264,41 @@ impl StorageBackend for InMemoryStorage {
             amount: command.amount,
             dimensions: command.dimensions.clone(),
             created_at: time::OffsetDateTime::now_utc(),
+            lines: command.ledger_entries.iter().enumerate().map(|(index, e)| {
+                let dimensions = command.leg_dimensions.get(&index).cloned();
+                match e {
+                    LedgerEntryCommand::Debit { account_id, amount, currency_amount, .. } => JournalLine { account_id: account_id.clone(), debit: *amount, credit: Decimal::ZERO, currency_amount: *currency_amount, dimensions },
+                    LedgerEntryCommand::Credit { account_id, amount, currency_amount, .. } => JournalLine { account_id: account_id.clone(), debit: Decimal::ZERO, credit: *amount, currency_amount: *currency_amount, dimensions },
+                }
+            }).collect(),
+            reverses: command.reverses,
+            reversed_by: None,
         };
 
         let mut entities = self.entities.write().unwrap();
         let entity = entities.get_mut(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        ensure_period_open(entity.locked_through, command.date)?;
+
+        for ledger_entry in &command.ledger_entries {
+            let (LedgerEntryCommand::Debit { account_id, .. } | LedgerEntryCommand::Credit { account_id, .. }) = ledger_entry;
+            if entity.account_children.contains_key(account_id) {
+                return Err(StorageError::ParentAccountPosting(account_id.to_string()));
+            }
+        }
 
         entity.journals.insert(jid, entry);
 
-        for ledger_entry in &command.ledger_entries {
+        for (index, ledger_entry) in command.ledger_entries.iter().enumerate() {
+            let dimensions = command.entry_dimensions(index);
             match ledger_entry {
-                LedgerEntryCommand::Debit {account_id, amount, units} => {
+                LedgerEntryCommand::Debit {account_id, amount, units, currency_amount} => {
                     let ledger_account = entity.ledger_accounts.get_mut(account_id)
                         .ok_or_else(|| StorageError::AccountNotFound(account_id.to_string()))?;
-                    ledger_account.add_entry(command.date, jid, *amount, &command.dimensions);
+                    ledger_account.add_entry(command.date, (jid, index), *amount, true, dimensions);
+                    if let (Some(currency_amount), Some(currency_ledger)) = (currency_amount, entity.currency_ledgers.get_mut(account_id)) {
+                        currency_ledger.add_entry(command.date, (jid, index), *currency_amount, true, dimensions);
+                    }
                     if let Some(unit_count) = units {
                         if let Some(lot_store) = entity.lot_stores.get_mut(account_id) {
                             lot_store.add_lot(Lot {
@@ -163,15 +306,18 @@ impl StorageBackend for InMemoryStorage {
                                 units_remaining: *unit_count,
                                 cost_per_unit: if *unit_count != Decimal::ZERO { *amount / *unit_count } else { Decimal::ZERO },
                                 journal_id: jid,
-                                dimensions: command.dimensions.clone(),
+                                dimensions: dimensions.clone(),
                             });
                         }
                     }
                 },
-                LedgerEntryCommand::Credit {account_id, amount, units} => {
+                LedgerEntryCommand::Credit {account_id, amount, units, currency_amount} => {
                     let ledger_account = entity.ledger_accounts.get_mut(account_id)
                         .ok_or_else(|| StorageError::AccountNotFound(account_id.to_string()))?;
-                    ledger_account.add_entry(command.date, jid, -*amount, &command.dimensions);
+                    ledger_account.add_entry(command.date, (jid, index), *amount, false, dimensions);
+                    if let (Some(currency_amount), Some(currency_ledger)) = (currency_amount, entity.currency_ledgers.get_mut(account_id)) {
+                        currency_ledger.add_entry(command.date, (jid, index), *currency_amount, false, dimensions);
+                    }
                     if let Some(unit_count) = units {
                         if let Some(lot_store) = entity.lot_stores.get_mut(account_id) {
                             lot_store.deplete_fifo(*unit_count)
@@ -182,40 +328,65 @@ impl StorageBackend for InMemoryStorage {
             }
         }
 
-        Ok(())
+        if let Some(original) = command.reverses.and_then(|id| entity.journals.get_mut(&id)) {
+            original.reversed_by = Some(jid);
+        }
+
+        Ok(jid)
     }
 
-    fn get_balance(&self, entity_id: &str, account_id: &str, date: Date, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Decimal, StorageError> {
+    fn get_journal(&self, entity_id: &str, journal_id: u128) -> Result<JournalEntry, StorageError> {
         let entities = self.entities.read().unwrap();
         let entity = entities.get(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
-        let acct = entity.ledger_accounts.get(account_id)
-            .ok_or_else(|| StorageError::AccountNotFound(account_id.to_string()))?;
-        Ok(acct.get_balance(date, dimension))
+        entity.journals.get(&journal_id)
+            .cloned()
+            .ok_or_else(|| StorageError::JournalNotFound(Uuid::from_u128(journal_id).to_string()))
     }
 
-    fn get_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<DataValue, StorageError> {
+    fn list_journals(&self, entity_id: &str, from: Date, to: Date, dimensions: &DimensionFilter) -> Result<Vec<JournalEntry>, StorageError> {
         let entities = self.entities.read().unwrap();
         let entity = entities.get(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
-        let acct = entity.ledger_accounts.get(account_id)
-            .ok_or_else(|| StorageError::AccountNotFound(account_id.to_string()))?;
-        let entries = acct.get_statement(from, to, dimension);
+        let mut result: Vec<JournalEntry> = entity.journals.values()
+            .filter(|j| j.date >= from && j.date <= to)
+            .filter(|j| dimensions.matches(&j.dimensions))
+            .cloned()
+            .collect();
+        result.sort_by_key(|j| (j.date, j.sequence));
+        Ok(result)
+    }
 
-        let mut result = Vec::new();
-        for e in entries {
-            if let Some(j) = entity.journals.get(&e.0) {
-                result.push(StatementTxn {
-                    journal_id: e.0,
-                    date: j.date,
-                    description: j.description.clone(),
-                    amount: e.1,
-                    balance: e.2,
-                });
-            }
-        }
+    fn get_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let ledgers = entity.subtree_ledgers(account_id)?;
+        Ok(ledgers.iter().map(|ledger| ledger.get_balance(date, dimensions)).sum())
+    }
 
-        Ok(DataValue::Statement(result))
+    fn get_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let ledgers = entity.subtree_ledgers(account_id)?;
+        Ok(statement_of(entity, &ledgers, from, to, dimensions))
+    }
+
+    fn get_currency_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let ledgers = entity.subtree_currency_ledgers(account_id)?;
+        Ok(ledgers.iter().map(|ledger| ledger.get_balance(date, dimensions)).sum())
+    }
+
+    fn get_currency_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let ledgers = entity.subtree_currency_ledgers(account_id)?;
+        Ok(statement_of(entity, &ledgers, from, to, dimensions))
     }
 
     fn get_dimension_values(&self, entity_id: &str, account_id: &str, dimension_key: Arc<str>, from: Date, to: Date) -> Result<HashSet<Arc<DataValue>>, StorageError> {
@@ -227,6 +398,103 @@ impl StorageBackend for InMemoryStorage {
         Ok(acct.get_dimension_values(dimension_key, from, to))
     }
 
+    fn get_dimension_combinations(&self, entity_id: &str, account_id: &str, dimension_keys: &[Arc<str>], from: Date, to: Date) -> Result<HashSet<Vec<Arc<DataValue>>>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let ledgers = entity.subtree_ledgers(account_id)?;
+        if dimension_keys.is_empty() {
+            return Ok(HashSet::from([Vec::new()]));
+        }
+        let mut result = HashSet::new();
+        for ledger in ledgers {
+            for (_, day) in ledger.days.range(from..=to) {
+                for dimensions in day.entry_dimensions.values() {
+                    let combination: Option<Vec<_>> = dimension_keys.iter()
+                        .map(|key| dimensions.get(key).cloned())
+                        .collect();
+                    if let Some(combination) = combination {
+                        result.insert(combination);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    fn get_dimension_movements(&self, entity_id: &str, account_id: &str, dimension_keys: &[Arc<str>], from: Date, to: Date) -> Result<Vec<DimensionMovement>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let mut totals: HashMap<(Date, Vec<Arc<DataValue>>), Decimal> = HashMap::new();
+        for ledger in entity.subtree_ledgers(account_id)? {
+            for (date, day) in ledger.days.range(..=to) {
+                // Earlier days make up the opening balance on `from`
+                let date = (*date).max(from);
+                if dimension_keys.is_empty() {
+                    *totals.entry((date, Vec::new())).or_default() += day.total;
+                    continue;
+                }
+                for (leg, amount) in &day.entries {
+                    let Some(dimensions) = day.entry_dimensions.get(leg) else { continue };
+                    let combination: Option<Vec<_>> = dimension_keys.iter()
+                        .map(|key| dimensions.get(key).cloned())
+                        .collect();
+                    if let Some(combination) = combination {
+                        *totals.entry((date, combination)).or_default() += amount;
+                    }
+                }
+            }
+        }
+        let mut result: Vec<_> = totals.into_iter().map(|((date, combination), amount)| (date, combination, amount)).collect();
+        result.sort_by_key(|(date, _, _)| *date);
+        Ok(result)
+    }
+
+    fn get_balance_series(&self, entity_id: &str, account_id: &str, dates: &[Date], dimensions: &DimensionFilter) -> Result<Vec<Decimal>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let mut balances = vec![Decimal::ZERO; dates.len()];
+        for ledger in entity.subtree_ledgers(account_id)? {
+            for (total, balance) in balances.iter_mut().zip(ledger.get_balance_series(dates, dimensions)) {
+                *total += balance;
+            }
+        }
+        Ok(balances)
+    }
+
+    fn get_turnover(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Date, dimensions: &DimensionFilter) -> Result<(Decimal, Decimal), StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let mut debits = Decimal::ZERO;
+        let mut credits = Decimal::ZERO;
+        for ledger in entity.subtree_ledgers(account_id)? {
+            let (d, c) = ledger.get_turnover(from, to, dimensions);
+            debits += d;
+            credits += c;
+        }
+        Ok((debits, credits))
+    }
+
+    fn get_balances_by_dimension(&self, entity_id: &str, account_id: &str, dimension_key: &str, from: Bound<Date>, to: Date) -> Result<Vec<(Arc<DataValue>, Decimal)>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let ledgers = entity.subtree_ledgers(account_id)?;
+        let mut totals: HashMap<Arc<DataValue>, Decimal> = HashMap::new();
+        for ledger in ledgers {
+            ledger.add_balances_by_dimension(dimension_key, from, to, &mut totals);
+        }
+        let mut result: Vec<_> = totals.into_iter().collect();
+        result.sort_by_cached_key(|(value, _)| match value.as_ref() {
+            DataValue::String(s) => s.to_string(),
+            other => format!("{:?}", other),
+        });
+        Ok(result)
+    }
+
     fn list_accounts(&self, entity_id: &str) -> Vec<(Arc<str>, AccountType)> {
         let entities = self.entities.read().unwrap();
         match entities.get(entity_id) {
@@ -272,30 +540,69 @@ impl StorageBackend for InMemoryStorage {
         Ok(())
     }
 
-    fn get_lots(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Vec<LotItem>, StorageError> {
+    fn create_fixed_asset(&self, entity_id: &str, asset: &FixedAsset) -> Result<(), StorageError> {
+        let mut entities = self.entities.write().unwrap();
+        let entity = entities.get_mut(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        if entity.fixed_assets.contains_key(&asset.id) {
+            return Err(StorageError::DuplicateFixedAsset(asset.id.to_string()));
+        }
+        entity.fixed_assets.insert(asset.id.clone(), asset.clone());
+        Ok(())
+    }
+
+    fn get_fixed_asset(&self, entity_id: &str, asset_id: &str) -> Result<FixedAsset, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        entity.fixed_assets.get(asset_id)
+            .cloned()
+            .ok_or_else(|| StorageError::FixedAssetNotFound(asset_id.to_string()))
+    }
+
+    fn list_fixed_assets(&self, entity_id: &str) -> Result<Vec<FixedAsset>, StorageError> {
+        let entities = self.entities.read().unwrap();
+        let entity = entities.get(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        Ok(entity.fixed_assets.values().cloned().collect())
+    }
+
+    fn update_fixed_asset(&self, entity_id: &str, asset_id: &str, depreciated_through: Option<Date>, disposed_on: Option<Date>) -> Result<(), StorageError> {
+        let mut entities = self.entities.write().unwrap();
+        let entity = entities.get_mut(entity_id)
+            .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        let asset = entity.fixed_assets.get_mut(asset_id)
+            .ok_or_else(|| StorageError::FixedAssetNotFound(asset_id.to_string()))?;
+        asset.depreciated_through = depreciated_through;
+        asset.disposed_on = disposed_on;
+        Ok(())
+    }
+
+    fn get_lots(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Vec<LotItem>, StorageError> {
         let entities = self.entities.read().unwrap();
         let entity = entities.get(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
         match entity.lot_stores.get(account_id) {
-            Some(store) => Ok(store.open_lots_filtered(dimension)),
+            Some(store) => Ok(store.open_lots_filtered(dimensions)),
             None => Err(StorageError::Other(format!("Account @{} is not a unit account", account_id))),
         }
     }
 
-    fn get_total_units(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Decimal, StorageError> {
+    fn get_total_units(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
         let entities = self.entities.read().unwrap();
         let entity = entities.get(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
         match entity.lot_stores.get(account_id) {
-            Some(store) => Ok(store.total_units_filtered(dimension)),
+            Some(store) => Ok(store.total_units_filtered(dimensions)),
             None => Err(StorageError::Other(format!("Account @{} is not a unit account", account_id))),
         }
     }
 
-    fn deplete_lots(&self, entity_id: &str, account_id: &str, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError> {
+    fn deplete_lots(&self, entity_id: &str, account_id: &str, date: Date, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError> {
         let mut entities = self.entities.write().unwrap();
         let entity = entities.get_mut(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        ensure_period_open(entity.locked_through, date)?;
         let store = entity.lot_stores.get_mut(account_id)
             .ok_or_else(|| StorageError::Other(format!("Account @{} is not a unit account", account_id)))?;
         let cost = match method {
@@ -306,13 +613,14 @@ impl StorageBackend for InMemoryStorage {
         Ok(cost)
     }
 
-    fn split_lots(&self, entity_id: &str, account_id: &str, new_per_old: Decimal, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<(), StorageError> {
+    fn split_lots(&self, entity_id: &str, account_id: &str, date: Date, new_per_old: Decimal, dimensions: &DimensionFilter) -> Result<(), StorageError> {
         let mut entities = self.entities.write().unwrap();
         let entity = entities.get_mut(entity_id)
             .ok_or_else(|| StorageError::EntityNotFound(entity_id.to_string()))?;
+        ensure_period_open(entity.locked_through, date)?;
         let store = entity.lot_stores.get_mut(account_id)
             .ok_or_else(|| StorageError::Other(format!("Account @{} is not a unit account", account_id)))?;
-        store.split_filtered(new_per_old, dimension);
+        store.split_filtered(new_per_old, dimensions);
         Ok(())
     }
 
@@ -322,6 +630,36 @@ impl StorageBackend for InMemoryStorage {
             .and_then(|e| e.unit_rate_links.get(account_id).cloned())
     }
 
+    fn get_parent_account(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>> {
+        let entities = self.entities.read().unwrap();
+        entities.get(entity_id)
+            .and_then(|e| e.account_parents.get(account_id).cloned())
+    }
+
+    fn get_account_type(&self, entity_id: &str, account_id: &str) -> Option<AccountType> {
+        let entities = self.entities.read().unwrap();
+        entities.get(entity_id)
+            .and_then(|e| e.ledger_accounts.get(account_id).map(|ledger| ledger.account_type.clone()))
+    }
+
+    fn get_account_class(&self, entity_id: &str, account_id: &str) -> Option<AccountClass> {
+        let entities = self.entities.read().unwrap();
+        entities.get(entity_id)
+            .and_then(|e| e.account_classes.get(account_id).copied())
+    }
+
+    fn get_cash_flow_category(&self, entity_id: &str, account_id: &str) -> Option<CashFlowCategory> {
+        let entities = self.entities.read().unwrap();
+        entities.get(entity_id)
+            .and_then(|e| e.cash_flow_categories.get(account_id).copied())
+    }
+
+    fn get_account_currency(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>> {
+        let entities = self.entities.read().unwrap();
+        entities.get(entity_id)
+            .and_then(|e| e.account_currencies.get(account_id).cloned())
+    }
+
     fn is_unit_account(&self, entity_id: &str, account_id: &str) -> bool {
         let entities = self.entities.read().unwrap();
         entities.get(entity_id)
@@ -344,51 +682,62 @@ impl LedgerStore {
         }
     }
 
-    pub fn add_entry(&mut self, date: Date, journal_id: u128, amount: Decimal, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) {
-        let amount = match self.account_type {
-            AccountType::Asset | AccountType::Expense => amount,
-            AccountType::Liability | AccountType::Equity | AccountType::Income => -amount,
+    /// Post a leg of `amount` on its debit or credit side.
+    pub fn add_entry(&mut self, date: Date, leg: LegId, amount: Decimal, debit: bool, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) {
+        let turnover = if debit { (amount, Decimal::ZERO) } else { (Decimal::ZERO, amount) };
+        let net = turnover.0 - turnover.1;
+        let balance = match self.account_type {
+            AccountType::Asset | AccountType::Expense | AccountType::Statistical => net,
+            AccountType::Liability | AccountType::Equity | AccountType::Income => -net,
         };
         let day = self.days.entry(date).or_insert(LedgerDay::new());
-        day.add_entry(journal_id, amount, dimensions);
+        day.add_entry(leg, balance, turnover, dimensions);
     }
 
-    pub fn get_balance(&self, date: Date, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Decimal {        
+    pub fn get_balance(&self, date: Date, dimensions: &DimensionFilter) -> Decimal {        
         let mut balance = Decimal::ZERO;
         let days = self.days.range((Bound::Unbounded, Bound::Included(date)));
         for (_, day) in days {
-            match &dimension {
-                Some(dimension) => {
-                    balance += day.get_balance(dimension);
-                },
-                None => {
-                    balance += day.total;
-                }
-            }
+            balance += day.get_balance(dimensions);
         }
         balance
     }
 
-    pub fn get_statement(&self, from: Bound<Date>, to: Bound<Date>, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Vec<(u128, Decimal, Decimal)> {        
-        let mut result = Vec::new();
-        
-        let balance_date = match from {
-            Bound::Included(d) => d.previous_day().unwrap_or(d),
-            Bound::Excluded(d) => d,
-            Bound::Unbounded => Date::MIN,
-        };
+    /// Balances at each of the ascending `dates`, walking the days once.
+    pub fn get_balance_series(&self, dates: &[Date], dimensions: &DimensionFilter) -> Vec<Decimal> {
+        let mut result = Vec::with_capacity(dates.len());
+        let mut days = self.days.iter().peekable();
+        let mut balance = Decimal::ZERO;
+        for date in dates {
+            while let Some((_, day)) = days.next_if(|(day_date, _)| *day_date <= date) {
+                balance += day.get_balance(dimensions);
+            }
+            result.push(balance);
+        }
+        result
+    }
 
-        let mut balance = self.get_balance(balance_date, dimension);
+    /// Debits and credits posted between `from` and `to`.
+    pub fn get_turnover(&self, from: Bound<Date>, to: Date, dimensions: &DimensionFilter) -> (Decimal, Decimal) {
+        if matches!(from, Bound::Included(f) | Bound::Excluded(f) if f > to) {
+            return (Decimal::ZERO, Decimal::ZERO);
+        }
+        let mut debits = Decimal::ZERO;
+        let mut credits = Decimal::ZERO;
+        for (_, day) in self.days.range((from, Bound::Included(to))) {
+            let (d, c) = day.get_turnover(dimensions);
+            debits += d;
+            credits += c;
+        }
+        (debits, credits)
+    }
 
+    pub fn get_entries(&self, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Vec<(LegId, Decimal)> {
+        let mut result = Vec::new();
         let days = self.days.range((from, to));
         for (_, day) in days {
-            let entries = day.get_entries(dimension);
-            for (jid, amt) in entries {
-                balance += amt;
-                result.push((jid, amt, balance));
-            }
+            result.extend(day.get_entries(dimensions));
         }
-
         result
     }
 
@@ -401,39 +750,74 @@ impl LedgerStore {
         }
         result
     }
+
+    pub fn add_balances_by_dimension(&self, dimension_key: &str, from: Bound<Date>, to: Date, totals: &mut HashMap<Arc<DataValue>, Decimal>) {
+        if matches!(from, Bound::Included(f) if f > to) {
+            return;
+        }
+        for (_, day) in self.days.range((from, Bound::Included(to))) {
+            if let Some(values) = day.exact_sum_by_dimension.get(dimension_key) {
+                for (value, amount) in values {
+                    *totals.entry(value.clone()).or_insert(Decimal::ZERO) += amount;
+                }
+            }
+        }
+    }
 }
 
+/// A journal leg: the journal id and the leg's position among its legs.
+type LegId = (u128, usize);
+
 #[derive(Debug, Clone)]
 struct LedgerDay {
     sum_by_dimension: HashMap<Arc<str>, HashMap<Arc<DataValue>, Decimal>>,
+    /// Like `sum_by_dimension` but without the ancestor roll-ups, for per-value breakdowns.
+    exact_sum_by_dimension: HashMap<Arc<str>, HashMap<Arc<DataValue>, Decimal>>,
     total: Decimal,
-    entries: HashMap<u128, Decimal>,
-    entry_by_dimension: HashMap<(Arc<str>, Arc<DataValue>), Vec<u128>>,
+    /// Sums of the debit and credit legs, kept apart for turnover.
+    debits: Decimal,
+    credits: Decimal,
+    /// Entries per leg, as a journal can post several legs to the account on one day.
+    entries: HashMap<LegId, Decimal>,
+    /// Debit and credit of each leg, by the side it was posted on rather than its sign.
+    entry_turnover: HashMap<LegId, (Decimal, Decimal)>,
+    /// Dimensions each entry was posted with, which can differ from its journal's.
+    entry_dimensions: HashMap<LegId, BTreeMap<Arc<str>, Arc<DataValue>>>,
+    entry_by_dimension: HashMap<(Arc<str>, Arc<DataValue>), Vec<LegId>>,
 }
 
 impl LedgerDay {
     pub fn new() -> Self {
         Self {
             sum_by_dimension: HashMap::new(),
+            exact_sum_by_dimension: HashMap::new(),
             total: Decimal::ZERO,
+            debits: Decimal::ZERO,
+            credits: Decimal::ZERO,
             entries: HashMap::new(),
+            entry_turnover: HashMap::new(),
+            entry_dimensions: HashMap::new(),
             entry_by_dimension: HashMap::new(),
         }
     }
 
-    pub fn add_entry(&mut self, journal_id: u128, amount: Decimal, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) {
+    pub fn add_entry(&mut self, leg: LegId, amount: Decimal, turnover: (Decimal, Decimal), dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) {
         
-        self.entries.insert(journal_id, amount);
+        self.entries.insert(leg, amount);
+        self.entry_turnover.insert(leg, turnover);
+        self.debits += turnover.0;
+        self.credits += turnover.1;
+        self.entry_dimensions.insert(leg, dimensions.clone());
         for (k, v) in dimensions {
             let e = self.entry_by_dimension.entry((k.clone(), v.clone())).or_default();
-            e.push(journal_id);
+            e.push(leg);
             // Also index at ancestor prefixes for hierarchical matching
             if let DataValue::String(s) = v.as_ref() {
                 for prefix in ancestor_prefixes(s) {
                     let ancestor_val: Arc<DataValue> = Arc::new(DataValue::String(Arc::from(prefix.as_str())));
                     let e = self.entry_by_dimension.entry((k.clone(), ancestor_val)).or_default();
-                    if !e.contains(&journal_id) {
-                        e.push(journal_id);
+                    if !e.contains(&leg) {
+                        e.push(leg);
                     }
                 }
             }
@@ -446,6 +830,12 @@ impl LedgerDay {
     fn increment_balance(&mut self, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>, amount: Decimal) {
         self.total += amount;
         for (dimension, value) in dimensions {
+            *self.exact_sum_by_dimension
+                .entry(dimension.clone())
+                .or_default()
+                .entry(value.clone())
+                .or_insert(Decimal::ZERO) += amount;
+
             let dim_map = self.sum_by_dimension
                 .entry(dimension.clone())
                 .or_default();
@@ -463,12 +853,52 @@ impl LedgerDay {
         }
     }
 
-    pub fn get_balance(&self, dimension: &(Arc<str>, Arc<DataValue>)) -> Decimal {
-        *self.sum_by_dimension
-            .get(&dimension.0)
-            .unwrap_or(&HashMap::new())
-            .get(&dimension.1)
-            .unwrap_or(&Decimal::ZERO)
+    pub fn get_balance(&self, dimensions: &DimensionFilter) -> Decimal {
+        match dimensions.len() {
+            0 => self.total,
+            1 => dimensions.iter()
+                .filter_map(|(key, value)| self.sum_by_dimension.get(key)?.get(value))
+                .sum(),
+            _ => self.matching_entries(dimensions)
+                .iter()
+                .filter_map(|leg| self.entries.get(leg))
+                .sum(),
+        }
+    }
+
+    pub fn get_turnover(&self, dimensions: &DimensionFilter) -> (Decimal, Decimal) {
+        if dimensions.is_empty() {
+            return (self.debits, self.credits);
+        }
+        let mut debits = Decimal::ZERO;
+        let mut credits = Decimal::ZERO;
+        for leg in self.matching_entries(dimensions) {
+            if let Some((debit, credit)) = self.entry_turnover.get(&leg) {
+                debits += debit;
+                credits += credit;
+            }
+        }
+        (debits, credits)
+    }
+
+    /// Legs tagged with every dimension in the filter, intersecting the per-dimension
+    /// indexes starting from the smallest.
+    fn matching_entries(&self, dimensions: &DimensionFilter) -> Vec<LegId> {
+        let mut indexes = Vec::with_capacity(dimensions.len());
+        for dimension in dimensions.iter() {
+            match self.entry_by_dimension.get(dimension) {
+                Some(legs) => indexes.push(legs),
+                None => return Vec::new(),
+            }
+        }
+        indexes.sort_by_key(|legs| legs.len());
+        match indexes.split_first() {
+            Some((smallest, rest)) => smallest.iter()
+                .filter(|leg| rest.iter().all(|legs| legs.contains(leg)))
+                .copied()
+                .collect(),
+            None => Vec::new(),
+        }
     }
 
     pub fn get_dimension_values(&self, dimension: Arc<str>) -> HashSet<Arc<DataValue>> {
@@ -478,22 +908,17 @@ impl LedgerDay {
         }
     }
 
-    pub fn get_entries(&self, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Vec<(u128, Decimal)> {
+    pub fn get_entries(&self, dimensions: &DimensionFilter) -> Vec<(LegId, Decimal)> {
         let mut result = Vec::new();
 
-        match dimension {
-            Some(dimension) => {
-                if let Some(jids) = self.entry_by_dimension.get(dimension) {
-                    for jid in jids {
-                        if let Some(amt) = self.entries.get(jid) { result.push((*jid, *amt)) }
-                    }
-                };
-            },
-            None => {
-                for (jid, amt) in self.entries.iter() {
-                    result.push((*jid, *amt));
-                }
-            },
+        if dimensions.is_empty() {
+            for (leg, amt) in self.entries.iter() {
+                result.push((*leg, *amt));
+            }
+        } else {
+            for leg in self.matching_entries(dimensions) {
+                if let Some(amt) = self.entries.get(&leg) { result.push((leg, *amt)) }
+            }
         }
         
         result
@@ -504,17 +929,43 @@ impl LedgerDay {
 #[derive(Clone)]
 struct RateStore {
     values: BTreeMap<Date, Decimal>,
+    /// Values set `FOR` a single dimension value.
+    values_by_dimension: HashMap<(Arc<str>, Arc<DataValue>), BTreeMap<Date, Decimal>>,
 }
 
 impl RateStore {
     pub fn new() -> Self {
         Self {
             values: BTreeMap::new(),
+            values_by_dimension: HashMap::new(),
         }
     }
 
-    pub fn add_rate(&mut self, date: Date, value: Decimal) {
-        self.values.insert(date, value);
+    pub fn add_rate(&mut self, date: Date, value: Decimal, dimension: Option<(Arc<str>, Arc<DataValue>)>) {
+        match dimension {
+            Some(dimension) => self.values_by_dimension.entry(dimension).or_default().insert(date, value),
+            None => self.values.insert(date, value),
+        };
+    }
+
+    pub fn get_changes(&self, from: Date, to: Date) -> Vec<RateChange> {
+        let mut changes = Vec::new();
+        let series = std::iter::once((None, &self.values))
+            .chain(self.values_by_dimension.iter().map(|(dimension, values)| (Some(dimension), values)));
+        for (dimension, values) in series {
+            if let Some((_, rate)) = values.range(..=from).next_back() {
+                changes.push(RateChange { date: from, rate: *rate, dimension: dimension.cloned() });
+            }
+            if from < to {
+                changes.extend(values.range((Bound::Excluded(from), Bound::Included(to))).map(|(date, rate)| RateChange {
+                    date: *date,
+                    rate: *rate,
+                    dimension: dimension.cloned(),
+                }));
+            }
+        }
+        changes.sort_by_key(|change| change.date);
+        changes
     }
 
     pub fn get_rate(&self, date: Date) -> Result<Decimal, StorageError> {
@@ -528,6 +979,37 @@ impl RateStore {
 
 /// Returns all proper ancestor prefixes of a `/`-separated path.
 /// e.g., "Americas/US/West" → ["Americas", "Americas/US"]
+/// Running statement over `ledgers`, ordered by journal date, sequence and leg.
+fn statement_of(entity: &EntityData, ledgers: &[&LedgerStore], from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> DataValue {
+    let balance_date = match from {
+        Bound::Included(d) => d.previous_day().unwrap_or(d),
+        Bound::Excluded(d) => d,
+        Bound::Unbounded => Date::MIN,
+    };
+    let mut balance: Decimal = ledgers.iter().map(|ledger| ledger.get_balance(balance_date, dimensions)).sum();
+
+    let mut entries: Vec<(LegId, Decimal)> = ledgers.iter()
+        .flat_map(|ledger| ledger.get_entries(from, to, dimensions))
+        .collect();
+    entries.sort_by_key(|((jid, index), _)| entity.journals.get(jid).map(|j| (j.date, j.sequence, *index)));
+
+    let mut result = Vec::new();
+    for ((jid, _), amount) in entries {
+        balance += amount;
+        if let Some(j) = entity.journals.get(&jid) {
+            result.push(StatementTxn {
+                journal_id: jid,
+                date: j.date,
+                description: j.description.clone(),
+                amount,
+                balance,
+            });
+        }
+    }
+
+    DataValue::Statement(result)
+}
+
 fn ancestor_prefixes(path: &str) -> Vec<String> {
     let mut prefixes = Vec::new();
     let mut pos = 0;
@@ -541,28 +1023,6 @@ fn ancestor_prefixes(path: &str) -> Vec<String> {
     prefixes
 }
 
-/// Check if a lot matches a dimension filter with hierarchical prefix matching.
-/// A dimension value "Americas/US" matches "Americas", "Americas/US", but not "Americas/EU".
-fn dimension_matches(lot_dims: &BTreeMap<Arc<str>, Arc<DataValue>>, filter: &(Arc<str>, Arc<DataValue>)) -> bool {
-    let (key, filter_val) = filter;
-    match lot_dims.get(key.as_ref()) {
-        Some(lot_val) => {
-            if lot_val == filter_val {
-                return true;
-            }
-            // Hierarchical prefix match: filter "Americas" matches lot "Americas/US/West"
-            if let (DataValue::String(filter_s), DataValue::String(lot_s)) = (filter_val.as_ref(), lot_val.as_ref()) {
-                let prefix = filter_s.as_ref();
-                let value = lot_s.as_ref();
-                value.starts_with(prefix) && value.as_bytes().get(prefix.len()) == Some(&b'/')
-            } else {
-                false
-            }
-        }
-        None => false,
-    }
-}
-
 /// Check if a lot matches a full set of dimensions for exact pool matching.
 /// Empty dimensions map matches all lots (backward compatible).
 fn dimensions_match_exact(lot_dims: &BTreeMap<Arc<str>, Arc<DataValue>>, filter_dims: &BTreeMap<Arc<str>, Arc<DataValue>>) -> bool {
@@ -609,19 +1069,16 @@ impl LotStoreData {
         self.lots.iter().map(|l| l.units_remaining).sum()
     }
 
-    fn total_units_filtered(&self, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Decimal {
-        match dimension {
-            Some(filter) => self.lots.iter()
-                .filter(|l| dimension_matches(&l.dimensions, filter))
-                .map(|l| l.units_remaining)
-                .sum(),
-            None => self.total_units(),
-        }
+    fn total_units_filtered(&self, dimensions: &DimensionFilter) -> Decimal {
+        self.lots.iter()
+            .filter(|l| dimensions.matches(&l.dimensions))
+            .map(|l| l.units_remaining)
+            .sum()
     }
 
-    fn open_lots(&self) -> Vec<LotItem> {
+    fn open_lots_filtered(&self, dimensions: &DimensionFilter) -> Vec<LotItem> {
         self.lots.iter()
-            .filter(|l| l.units_remaining > Decimal::ZERO)
+            .filter(|l| l.units_remaining > Decimal::ZERO && dimensions.matches(&l.dimensions))
             .map(|l| LotItem {
                 date: l.date,
                 units: l.units_remaining,
@@ -632,22 +1089,6 @@ impl LotStoreData {
             .collect()
     }
 
-    fn open_lots_filtered(&self, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Vec<LotItem> {
-        match dimension {
-            Some(filter) => self.lots.iter()
-                .filter(|l| l.units_remaining > Decimal::ZERO && dimension_matches(&l.dimensions, filter))
-                .map(|l| LotItem {
-                    date: l.date,
-                    units: l.units_remaining,
-                    cost_per_unit: l.cost_per_unit,
-                    total_cost: l.units_remaining * l.cost_per_unit,
-                    dimensions: l.dimensions.clone(),
-                })
-                .collect(),
-            None => self.open_lots(),
-        }
-    }
-
     fn deplete_fifo(&mut self, mut units: Decimal) -> Result<Decimal, String> {
         let available = self.total_units();
         if units > available {
@@ -777,13 +1218,9 @@ impl LotStoreData {
         Ok(total_cost)
     }
 
-    fn split_filtered(&mut self, new_per_old: Decimal, dimension: Option<&(Arc<str>, Arc<DataValue>)>) {
+    fn split_filtered(&mut self, new_per_old: Decimal, dimensions: &DimensionFilter) {
         for lot in &mut self.lots {
-            let matches = match dimension {
-                Some(filter) => dimension_matches(&lot.dimensions, filter),
-                None => true,
-            };
-            if matches {
+            if dimensions.matches(&lot.dimensions) {
                 lot.units_remaining *= new_per_old;
                 if new_per_old > Decimal::ZERO {
                     lot.cost_per_unit /= new_per_old;
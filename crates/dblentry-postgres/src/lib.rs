@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::Bound,
     str::FromStr,
     sync::{
@@ -14,11 +14,12 @@ use time::{Date, Month, OffsetDateTime};
 use uuid::Uuid;
 
 use dblentry_core::{
-    AccountExpression, AccountType, CostMethod, LotItem,
+    AccountClass, AccountExpression, CashFlowCategory, AccountType, CostMethod, LotItem, FixedAsset, DepreciationMethod,
     CreateJournalCommand, CreateRateCommand, LedgerEntryCommand, SetRateCommand,
-    DataValue, StatementTxn,
-    StorageBackend, StorageError, TransactionId,
+    DataValue, DimensionFilter, JournalEntry, JournalLine, RateChange, StatementTxn,
+    DimensionMovement, StorageBackend, StorageError, TransactionId,
 };
+use dblentry_core::storage::ensure_period_open;
 
 pub struct PostgresStorage {
     client: Mutex<Client>,
@@ -49,6 +50,35 @@ impl PostgresStorage {
                 id TEXT PRIMARY KEY
             );
 
+            CREATE TABLE IF NOT EXISTS period_locks (
+                entity_id TEXT PRIMARY KEY,
+                locked_through TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS year_closes (
+                entity_id TEXT NOT NULL,
+                year_end TEXT NOT NULL,
+                journal_ids TEXT NOT NULL,
+                PRIMARY KEY (entity_id, year_end)
+            );
+
+            CREATE TABLE IF NOT EXISTS fixed_assets (
+                entity_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                cost TEXT NOT NULL,
+                in_service TEXT NOT NULL,
+                life_years INTEGER NOT NULL,
+                salvage TEXT NOT NULL,
+                method TEXT NOT NULL,
+                factor TEXT,
+                asset_account TEXT NOT NULL,
+                accumulated_account TEXT NOT NULL,
+                expense_account TEXT NOT NULL,
+                depreciated_through TEXT,
+                disposed_on TEXT,
+                PRIMARY KEY (entity_id, id)
+            );
+
             INSERT INTO entities (id) VALUES ('default')
                 ON CONFLICT (id) DO NOTHING;
 
@@ -57,6 +87,10 @@ impl PostgresStorage {
                 account_type TEXT NOT NULL,
                 unit_rate_id TEXT,
                 entity_id TEXT NOT NULL DEFAULT 'default',
+                parent_id TEXT,
+                account_class TEXT,
+                cash_flow TEXT,
+                currency TEXT,
                 PRIMARY KEY (entity_id, id)
             );
 
@@ -65,7 +99,9 @@ impl PostgresStorage {
                 date TEXT NOT NULL,
                 value TEXT NOT NULL,
                 entity_id TEXT NOT NULL DEFAULT 'default',
-                PRIMARY KEY (entity_id, id, date)
+                dimension_key TEXT NOT NULL DEFAULT '',
+                dimension_value TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (entity_id, id, dimension_key, dimension_value, date)
             );
 
             CREATE TABLE IF NOT EXISTS journals (
@@ -76,10 +112,37 @@ impl PostgresStorage {
                 amount TEXT NOT NULL,
                 created_at TEXT NOT NULL,
                 entity_id TEXT NOT NULL DEFAULT 'default',
+                reverses_journal_id TEXT,
                 PRIMARY KEY (entity_id, id),
                 UNIQUE (id)
             );
 
+            -- Databases created before journal reversals lack the link column
+            ALTER TABLE journals ADD COLUMN IF NOT EXISTS reverses_journal_id TEXT;
+
+            -- Databases created before account hierarchies lack the parent column
+            ALTER TABLE accounts ADD COLUMN IF NOT EXISTS parent_id TEXT;
+
+            -- Databases created before balance sheet classification lack the class column
+            ALTER TABLE accounts ADD COLUMN IF NOT EXISTS account_class TEXT;
+
+            -- Databases created before cash flow statements lack the category column
+            ALTER TABLE accounts ADD COLUMN IF NOT EXISTS cash_flow TEXT;
+
+            -- Databases created before per-dimension rates key rates without the dimension
+            ALTER TABLE rates ADD COLUMN IF NOT EXISTS dimension_key TEXT NOT NULL DEFAULT '';
+            ALTER TABLE rates ADD COLUMN IF NOT EXISTS dimension_value TEXT NOT NULL DEFAULT '';
+            DO $$
+            BEGIN
+                IF NOT EXISTS (
+                    SELECT 1 FROM information_schema.key_column_usage
+                    WHERE table_name = 'rates' AND constraint_name = 'rates_pkey' AND column_name = 'dimension_key'
+                ) THEN
+                    ALTER TABLE rates DROP CONSTRAINT IF EXISTS rates_pkey;
+                    ALTER TABLE rates ADD PRIMARY KEY (entity_id, id, dimension_key, dimension_value, date);
+                END IF;
+            END $$;
+
             CREATE TABLE IF NOT EXISTS journal_dimensions (
                 journal_id TEXT NOT NULL REFERENCES journals(id),
                 dimension_key TEXT NOT NULL,
@@ -92,9 +155,18 @@ impl PostgresStorage {
                 account_id TEXT NOT NULL,
                 date TEXT NOT NULL,
                 amount TEXT NOT NULL,
-                entity_id TEXT NOT NULL DEFAULT 'default'
+                entity_id TEXT NOT NULL DEFAULT 'default',
+                currency_amount TEXT,
+                side TEXT
             );
 
+            -- Databases created before currency accounts lack the currency columns
+            ALTER TABLE accounts ADD COLUMN IF NOT EXISTS currency TEXT;
+            ALTER TABLE ledger_entries ADD COLUMN IF NOT EXISTS currency_amount TEXT;
+
+            -- Databases created before legs recorded their side leave it to be inferred from the sign
+            ALTER TABLE ledger_entries ADD COLUMN IF NOT EXISTS side TEXT;
+
             CREATE TABLE IF NOT EXISTS ledger_entry_dimensions (
                 ledger_entry_id BIGINT NOT NULL REFERENCES ledger_entries(id),
                 dimension_key TEXT NOT NULL,
@@ -158,12 +230,322 @@ impl PostgresStorage {
         let seq: i64 = row.get(0);
         Ok(seq as u64)
     }
+
+    fn period_lock(client: &mut Client, entity_id: &str) -> Result<Option<Date>, StorageError> {
+        let row = client
+            .query_opt(
+                "SELECT locked_through FROM period_locks WHERE entity_id = $1",
+                &[&entity_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(row.map(|r| str_to_date(&r.get::<_, String>(0))))
+    }
+
+    /// Load the journals `selection` picks, with their dimensions and legs, in date and
+    /// sequence order. `selection` is a query returning journal ids over `values`, with the
+    /// entity as `$1`; each part of the journals is loaded in one query for all of them.
+    fn read_journals(client: &mut Client, selection: &str, values: &[String]) -> Result<Vec<JournalEntry>, StorageError> {
+        let params = sql_params(values);
+
+        let mut dimensions: HashMap<String, BTreeMap<Arc<str>, Arc<DataValue>>> = HashMap::new();
+        let dim_rows = client
+            .query(
+                &format!("SELECT journal_id, dimension_key, dimension_value FROM journal_dimensions WHERE journal_id IN ({selection})"),
+                &params,
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        for dim_row in dim_rows {
+            let k: String = dim_row.get(1);
+            let v: String = dim_row.get(2);
+            dimensions.entry(dim_row.get(0)).or_default()
+                .insert(Arc::from(k.as_str()), Arc::new(DataValue::String(Arc::from(v.as_str()))));
+        }
+
+        let mut leg_dimensions: HashMap<i64, BTreeMap<Arc<str>, Arc<DataValue>>> = HashMap::new();
+        let dim_rows = client
+            .query(
+                &format!(
+                    "SELECT led.ledger_entry_id, led.dimension_key, led.dimension_value
+                     FROM ledger_entry_dimensions led
+                     JOIN ledger_entries le ON le.id = led.ledger_entry_id
+                     WHERE le.entity_id = $1 AND le.journal_id IN ({selection})"
+                ),
+                &params,
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        for dim_row in dim_rows {
+            let k: String = dim_row.get(1);
+            let v: String = dim_row.get(2);
+            leg_dimensions.entry(dim_row.get(0)).or_default()
+                .insert(Arc::from(k.as_str()), Arc::new(DataValue::String(Arc::from(v.as_str()))));
+        }
+
+        // Ledger amounts are stored signed by account type; undo that to recover the legs
+        let mut lines: HashMap<String, Vec<JournalLine>> = HashMap::new();
+        let leg_rows = client
+            .query(
+                &format!(
+                    "SELECT le.journal_id, le.account_id, a.account_type, le.amount, le.currency_amount, le.id, le.side
+                     FROM ledger_entries le
+                     JOIN accounts a ON a.entity_id = le.entity_id AND a.id = le.account_id
+                     WHERE le.entity_id = $1 AND le.journal_id IN ({selection})
+                     ORDER BY le.id"
+                ),
+                &params,
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        for leg_row in leg_rows {
+            let jid: String = leg_row.get(0);
+            let account_id: String = leg_row.get(1);
+            let acct_type_str: String = leg_row.get(2);
+            let amt_str: String = leg_row.get(3);
+            let currency_amt_str: Option<String> = leg_row.get(4);
+            let side: Option<String> = leg_row.get(6);
+            let signed = Decimal::from_str(&amt_str)
+                .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?;
+            let sign = match str_to_account_type(&acct_type_str) {
+                AccountType::Asset | AccountType::Expense | AccountType::Statistical => Decimal::ONE,
+                AccountType::Liability | AccountType::Equity | AccountType::Income => Decimal::NEGATIVE_ONE,
+            };
+            let raw = signed * sign;
+            let debit = match side.as_deref() {
+                Some("DEBIT") => true,
+                Some("CREDIT") => false,
+                // Entries from before the side was recorded sit on the side of their sign
+                _ => raw >= Decimal::ZERO,
+            };
+            // The transaction amount is signed like the functional one and read on the same side
+            let currency_amount = match currency_amt_str {
+                Some(a) => {
+                    let raw_currency = Decimal::from_str(&a)
+                        .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))? * sign;
+                    Some(if debit { raw_currency } else { -raw_currency })
+                }
+                None => None,
+            };
+            let leg_dimensions = leg_dimensions.remove(&leg_row.get::<_, i64>(5)).unwrap_or_default();
+            let journal_dimensions = dimensions.get(&jid).cloned().unwrap_or_default();
+            lines.entry(jid).or_default().push(JournalLine {
+                account_id: Arc::from(account_id.as_str()),
+                debit: if debit { raw } else { Decimal::ZERO },
+                credit: if debit { Decimal::ZERO } else { -raw },
+                currency_amount,
+                dimensions: (leg_dimensions != journal_dimensions).then_some(leg_dimensions),
+            });
+        }
+
+        let reversed_by: HashMap<String, String> = client
+            .query(
+                &format!("SELECT reverses_journal_id, id FROM journals WHERE entity_id = $1 AND reverses_journal_id IN ({selection})"),
+                &params,
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .map(|r| (r.get(0), r.get(1)))
+            .collect();
+
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT id, sequence, date, description, amount, created_at, reverses_journal_id FROM journals
+                     WHERE entity_id = $1 AND id IN ({selection})
+                     ORDER BY date, sequence"
+                ),
+                &params,
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let parse_id = |id: &str| Uuid::parse_str(id).ok().map(|u| u.as_u128());
+        let mut journals = Vec::with_capacity(rows.len());
+        for row in rows {
+            let jid: String = row.get(0);
+            let sequence: i64 = row.get(1);
+            let date_str: String = row.get(2);
+            let description: String = row.get(3);
+            let amount_str: String = row.get(4);
+            let created_at: String = row.get(5);
+            let reverses: Option<String> = row.get(6);
+            journals.push(JournalEntry {
+                id: parse_id(&jid).ok_or_else(|| StorageError::DatabaseError(format!("Invalid journal id: {}", jid)))?,
+                sequence: sequence as u64,
+                date: str_to_date(&date_str),
+                description: Arc::from(description.as_str()),
+                amount: Decimal::from_str(&amount_str)
+                    .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?,
+                dimensions: dimensions.remove(&jid).unwrap_or_default(),
+                created_at: str_to_timestamp(&created_at),
+                lines: lines.remove(&jid).unwrap_or_default(),
+                reverses: reverses.as_deref().and_then(parse_id),
+                reversed_by: reversed_by.get(&jid).and_then(|r| parse_id(r)),
+            });
+        }
+        Ok(journals)
+    }
+
+    /// Balance of the account and its descendants, summing `column` of their ledger entries.
+    fn ledger_balance(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        date: Date,
+        dimensions: &DimensionFilter,
+        column: &str,
+    ) -> Result<Decimal, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        // Verify account exists
+        let exists = client
+            .query_one(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
+                &[&entity_id, &account_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let acct_exists: bool = exists.get(0);
+        if !acct_exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let date_str = date_to_str(date);
+
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_str];
+        values.extend(dim_values);
+        let row = client
+            .query_one(
+                &format!(
+                    "{ACCOUNT_SUBTREE}
+                     SELECT COALESCE(SUM(le.{column}::NUMERIC), 0)::TEXT
+                     FROM ledger_entries le{joins}
+                     WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= $3"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let total_str: String = row.get(0);
+
+        Decimal::from_str(&total_str)
+            .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))
+    }
+
+    /// Running statement of the account and its descendants over `column` of their ledger entries.
+    fn ledger_statement(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        from: Bound<Date>,
+        to: Bound<Date>,
+        dimensions: &DimensionFilter,
+        column: &str,
+    ) -> Result<DataValue, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        // Verify account exists
+        let exists = client
+            .query_one(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
+                &[&entity_id, &account_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let acct_exists: bool = exists.get(0);
+        if !acct_exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let balance_date = match from {
+            Bound::Included(d) => d.previous_day().unwrap_or(d),
+            Bound::Excluded(d) => d,
+            Bound::Unbounded => Date::MIN,
+        };
+
+        let (from_op, from_str) = match from {
+            Bound::Included(d) => (">=", date_to_str(d)),
+            Bound::Excluded(d) => (">", date_to_str(d)),
+            Bound::Unbounded => (">=", "0000-01-01".to_string()),
+        };
+        let (to_op, to_str) = match to {
+            Bound::Included(d) => ("<=", date_to_str(d)),
+            Bound::Excluded(d) => ("<", date_to_str(d)),
+            Bound::Unbounded => ("<=", "9999-12-31".to_string()),
+        };
+
+        // Opening balance
+        let balance_date_str = date_to_str(balance_date);
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), balance_date_str];
+        values.extend(dim_values);
+        let row = client
+            .query_one(
+                &format!(
+                    "{ACCOUNT_SUBTREE}
+                     SELECT COALESCE(SUM(le.{column}::NUMERIC), 0)::TEXT
+                     FROM ledger_entries le{joins}
+                     WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= $3"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let opening_str: String = row.get(0);
+        let mut opening_balance =
+            Decimal::from_str(&opening_str).unwrap_or(Decimal::ZERO);
+
+        // Fetch entries in range
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 5);
+        let query = format!(
+            "{ACCOUNT_SUBTREE}
+             SELECT le.journal_id, le.date, j.description, le.{column}
+             FROM ledger_entries le
+             JOIN journals j ON j.id = le.journal_id{joins}
+             WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date {} $3 AND le.date {} $4 AND le.{column} IS NOT NULL
+             ORDER BY le.date, le.id",
+            from_op, to_op
+        );
+
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), from_str, to_str];
+        values.extend(dim_values);
+        let rows = client
+            .query(&query, &sql_params(&values))
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let jid_str: String = row.get(0);
+            let date_str_row: String = row.get(1);
+            let desc: String = row.get(2);
+            let amt_str: String = row.get(3);
+
+            let amount = Decimal::from_str(&amt_str).unwrap_or(Decimal::ZERO);
+            opening_balance += amount;
+            let journal_id = Uuid::parse_str(&jid_str)
+                .map(|u| u.as_u128())
+                .unwrap_or(0);
+            result.push(StatementTxn {
+                journal_id,
+                date: str_to_date(&date_str_row),
+                description: Arc::from(desc.as_str()),
+                amount,
+                balance: opening_balance,
+            });
+        }
+
+        Ok(DataValue::Statement(result))
+    }
 }
 
 fn date_to_str(d: Date) -> String {
     format!("{:04}-{:02}-{:02}", d.year(), d.month() as u8, d.day())
 }
 
+/// Parse a comma-separated list of journal UUIDs.
+fn parse_journal_ids(s: &str) -> Result<Vec<u128>, StorageError> {
+    s.split(',')
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            Uuid::parse_str(id)
+                .map(|u| u.as_u128())
+                .map_err(|e| StorageError::DatabaseError(format!("Invalid journal id: {}", e)))
+        })
+        .collect()
+}
+
 fn str_to_date(s: &str) -> Date {
     let parts: Vec<&str> = s.split('-').collect();
     let year = parts[0].parse::<i32>().unwrap();
@@ -172,6 +554,33 @@ fn str_to_date(s: &str) -> Date {
     Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
 }
 
+/// Parse a `created_at` value, which is stored in `OffsetDateTime`'s display
+/// format (e.g. `2024-01-31 9:05:00.123 +00:00:00`).
+fn str_to_timestamp(s: &str) -> OffsetDateTime {
+    fn parse(s: &str) -> Option<OffsetDateTime> {
+        let mut parts = s.split(' ');
+        let date = str_to_date(parts.next()?);
+        let mut clock = parts.next()?.split([':', '.']);
+        let hour = clock.next()?.parse().ok()?;
+        let minute = clock.next()?.parse().ok()?;
+        let second = clock.next()?.parse().ok()?;
+        let fraction = clock.next().unwrap_or("0");
+        let nanos = format!("{:0<9}", fraction).get(..9)?.parse().ok()?;
+        let time = time::Time::from_hms_nano(hour, minute, second, nanos).ok()?;
+        let offset = parts.next()?;
+        let sign: i8 = if offset.starts_with('-') { -1 } else { 1 };
+        let mut fields = offset.get(1..)?.split(':').map(|f| f.parse::<i8>().ok());
+        let offset = time::UtcOffset::from_hms(
+            sign * fields.next()??,
+            sign * fields.next()??,
+            sign * fields.next()??,
+        )
+        .ok()?;
+        Some(date.with_time(time).assume_offset(offset))
+    }
+    parse(s).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
 fn account_type_to_str(at: &AccountType) -> &'static str {
     match at {
         AccountType::Asset => "ASSET",
@@ -179,6 +588,99 @@ fn account_type_to_str(at: &AccountType) -> &'static str {
         AccountType::Equity => "EQUITY",
         AccountType::Income => "INCOME",
         AccountType::Expense => "EXPENSE",
+        AccountType::Statistical => "STATISTICAL",
+    }
+}
+
+fn account_class_to_str(class: AccountClass) -> &'static str {
+    match class {
+        AccountClass::Current => "CURRENT",
+        AccountClass::NonCurrent => "NON_CURRENT",
+    }
+}
+
+fn str_to_account_class(s: &str) -> Option<AccountClass> {
+    match s {
+        "CURRENT" => Some(AccountClass::Current),
+        "NON_CURRENT" => Some(AccountClass::NonCurrent),
+        _ => None,
+    }
+}
+
+fn cash_flow_category_to_str(category: CashFlowCategory) -> &'static str {
+    match category {
+        CashFlowCategory::Cash => "CASH",
+        CashFlowCategory::Operating => "OPERATING",
+        CashFlowCategory::Investing => "INVESTING",
+        CashFlowCategory::Financing => "FINANCING",
+    }
+}
+
+fn depreciation_method_to_str(method: &DepreciationMethod) -> (&'static str, Option<String>) {
+    match method {
+        DepreciationMethod::StraightLine => ("STRAIGHT_LINE", None),
+        DepreciationMethod::DecliningBalance(factor) => ("DECLINING_BALANCE", Some(factor.to_string())),
+        DepreciationMethod::SumOfYears => ("SUM_OF_YEARS", None),
+    }
+}
+
+fn str_to_depreciation_method(method: &str, factor: Option<&str>) -> Result<DepreciationMethod, StorageError> {
+    match (method, factor) {
+        ("STRAIGHT_LINE", _) => Ok(DepreciationMethod::StraightLine),
+        ("DECLINING_BALANCE", Some(factor)) => Decimal::from_str(factor)
+            .map(DepreciationMethod::DecliningBalance)
+            .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e))),
+        ("SUM_OF_YEARS", _) => Ok(DepreciationMethod::SumOfYears),
+        _ => Err(StorageError::DatabaseError(format!("Invalid depreciation method: {}", method))),
+    }
+}
+
+/// Fixed assets of the entity matching `filter` (a condition on `id`, or empty), ordered by id.
+fn query_fixed_assets(client: &mut Client, entity_id: &str, filter: &str, values: &[String]) -> Result<Vec<FixedAsset>, StorageError> {
+    let mut all_values = vec![entity_id.to_string()];
+    all_values.extend_from_slice(values);
+    let rows = client
+        .query(
+            &format!(
+                "SELECT id, cost, in_service, life_years, salvage, method, factor, asset_account, accumulated_account,
+                        expense_account, depreciated_through, disposed_on
+                 FROM fixed_assets WHERE entity_id = $1{filter} ORDER BY id"
+            ),
+            &sql_params(&all_values),
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+    let decimal = |s: String| Decimal::from_str(&s).map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)));
+    rows.iter()
+        .map(|row| {
+            let method: String = row.get(5);
+            let factor: Option<String> = row.get(6);
+            let depreciated_through: Option<String> = row.get(10);
+            let disposed_on: Option<String> = row.get(11);
+            Ok(FixedAsset {
+                id: Arc::from(row.get::<_, String>(0).as_str()),
+                cost: decimal(row.get(1))?,
+                in_service: str_to_date(&row.get::<_, String>(2)),
+                life_years: row.get::<_, i32>(3) as u32,
+                salvage: decimal(row.get(4))?,
+                method: str_to_depreciation_method(&method, factor.as_deref())?,
+                asset_account: Arc::from(row.get::<_, String>(7).as_str()),
+                accumulated_account: Arc::from(row.get::<_, String>(8).as_str()),
+                expense_account: Arc::from(row.get::<_, String>(9).as_str()),
+                depreciated_through: depreciated_through.as_deref().map(str_to_date),
+                disposed_on: disposed_on.as_deref().map(str_to_date),
+            })
+        })
+        .collect()
+}
+
+fn str_to_cash_flow_category(s: &str) -> Option<CashFlowCategory> {
+    match s {
+        "CASH" => Some(CashFlowCategory::Cash),
+        "OPERATING" => Some(CashFlowCategory::Operating),
+        "INVESTING" => Some(CashFlowCategory::Investing),
+        "FINANCING" => Some(CashFlowCategory::Financing),
+        _ => None,
     }
 }
 
@@ -189,10 +691,33 @@ fn str_to_account_type(s: &str) -> AccountType {
         "EQUITY" => AccountType::Equity,
         "INCOME" => AccountType::Income,
         "EXPENSE" => AccountType::Expense,
+        "STATISTICAL" => AccountType::Statistical,
         _ => AccountType::Asset,
     }
 }
 
+/// Recursive CTE `subtree` holding the account bound to $2 and its descendants, so
+/// that balances of parent accounts roll up their children.
+const ACCOUNT_SUBTREE: &str = "WITH RECURSIVE subtree(id) AS (
+    SELECT $2::TEXT
+    UNION ALL
+    SELECT a.id FROM accounts a JOIN subtree s ON a.parent_id = s.id WHERE a.entity_id = $1
+)";
+
+/// Fold per-day movements (ascending) into the balance at each of the ascending `dates`.
+fn running_balances(movements: &[(Date, Decimal)], dates: &[Date]) -> Vec<Decimal> {
+    let mut result = Vec::with_capacity(dates.len());
+    let mut movements = movements.iter().peekable();
+    let mut balance = Decimal::ZERO;
+    for date in dates {
+        while let Some((_, amount)) = movements.next_if(|(day, _)| day <= date) {
+            balance += amount;
+        }
+        result.push(balance);
+    }
+    result
+}
+
 fn escape_like(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     for c in s.chars() {
@@ -207,6 +732,30 @@ fn escape_like(s: &str) -> String {
     result
 }
 
+/// JOIN clauses keeping only `owner` rows tagged with every dimension in the filter,
+/// with hierarchical prefix matching. Placeholders are numbered from `${first}` and
+/// the returned values bind in that order after the caller's own parameters.
+fn dimension_joins(dimensions: &DimensionFilter, table: &str, fk: &str, owner: &str, first: usize) -> (String, Vec<String>) {
+    let mut sql = String::new();
+    let mut values = Vec::with_capacity(dimensions.len() * 3);
+    for (i, (key, value)) in dimensions.iter().enumerate() {
+        let p = first + i * 3;
+        sql.push_str(&format!(
+            " JOIN {table} d{i} ON d{i}.{fk} = {owner} AND d{i}.dimension_key = ${p1}
+               AND (d{i}.dimension_value = ${p2} OR d{i}.dimension_value LIKE ${p3} || '/%' ESCAPE '\\')",
+            p1 = p, p2 = p + 1, p3 = p + 2
+        ));
+        let value = data_value_to_str(value);
+        let escaped = escape_like(&value);
+        values.extend([key.to_string(), value, escaped]);
+    }
+    (sql, values)
+}
+
+fn sql_params(values: &[String]) -> Vec<&(dyn postgres::types::ToSql + Sync)> {
+    values.iter().map(|s| s as &(dyn postgres::types::ToSql + Sync)).collect()
+}
+
 fn data_value_to_str(dv: &DataValue) -> String {
     match dv {
         DataValue::Null => "null".to_string(),
@@ -223,6 +772,13 @@ fn data_value_to_str(dv: &DataValue) -> String {
         DataValue::Statement(stmt) => format!("{:?}", stmt),
         DataValue::TrialBalance(items) => format!("{:?}", items),
         DataValue::Lots(lots) => format!("{:?}", lots),
+        DataValue::Journals(journals) => format!("{:?}", journals),
+        DataValue::DimensionBalances(items) => format!("{:?}", items),
+        DataValue::BalanceSheet(sheet) => format!("{:?}", sheet),
+        DataValue::CashFlowStatement(statement) => format!("{:?}", statement),
+        DataValue::BalanceSeries(items) => format!("{:?}", items),
+        DataValue::Amortization(items) => format!("{:?}", items),
+        DataValue::DepreciationSchedule(items) => format!("{:?}", items),
     }
 }
 
@@ -251,6 +807,62 @@ impl StorageBackend for PostgresStorage {
             .collect()
     }
 
+    fn lock_period(&self, entity_id: &str, through: Date) -> Result<(), StorageError> {
+        let mut client = self.client.lock().unwrap();
+        ensure_period_open(Self::period_lock(&mut client, entity_id)?, through)?;
+        client
+            .execute(
+                "INSERT INTO period_locks (entity_id, locked_through) VALUES ($1, $2)
+                 ON CONFLICT (entity_id) DO UPDATE SET locked_through = $2",
+                &[&entity_id, &date_to_str(through)],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn unlock_period(&self, entity_id: &str) -> Result<(), StorageError> {
+        let mut client = self.client.lock().unwrap();
+        client
+            .execute("DELETE FROM period_locks WHERE entity_id = $1", &[&entity_id])
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn get_period_lock(&self, entity_id: &str) -> Result<Option<Date>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+        Self::period_lock(&mut client, entity_id)
+    }
+
+    fn record_year_close(&self, entity_id: &str, year_end: Date, journal_ids: &[u128]) -> Result<(), StorageError> {
+        let mut client = self.client.lock().unwrap();
+        let ids: Vec<String> = journal_ids.iter().map(|id| Uuid::from_u128(*id).to_string()).collect();
+        client
+            .execute(
+                "INSERT INTO year_closes (entity_id, year_end, journal_ids) VALUES ($1, $2, $3)
+                 ON CONFLICT (entity_id, year_end) DO UPDATE SET journal_ids = $3",
+                &[&entity_id, &date_to_str(year_end), &ids.join(",")],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn list_year_closes(&self, entity_id: &str) -> Result<Vec<(Date, Vec<u128>)>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+        let rows = client
+            .query(
+                "SELECT year_end, journal_ids FROM year_closes WHERE entity_id = $1 ORDER BY year_end",
+                &[&entity_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let mut closes = Vec::new();
+        for row in rows {
+            let year_end: String = row.get(0);
+            let ids: String = row.get(1);
+            closes.push((str_to_date(&year_end), parse_journal_ids(&ids)?));
+        }
+        Ok(closes)
+    }
+
     fn entity_exists(&self, entity_id: &str) -> bool {
         let mut client = self.client.lock().unwrap();
         let result = client.query_one(
@@ -266,11 +878,46 @@ impl StorageBackend for PostgresStorage {
     fn create_account(&self, entity_id: &str, account: &AccountExpression) -> Result<(), StorageError> {
         let mut client = self.client.lock().unwrap();
         let unit_rate_id_opt = account.unit_rate_id.as_ref().map(|r| r.as_ref());
+        let parent_id_opt = account.parent.as_ref().map(|p| p.as_ref());
+        let mut class = account.class.map(account_class_to_str);
+        let mut cash_flow = account.cash_flow.map(cash_flow_category_to_str);
+        let currency_opt = account.currency.as_ref().map(|c| c.as_ref());
+        if let Some(parent) = parent_id_opt {
+            let row = client
+                .query_opt(
+                    "SELECT account_type, unit_rate_id, account_class, cash_flow FROM accounts WHERE entity_id = $1 AND id = $2",
+                    &[&entity_id, &parent],
+                )
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+                .ok_or_else(|| StorageError::AccountNotFound(parent.to_string()))?;
+            let parent_type: String = row.get(0);
+            let parent_unit_rate: Option<String> = row.get(1);
+            let parent_class: Option<String> = row.get(2);
+            let parent_cash_flow: Option<String> = row.get(3);
+            if parent_type != account_type_to_str(&account.account_type) {
+                return Err(StorageError::InvalidParentAccount(format!("@{} must have the same account type as @{}", account.id, parent)));
+            }
+            if parent_unit_rate.is_some() {
+                return Err(StorageError::InvalidParentAccount(format!("@{} is a unit account", parent)));
+            }
+            let has_entries: bool = client
+                .query_one(
+                    "SELECT EXISTS(SELECT 1 FROM ledger_entries WHERE entity_id = $1 AND account_id = $2)",
+                    &[&entity_id, &parent],
+                )
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+                .get(0);
+            if has_entries {
+                return Err(StorageError::InvalidParentAccount(format!("@{} already has ledger entries", parent)));
+            }
+            class = class.or(parent_class.as_deref().and_then(str_to_account_class).map(account_class_to_str));
+            cash_flow = cash_flow.or(parent_cash_flow.as_deref().and_then(str_to_cash_flow_category).map(cash_flow_category_to_str));
+        }
         let rows = client
             .execute(
-                "INSERT INTO accounts (id, account_type, unit_rate_id, entity_id) VALUES ($1, $2, $3, $4)
+                "INSERT INTO accounts (id, account_type, unit_rate_id, entity_id, parent_id, account_class, cash_flow, currency) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
                  ON CONFLICT (entity_id, id) DO NOTHING",
-                &[&account.id.as_ref(), &account_type_to_str(&account.account_type), &unit_rate_id_opt, &entity_id],
+                &[&account.id.as_ref(), &account_type_to_str(&account.account_type), &unit_rate_id_opt, &entity_id, &parent_id_opt, &class, &cash_flow, &currency_opt],
             )
             .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
         if rows == 0 {
@@ -286,13 +933,18 @@ impl StorageBackend for PostgresStorage {
 
     fn set_rate(&self, entity_id: &str, command: &SetRateCommand) -> Result<(), StorageError> {
         let mut client = self.client.lock().unwrap();
+        ensure_period_open(Self::period_lock(&mut client, entity_id)?, command.date)?;
         let date_str = date_to_str(command.date);
         let val_str = command.rate.to_string();
+        let (dimension_key, dimension_value) = match &command.dimension {
+            Some((key, value)) => (key.to_string(), data_value_to_str(value)),
+            None => (String::new(), String::new()),
+        };
         client
             .execute(
-                "INSERT INTO rates (id, date, value, entity_id) VALUES ($1, $2, $3, $4)
-                 ON CONFLICT (entity_id, id, date) DO UPDATE SET value = $3",
-                &[&command.id.as_ref(), &date_str, &val_str, &entity_id],
+                "INSERT INTO rates (id, date, value, entity_id, dimension_key, dimension_value) VALUES ($1, $2, $3, $4, $5, $6)
+                 ON CONFLICT (entity_id, id, dimension_key, dimension_value, date) DO UPDATE SET value = $3",
+                &[&command.id.as_ref(), &date_str, &val_str, &entity_id, &dimension_key, &dimension_value],
             )
             .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
         Ok(())
@@ -302,7 +954,7 @@ impl StorageBackend for PostgresStorage {
         let mut client = self.client.lock().unwrap();
         let date_str = date_to_str(date);
         let result = client.query_opt(
-            "SELECT value FROM rates WHERE entity_id = $1 AND id = $2 AND date <= $3 ORDER BY date DESC LIMIT 1",
+            "SELECT value FROM rates WHERE entity_id = $1 AND id = $2 AND dimension_key = '' AND date <= $3 ORDER BY date DESC LIMIT 1",
             &[&entity_id, &id, &date_str],
         );
         match result {
@@ -316,19 +968,69 @@ impl StorageBackend for PostgresStorage {
         }
     }
 
-    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<(), StorageError> {
+    fn get_rate_changes(&self, entity_id: &str, id: &str, from: Date, to: Date) -> Result<Vec<RateChange>, StorageError> {
         let mut client = self.client.lock().unwrap();
-        let jid = Uuid::new_v4().to_string();
+        // Changes within the period plus, for each dimension value, the last one before it
+        let rows = client
+            .query(
+                "SELECT r.dimension_key, r.dimension_value, r.date, r.value FROM rates r
+                 WHERE r.entity_id = $1 AND r.id = $2 AND r.date <= $4
+                   AND (r.date > $3 OR r.date = (
+                       SELECT MAX(p.date) FROM rates p
+                       WHERE p.entity_id = r.entity_id AND p.id = r.id AND p.dimension_key = r.dimension_key
+                         AND p.dimension_value = r.dimension_value AND p.date <= $3))
+                 ORDER BY r.date",
+                &[&entity_id, &id, &date_to_str(from), &date_to_str(to)],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut changes = Vec::with_capacity(rows.len());
+        for row in rows {
+            let key: String = row.get(0);
+            let value: String = row.get(1);
+            let date: String = row.get(2);
+            let rate: String = row.get(3);
+            let rate = Decimal::from_str(&rate)
+                .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?;
+            changes.push(RateChange {
+                date: str_to_date(&date).max(from),
+                rate,
+                dimension: (!key.is_empty()).then(|| (Arc::from(key.as_str()), Arc::new(DataValue::String(Arc::from(value.as_str()))))),
+            });
+        }
+        Ok(changes)
+    }
+
+    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<u128, StorageError> {
+        let mut client = self.client.lock().unwrap();
+        let journal_uuid = Uuid::new_v4();
+        let jid = journal_uuid.to_string();
         let seq = Self::next_sequence(&mut client)?;
         let seq_i64 = seq as i64;
         let date_str = date_to_str(command.date);
         let now = OffsetDateTime::now_utc().to_string();
         let amount_str = command.amount.to_string();
+        let reverses = command.reverses.map(|id| Uuid::from_u128(id).to_string());
+        ensure_period_open(Self::period_lock(&mut client, entity_id)?, command.date)?;
+
+        for ledger_entry in &command.ledger_entries {
+            let (LedgerEntryCommand::Debit { account_id, .. } | LedgerEntryCommand::Credit { account_id, .. }) = ledger_entry;
+            let has_children: bool = client
+                .query_one(
+                    "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND parent_id = $2",
+                    &[&entity_id, &account_id.as_ref()],
+                )
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+                .get(0);
+            if has_children {
+                return Err(StorageError::ParentAccountPosting(account_id.to_string()));
+            }
+        }
 
         client
             .execute(
-                "INSERT INTO journals (id, sequence, date, description, amount, created_at, entity_id)
-                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                "INSERT INTO journals (id, sequence, date, description, amount, created_at, entity_id, reverses_journal_id)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
                 &[
                     &jid,
                     &seq_i64,
@@ -337,6 +1039,7 @@ impl StorageBackend for PostgresStorage {
                     &amount_str,
                     &now,
                     &entity_id,
+                    &reverses,
                 ],
             )
             .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
@@ -352,18 +1055,21 @@ impl StorageBackend for PostgresStorage {
                 .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
         }
 
-        for entry in &command.ledger_entries {
-            let (account_id, raw_amount) = match entry {
+        for (index, entry) in command.ledger_entries.iter().enumerate() {
+            let entry_dimensions = command.entry_dimensions(index);
+            let (account_id, raw_amount, raw_currency_amount, side) = match entry {
                 LedgerEntryCommand::Debit {
                     account_id,
                     amount,
+                    currency_amount,
                     ..
-                } => (account_id, *amount),
+                } => (account_id, *amount, *currency_amount, "DEBIT"),
                 LedgerEntryCommand::Credit {
                     account_id,
                     amount,
+                    currency_amount,
                     ..
-                } => (account_id, -*amount),
+                } => (account_id, -*amount, currency_amount.map(|a| -a), "CREDIT"),
             };
 
             let row = client
@@ -376,23 +1082,24 @@ impl StorageBackend for PostgresStorage {
 
             let acct_type_str: String = row.get(0);
             let acct_type = str_to_account_type(&acct_type_str);
-            let signed_amount = match acct_type {
-                AccountType::Asset | AccountType::Expense => raw_amount,
-                AccountType::Liability | AccountType::Equity | AccountType::Income => -raw_amount,
+            let sign = match acct_type {
+                AccountType::Asset | AccountType::Expense | AccountType::Statistical => Decimal::ONE,
+                AccountType::Liability | AccountType::Equity | AccountType::Income => Decimal::NEGATIVE_ONE,
             };
 
-            let amount_str = signed_amount.to_string();
+            let amount_str = (raw_amount * sign).to_string();
+            let currency_amount_str = raw_currency_amount.map(|a| (a * sign).to_string());
             let le_row = client
                 .query_one(
-                    "INSERT INTO ledger_entries (journal_id, account_id, date, amount, entity_id)
-                     VALUES ($1, $2, $3, $4, $5) RETURNING id",
-                    &[&jid, &account_id.as_ref(), &date_str, &amount_str, &entity_id],
+                    "INSERT INTO ledger_entries (journal_id, account_id, date, amount, entity_id, currency_amount, side)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING id",
+                    &[&jid, &account_id.as_ref(), &date_str, &amount_str, &entity_id, &currency_amount_str, &side],
                 )
                 .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
             let le_id: i64 = le_row.get(0);
 
-            for (k, v) in &command.dimensions {
+            for (k, v) in entry_dimensions {
                 let dim_val = data_value_to_str(v);
                 client
                     .execute(
@@ -404,7 +1111,7 @@ impl StorageBackend for PostgresStorage {
             }
 
             // Handle lot creation for debits with units
-            if let LedgerEntryCommand::Debit { account_id, amount, units: Some(unit_count) } = entry {
+            if let LedgerEntryCommand::Debit { account_id, amount, units: Some(unit_count), .. } = entry {
                 let unit_rate_row = client
                     .query_opt(
                         "SELECT unit_rate_id FROM accounts WHERE entity_id = $1 AND id = $2",
@@ -431,7 +1138,7 @@ impl StorageBackend for PostgresStorage {
                         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
                     let lot_id: i64 = lot_row.get(0);
-                    for (k, v) in &command.dimensions {
+                    for (k, v) in entry_dimensions {
                         let dim_val = data_value_to_str(v);
                         client
                             .execute(
@@ -497,19 +1204,205 @@ impl StorageBackend for PostgresStorage {
             }
         }
 
-        Ok(())
+        Ok(journal_uuid.as_u128())
+    }
+
+    fn get_journal(&self, entity_id: &str, journal_id: u128) -> Result<JournalEntry, StorageError> {
+        let mut client = self.client.lock().unwrap();
+        let jid = Uuid::from_u128(journal_id).to_string();
+        Self::read_journals(&mut client, "SELECT id FROM journals WHERE entity_id = $1 AND id = $2", &[entity_id.to_string(), jid.clone()])?
+            .pop()
+            .ok_or(StorageError::JournalNotFound(jid))
+    }
+
+    fn list_journals(&self, entity_id: &str, from: Date, to: Date, dimensions: &DimensionFilter) -> Result<Vec<JournalEntry>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        let (joins, dim_values) = dimension_joins(dimensions, "journal_dimensions", "journal_id", "j.id", 4);
+        let selection = format!("SELECT j.id FROM journals j{joins} WHERE j.entity_id = $1 AND j.date >= $2 AND j.date <= $3");
+        let mut values = vec![entity_id.to_string(), date_to_str(from), date_to_str(to)];
+        values.extend(dim_values);
+        Self::read_journals(&mut client, &selection, &values)
+    }
+
+    fn get_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
+        self.ledger_balance(entity_id, account_id, date, dimensions, "amount")
+    }
+
+    fn get_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError> {
+        self.ledger_statement(entity_id, account_id, from, to, dimensions, "amount")
+    }
+
+    fn get_currency_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
+        self.ledger_balance(entity_id, account_id, date, dimensions, "currency_amount")
+    }
+
+    fn get_currency_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError> {
+        self.ledger_statement(entity_id, account_id, from, to, dimensions, "currency_amount")
+    }
+
+    fn get_dimension_values(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        dimension_key: Arc<str>,
+        from: Date,
+        to: Date,
+    ) -> Result<HashSet<Arc<DataValue>>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        let rows = client
+            .query(
+                "SELECT DISTINCT led.dimension_value
+                 FROM ledger_entries le
+                 JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
+                 WHERE le.entity_id = $1 AND le.account_id = $2 AND led.dimension_key = $3
+                   AND le.date >= $4 AND le.date <= $5",
+                &[
+                    &entity_id,
+                    &account_id,
+                    &dimension_key.as_ref(),
+                    &date_to_str(from),
+                    &date_to_str(to),
+                ],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = HashSet::new();
+        for row in rows {
+            let val: String = row.get(0);
+            result.insert(Arc::new(DataValue::String(Arc::from(val.as_str()))));
+        }
+        Ok(result)
+    }
+
+    fn get_dimension_combinations(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        dimension_keys: &[Arc<str>],
+        from: Date,
+        to: Date,
+    ) -> Result<HashSet<Vec<Arc<DataValue>>>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        let exists = client
+            .query_one(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
+                &[&entity_id, &account_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let exists: bool = exists.get(0);
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+        if dimension_keys.is_empty() {
+            return Ok(HashSet::from([Vec::new()]));
+        }
+
+        let mut columns = Vec::with_capacity(dimension_keys.len());
+        let mut joins = String::new();
+        for i in 0..dimension_keys.len() {
+            columns.push(format!("d{i}.dimension_value"));
+            joins.push_str(&format!(
+                "\n                 JOIN ledger_entry_dimensions d{i} ON d{i}.ledger_entry_id = le.id AND d{i}.dimension_key = ${}",
+                i + 5
+            ));
+        }
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(from), date_to_str(to)];
+        values.extend(dimension_keys.iter().map(|key| key.to_string()));
+        let rows = client
+            .query(
+                &format!(
+                    "{ACCOUNT_SUBTREE}
+                     SELECT DISTINCT {}
+                     FROM ledger_entries le{joins}
+                     WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date >= $3 AND le.date <= $4",
+                    columns.join(", ")
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = HashSet::new();
+        for row in rows {
+            let combination = (0..dimension_keys.len())
+                .map(|i| Arc::new(DataValue::String(Arc::from(row.get::<_, String>(i).as_str()))))
+                .collect();
+            result.insert(combination);
+        }
+        Ok(result)
+    }
+
+    fn get_dimension_movements(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        dimension_keys: &[Arc<str>],
+        from: Date,
+        to: Date,
+    ) -> Result<Vec<DimensionMovement>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        let exists = client
+            .query_one(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
+                &[&entity_id, &account_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let exists: bool = exists.get(0);
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let mut columns = String::new();
+        let mut joins = String::new();
+        for i in 0..dimension_keys.len() {
+            columns.push_str(&format!(", d{i}.dimension_value"));
+            joins.push_str(&format!(
+                "\n                 JOIN ledger_entry_dimensions d{i} ON d{i}.ledger_entry_id = le.id AND d{i}.dimension_key = ${}",
+                i + 5
+            ));
+        }
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(from), date_to_str(to)];
+        values.extend(dimension_keys.iter().map(|key| key.to_string()));
+        // Earlier entries are summed into the opening day rather than returned one by one
+        let rows = client
+            .query(
+                &format!(
+                    "{ACCOUNT_SUBTREE}
+                     SELECT GREATEST(le.date, $3) AS day{columns}, SUM(le.amount::NUMERIC)::TEXT
+                     FROM ledger_entries le{joins}
+                     WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= $4
+                     GROUP BY day{columns}
+                     ORDER BY day"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let keys = dimension_keys.len();
+        let mut result = Vec::with_capacity(rows.len());
+        for row in rows {
+            let date: String = row.get(0);
+            let combination = (1..=keys)
+                .map(|i| Arc::new(DataValue::String(Arc::from(row.get::<_, String>(i).as_str()))))
+                .collect();
+            let amount: String = row.get(keys + 1);
+            result.push((str_to_date(&date), combination, Decimal::from_str(&amount).unwrap_or(Decimal::ZERO)));
+        }
+        Ok(result)
     }
 
-    fn get_balance(
+    fn get_balance_series(
         &self,
         entity_id: &str,
         account_id: &str,
-        date: Date,
-        dimension: Option<&(Arc<str>, Arc<DataValue>)>,
-    ) -> Result<Decimal, StorageError> {
+        dates: &[Date],
+        dimensions: &DimensionFilter,
+    ) -> Result<Vec<Decimal>, StorageError> {
         let mut client = self.client.lock().unwrap();
 
-        // Verify account exists
         let exists = client
             .query_one(
                 "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
@@ -520,54 +1413,49 @@ impl StorageBackend for PostgresStorage {
         if !acct_exists {
             return Err(StorageError::AccountNotFound(account_id.to_string()));
         }
-
-        let date_str = date_to_str(date);
-
-        let total_str: String = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let escaped_dim_val_str = escape_like(&dim_val_str);
-                let row = client
-                    .query_one(
-                        "SELECT COALESCE(SUM(le.amount::NUMERIC), 0)::TEXT
-                         FROM ledger_entries le
-                         JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                         WHERE le.entity_id = $1 AND le.account_id = $2 AND le.date <= $3
-                           AND led.dimension_key = $4
-                           AND (led.dimension_value = $5 OR led.dimension_value LIKE $6 || '/%' ESCAPE '\\')",
-                        &[&entity_id, &account_id, &date_str, &dim_key.as_ref(), &dim_val_str, &escaped_dim_val_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                row.get(0)
-            }
-            None => {
-                let row = client
-                    .query_one(
-                        "SELECT COALESCE(SUM(le.amount::NUMERIC), 0)::TEXT
-                         FROM ledger_entries le
-                         WHERE le.entity_id = $1 AND le.account_id = $2 AND le.date <= $3",
-                        &[&entity_id, &account_id, &date_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                row.get(0)
-            }
+        let Some(last) = dates.last() else {
+            return Ok(Vec::new());
         };
 
-        Decimal::from_str(&total_str)
-            .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))
+        // One query for the daily movements up to the last date, folded into running balances
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(*last)];
+        values.extend(dim_values);
+        let rows = client
+            .query(
+                &format!(
+                    "{ACCOUNT_SUBTREE}
+                     SELECT le.date, SUM(le.amount::NUMERIC)::TEXT
+                     FROM ledger_entries le{joins}
+                     WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= $3
+                     GROUP BY le.date
+                     ORDER BY le.date"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut movements = Vec::with_capacity(rows.len());
+        for row in rows {
+            let date: String = row.get(0);
+            let amount: String = row.get(1);
+            let amount = Decimal::from_str(&amount)
+                .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?;
+            movements.push((str_to_date(&date), amount));
+        }
+        Ok(running_balances(&movements, dates))
     }
 
-    fn get_statement(
+    fn get_turnover(
         &self,
         entity_id: &str,
         account_id: &str,
         from: Bound<Date>,
-        to: Bound<Date>,
-        dimension: Option<&(Arc<str>, Arc<DataValue>)>,
-    ) -> Result<DataValue, StorageError> {
+        to: Date,
+        dimensions: &DimensionFilter,
+    ) -> Result<(Decimal, Decimal), StorageError> {
         let mut client = self.client.lock().unwrap();
 
-        // Verify account exists
         let exists = client
             .query_one(
                 "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
@@ -579,151 +1467,96 @@ impl StorageBackend for PostgresStorage {
             return Err(StorageError::AccountNotFound(account_id.to_string()));
         }
 
-        let balance_date = match from {
-            Bound::Included(d) => d.previous_day().unwrap_or(d),
-            Bound::Excluded(d) => d,
-            Bound::Unbounded => Date::MIN,
-        };
-
         let (from_op, from_str) = match from {
             Bound::Included(d) => (">=", date_to_str(d)),
             Bound::Excluded(d) => (">", date_to_str(d)),
             Bound::Unbounded => (">=", "0000-01-01".to_string()),
         };
-        let (to_op, to_str) = match to {
-            Bound::Included(d) => ("<=", date_to_str(d)),
-            Bound::Excluded(d) => ("<", date_to_str(d)),
-            Bound::Unbounded => ("<=", "9999-12-31".to_string()),
-        };
 
-        // Opening balance
-        let balance_date_str = date_to_str(balance_date);
-        let opening_str: String = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let escaped_dim_val_str = escape_like(&dim_val_str);
-                let row = client
-                    .query_one(
-                        "SELECT COALESCE(SUM(le.amount::NUMERIC), 0)::TEXT
-                         FROM ledger_entries le
-                         JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                         WHERE le.entity_id = $1 AND le.account_id = $2 AND le.date <= $3
-                           AND led.dimension_key = $4
-                           AND (led.dimension_value = $5 OR led.dimension_value LIKE $6 || '/%' ESCAPE '\\')",
-                        &[&entity_id, &account_id, &balance_date_str, &dim_key.as_ref(), &dim_val_str, &escaped_dim_val_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                row.get(0)
-            }
-            None => {
-                let row = client
-                    .query_one(
-                        "SELECT COALESCE(SUM(le.amount::NUMERIC), 0)::TEXT
+        // Entries are stored in the account's normal sign; flip credit-normal accounts
+        // back so that positive amounts are debits, then count each on the side it was
+        // posted on. Entries from before the side was recorded fall back to their sign.
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 5);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), from_str, date_to_str(to)];
+        values.extend(dim_values);
+        let row = client
+            .query_one(
+                &format!(
+                    "{ACCOUNT_SUBTREE},
+                     posted(amount, side) AS (
+                         SELECT CASE WHEN a.account_type IN ('ASSET', 'EXPENSE', 'STATISTICAL') THEN le.amount::NUMERIC ELSE -le.amount::NUMERIC END, le.side
                          FROM ledger_entries le
-                         WHERE le.entity_id = $1 AND le.account_id = $2 AND le.date <= $3",
-                        &[&entity_id, &account_id, &balance_date_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                row.get(0)
-            }
-        };
-        let mut opening_balance =
-            Decimal::from_str(&opening_str).unwrap_or(Decimal::ZERO);
-
-        // Fetch entries in range
-        let query = match dimension {
-            Some(_) => format!(
-                "SELECT le.journal_id, le.date, j.description, le.amount
-                 FROM ledger_entries le
-                 JOIN journals j ON j.id = le.journal_id
-                 JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                 WHERE le.entity_id = $1 AND le.account_id = $2 AND le.date {} $3 AND le.date {} $4
-                   AND led.dimension_key = $5
-                   AND (led.dimension_value = $6 OR led.dimension_value LIKE $7 || '/%' ESCAPE '\\')
-                 ORDER BY le.date, le.id",
-                from_op, to_op
-            ),
-            None => format!(
-                "SELECT le.journal_id, le.date, j.description, le.amount
-                 FROM ledger_entries le
-                 JOIN journals j ON j.id = le.journal_id
-                 WHERE le.entity_id = $1 AND le.account_id = $2 AND le.date {} $3 AND le.date {} $4
-                 ORDER BY le.date, le.id",
-                from_op, to_op
-            ),
-        };
-
-        let rows = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let escaped_dim_val_str = escape_like(&dim_val_str);
-                client
-                    .query(
-                        &query,
-                        &[&entity_id, &account_id, &from_str, &to_str, &dim_key.as_ref(), &dim_val_str, &escaped_dim_val_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-            }
-            None => client
-                .query(&query, &[&entity_id, &account_id, &from_str, &to_str])
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?,
-        };
-
-        let mut result = Vec::new();
-        for row in rows {
-            let jid_str: String = row.get(0);
-            let date_str_row: String = row.get(1);
-            let desc: String = row.get(2);
-            let amt_str: String = row.get(3);
-
-            let amount = Decimal::from_str(&amt_str).unwrap_or(Decimal::ZERO);
-            opening_balance += amount;
-            let journal_id = Uuid::parse_str(&jid_str)
-                .map(|u| u.as_u128())
-                .unwrap_or(0);
-            result.push(StatementTxn {
-                journal_id,
-                date: str_to_date(&date_str_row),
-                description: Arc::from(desc.as_str()),
-                amount,
-                balance: opening_balance,
-            });
-        }
+                         JOIN accounts a ON a.entity_id = le.entity_id AND a.id = le.account_id{joins}
+                         WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND le.date {from_op} $3 AND le.date <= $4
+                     )
+                     SELECT COALESCE(SUM(CASE WHEN side = 'DEBIT' OR (side IS NULL AND amount > 0) THEN amount ELSE 0 END), 0)::TEXT,
+                            COALESCE(SUM(CASE WHEN side = 'CREDIT' OR (side IS NULL AND amount < 0) THEN -amount ELSE 0 END), 0)::TEXT
+                     FROM posted"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let debits: String = row.get(0);
+        let credits: String = row.get(1);
 
-        Ok(DataValue::Statement(result))
+        Ok((
+            Decimal::from_str(&debits).map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?,
+            Decimal::from_str(&credits).map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?,
+        ))
     }
 
-    fn get_dimension_values(
+    fn get_balances_by_dimension(
         &self,
         entity_id: &str,
         account_id: &str,
-        dimension_key: Arc<str>,
-        from: Date,
+        dimension_key: &str,
+        from: Bound<Date>,
         to: Date,
-    ) -> Result<HashSet<Arc<DataValue>>, StorageError> {
+    ) -> Result<Vec<(Arc<DataValue>, Decimal)>, StorageError> {
         let mut client = self.client.lock().unwrap();
 
+        let exists = client
+            .query_one(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = $1 AND id = $2",
+                &[&entity_id, &account_id],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let acct_exists: bool = exists.get(0);
+        if !acct_exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let (from_op, from_str) = match from {
+            Bound::Included(d) => (">=", date_to_str(d)),
+            Bound::Excluded(d) => (">", date_to_str(d)),
+            Bound::Unbounded => (">=", "0000-01-01".to_string()),
+        };
+
         let rows = client
             .query(
-                "SELECT DISTINCT led.dimension_value
-                 FROM ledger_entries le
-                 JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                 WHERE le.entity_id = $1 AND le.account_id = $2 AND led.dimension_key = $3
-                   AND le.date >= $4 AND le.date <= $5",
-                &[
-                    &entity_id,
-                    &account_id,
-                    &dimension_key.as_ref(),
-                    &date_to_str(from),
-                    &date_to_str(to),
-                ],
+                &format!(
+                    "{ACCOUNT_SUBTREE}
+                     SELECT led.dimension_value, SUM(le.amount::NUMERIC)::TEXT
+                     FROM ledger_entries le
+                     JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
+                     WHERE le.entity_id = $1 AND le.account_id IN (SELECT id FROM subtree) AND led.dimension_key = $3
+                       AND le.date {} $4 AND le.date <= $5
+                     GROUP BY led.dimension_value
+                     ORDER BY led.dimension_value",
+                    from_op
+                ),
+                &[&entity_id, &account_id, &dimension_key, &from_str, &date_to_str(to)],
             )
             .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
-        let mut result = HashSet::new();
+        let mut result = Vec::with_capacity(rows.len());
         for row in rows {
-            let val: String = row.get(0);
-            result.insert(Arc::new(DataValue::String(Arc::from(val.as_str()))));
+            let value: String = row.get(0);
+            let amount: String = row.get(1);
+            result.push((
+                Arc::new(DataValue::String(Arc::from(value.as_str()))),
+                Decimal::from_str(&amount).unwrap_or(Decimal::ZERO),
+            ));
         }
         Ok(result)
     }
@@ -796,44 +1629,83 @@ impl StorageBackend for PostgresStorage {
         Ok(())
     }
 
-    fn get_lots(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Vec<LotItem>, StorageError> {
+    fn create_fixed_asset(&self, entity_id: &str, asset: &FixedAsset) -> Result<(), StorageError> {
         let mut client = self.client.lock().unwrap();
+        let (method, factor) = depreciation_method_to_str(&asset.method);
+        let inserted = client
+            .execute(
+                "INSERT INTO fixed_assets (entity_id, id, cost, in_service, life_years, salvage, method, factor,
+                    asset_account, accumulated_account, expense_account, depreciated_through, disposed_on)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                 ON CONFLICT (entity_id, id) DO NOTHING",
+                &[
+                    &entity_id,
+                    &asset.id.as_ref(),
+                    &asset.cost.to_string(),
+                    &date_to_str(asset.in_service),
+                    &(asset.life_years as i32),
+                    &asset.salvage.to_string(),
+                    &method,
+                    &factor,
+                    &asset.asset_account.as_ref(),
+                    &asset.accumulated_account.as_ref(),
+                    &asset.expense_account.as_ref(),
+                    &asset.depreciated_through.map(date_to_str),
+                    &asset.disposed_on.map(date_to_str),
+                ],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if inserted == 0 {
+            return Err(StorageError::DuplicateFixedAsset(asset.id.to_string()));
+        }
+        Ok(())
+    }
 
-        let lot_rows: Vec<(i64, String, String, String)> = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let escaped_dim_val_str = escape_like(&dim_val_str);
-                let rows = client
-                    .query(
-                        "SELECT l.id, l.date, l.units_remaining, l.cost_per_unit FROM lots l
-                         WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0
-                           AND EXISTS (
-                             SELECT 1 FROM lot_dimensions ld
-                             WHERE ld.lot_id = l.id AND ld.dimension_key = $3
-                               AND (ld.dimension_value = $4 OR ld.dimension_value LIKE $5 || '/%' ESCAPE '\\')
-                           )
-                         ORDER BY l.date ASC",
-                        &[&entity_id, &account_id, &dim_key.as_ref(), &dim_val_str, &escaped_dim_val_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                rows.iter()
-                    .map(|r| (r.get(0), r.get(1), r.get(2), r.get(3)))
-                    .collect()
-            }
-            None => {
-                let rows = client
-                    .query(
-                        "SELECT l.id, l.date, l.units_remaining, l.cost_per_unit FROM lots l
-                         WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0
-                         ORDER BY l.date ASC",
-                        &[&entity_id, &account_id],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                rows.iter()
-                    .map(|r| (r.get(0), r.get(1), r.get(2), r.get(3)))
-                    .collect()
-            }
-        };
+    fn get_fixed_asset(&self, entity_id: &str, asset_id: &str) -> Result<FixedAsset, StorageError> {
+        let mut client = self.client.lock().unwrap();
+        query_fixed_assets(&mut client, entity_id, " AND id = $2", &[asset_id.to_string()])?
+            .pop()
+            .ok_or_else(|| StorageError::FixedAssetNotFound(asset_id.to_string()))
+    }
+
+    fn list_fixed_assets(&self, entity_id: &str) -> Result<Vec<FixedAsset>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+        query_fixed_assets(&mut client, entity_id, "", &[])
+    }
+
+    fn update_fixed_asset(&self, entity_id: &str, asset_id: &str, depreciated_through: Option<Date>, disposed_on: Option<Date>) -> Result<(), StorageError> {
+        let mut client = self.client.lock().unwrap();
+        let updated = client
+            .execute(
+                "UPDATE fixed_assets SET depreciated_through = $3, disposed_on = $4 WHERE entity_id = $1 AND id = $2",
+                &[&entity_id, &asset_id, &depreciated_through.map(date_to_str), &disposed_on.map(date_to_str)],
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if updated == 0 {
+            return Err(StorageError::FixedAssetNotFound(asset_id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn get_lots(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Vec<LotItem>, StorageError> {
+        let mut client = self.client.lock().unwrap();
+
+        let (joins, dim_values) = dimension_joins(dimensions, "lot_dimensions", "lot_id", "l.id", 3);
+        let mut values = vec![entity_id.to_string(), account_id.to_string()];
+        values.extend(dim_values);
+        let rows = client
+            .query(
+                &format!(
+                    "SELECT l.id, l.date, l.units_remaining, l.cost_per_unit FROM lots l{joins}
+                     WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0
+                     ORDER BY l.date ASC"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let lot_rows: Vec<(i64, String, String, String)> = rows.iter()
+            .map(|r| (r.get(0), r.get(1), r.get(2), r.get(3)))
+            .collect();
 
         let mut result = Vec::new();
         for (lot_id, date_str, units_str, cpu_str) in lot_rows {
@@ -868,45 +1740,30 @@ impl StorageBackend for PostgresStorage {
         Ok(result)
     }
 
-    fn get_total_units(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Decimal, StorageError> {
+    fn get_total_units(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
         let mut client = self.client.lock().unwrap();
 
-        let total_str: String = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let escaped_dim_val_str = escape_like(&dim_val_str);
-                let row = client
-                    .query_one(
-                        "SELECT COALESCE(SUM(l.units_remaining::NUMERIC), 0)::TEXT FROM lots l
-                         WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0
-                           AND EXISTS (
-                             SELECT 1 FROM lot_dimensions ld
-                             WHERE ld.lot_id = l.id AND ld.dimension_key = $3
-                               AND (ld.dimension_value = $4 OR ld.dimension_value LIKE $5 || '/%' ESCAPE '\\')
-                           )",
-                        &[&entity_id, &account_id, &dim_key.as_ref(), &dim_val_str, &escaped_dim_val_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                row.get(0)
-            }
-            None => {
-                let row = client
-                    .query_one(
-                        "SELECT COALESCE(SUM(l.units_remaining::NUMERIC), 0)::TEXT FROM lots l
-                         WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0",
-                        &[&entity_id, &account_id],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                row.get(0)
-            }
-        };
+        let (joins, dim_values) = dimension_joins(dimensions, "lot_dimensions", "lot_id", "l.id", 3);
+        let mut values = vec![entity_id.to_string(), account_id.to_string()];
+        values.extend(dim_values);
+        let row = client
+            .query_one(
+                &format!(
+                    "SELECT COALESCE(SUM(l.units_remaining::NUMERIC), 0)::TEXT FROM lots l{joins}
+                     WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let total_str: String = row.get(0);
 
         Decimal::from_str(&total_str)
             .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))
     }
 
-    fn deplete_lots(&self, entity_id: &str, account_id: &str, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError> {
+    fn deplete_lots(&self, entity_id: &str, account_id: &str, date: Date, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError> {
         let mut client = self.client.lock().unwrap();
+        ensure_period_open(Self::period_lock(&mut client, entity_id)?, date)?;
 
         let order = match method {
             CostMethod::Fifo => "ASC",
@@ -1056,41 +1913,28 @@ impl StorageBackend for PostgresStorage {
         }
     }
 
-    fn split_lots(&self, entity_id: &str, account_id: &str, new_per_old: Decimal, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<(), StorageError> {
+    fn split_lots(&self, entity_id: &str, account_id: &str, date: Date, new_per_old: Decimal, dimensions: &DimensionFilter) -> Result<(), StorageError> {
         let mut client = self.client.lock().unwrap();
+        ensure_period_open(Self::period_lock(&mut client, entity_id)?, date)?;
         let ratio_str = new_per_old.to_string();
 
-        match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let escaped_dim_val_str = escape_like(&dim_val_str);
-                client
-                    .execute(
-                        "UPDATE lots SET
-                            units_remaining = (units_remaining::NUMERIC * $3::NUMERIC)::TEXT,
-                            cost_per_unit = (cost_per_unit::NUMERIC / $3::NUMERIC)::TEXT
-                         WHERE entity_id = $1 AND account_id = $2 AND units_remaining::NUMERIC > 0
-                           AND EXISTS (
-                             SELECT 1 FROM lot_dimensions ld
-                             WHERE ld.lot_id = lots.id AND ld.dimension_key = $4
-                               AND (ld.dimension_value = $5 OR ld.dimension_value LIKE $6 || '/%' ESCAPE '\\')
-                           )",
-                        &[&entity_id, &account_id, &ratio_str, &dim_key.as_ref(), &dim_val_str, &escaped_dim_val_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-            }
-            None => {
-                client
-                    .execute(
-                        "UPDATE lots SET
-                            units_remaining = (units_remaining::NUMERIC * $3::NUMERIC)::TEXT,
-                            cost_per_unit = (cost_per_unit::NUMERIC / $3::NUMERIC)::TEXT
-                         WHERE entity_id = $1 AND account_id = $2 AND units_remaining::NUMERIC > 0",
-                        &[&entity_id, &account_id, &ratio_str],
-                    )
-                    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-            }
-        }
+        let (joins, dim_values) = dimension_joins(dimensions, "lot_dimensions", "lot_id", "l.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), ratio_str];
+        values.extend(dim_values);
+        client
+            .execute(
+                &format!(
+                    "UPDATE lots SET
+                        units_remaining = (units_remaining::NUMERIC * $3::NUMERIC)::TEXT,
+                        cost_per_unit = (cost_per_unit::NUMERIC / $3::NUMERIC)::TEXT
+                     WHERE id IN (
+                         SELECT l.id FROM lots l{joins}
+                         WHERE l.entity_id = $1 AND l.account_id = $2 AND l.units_remaining::NUMERIC > 0
+                     )"
+                ),
+                &sql_params(&values),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
         Ok(())
     }
@@ -1110,6 +1954,81 @@ impl StorageBackend for PostgresStorage {
         }
     }
 
+    fn get_parent_account(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>> {
+        let mut client = self.client.lock().unwrap();
+        let result = client.query_opt(
+            "SELECT parent_id FROM accounts WHERE entity_id = $1 AND id = $2",
+            &[&entity_id, &account_id],
+        );
+        match result {
+            Ok(Some(row)) => {
+                let val: Option<String> = row.get(0);
+                val.map(|s| Arc::from(s.as_str()))
+            }
+            _ => None,
+        }
+    }
+
+    fn get_account_type(&self, entity_id: &str, account_id: &str) -> Option<AccountType> {
+        let mut client = self.client.lock().unwrap();
+        let result = client.query_opt(
+            "SELECT account_type FROM accounts WHERE entity_id = $1 AND id = $2",
+            &[&entity_id, &account_id],
+        );
+        match result {
+            Ok(Some(row)) => {
+                let account_type: String = row.get(0);
+                Some(str_to_account_type(&account_type))
+            }
+            _ => None,
+        }
+    }
+
+    fn get_account_class(&self, entity_id: &str, account_id: &str) -> Option<AccountClass> {
+        let mut client = self.client.lock().unwrap();
+        let result = client.query_opt(
+            "SELECT account_class FROM accounts WHERE entity_id = $1 AND id = $2",
+            &[&entity_id, &account_id],
+        );
+        match result {
+            Ok(Some(row)) => {
+                let val: Option<String> = row.get(0);
+                val.as_deref().and_then(str_to_account_class)
+            }
+            _ => None,
+        }
+    }
+
+    fn get_cash_flow_category(&self, entity_id: &str, account_id: &str) -> Option<CashFlowCategory> {
+        let mut client = self.client.lock().unwrap();
+        let result = client.query_opt(
+            "SELECT cash_flow FROM accounts WHERE entity_id = $1 AND id = $2",
+            &[&entity_id, &account_id],
+        );
+        match result {
+            Ok(Some(row)) => {
+                let val: Option<String> = row.get(0);
+                val.as_deref().and_then(str_to_cash_flow_category)
+            }
+            _ => None,
+        }
+    }
+
+    fn get_account_currency(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>> {
+        let mut client = self.client.lock().unwrap();
+        let result = client.query_opt(
+            "SELECT currency FROM accounts WHERE entity_id = $1 AND id = $2",
+            &[&entity_id, &account_id],
+        );
+        match result {
+            Ok(Some(row)) => {
+                let val: Option<String> = row.get(0);
+                val.map(|c| Arc::from(c.as_str()))
+            }
+            _ => None,
+        }
+    }
+
     fn is_unit_account(&self, entity_id: &str, account_id: &str) -> bool {
         let mut client = self.client.lock().unwrap();
         let result = client.query_opt(
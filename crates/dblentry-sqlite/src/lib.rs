@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     ops::Bound,
     str::FromStr,
     sync::{
@@ -9,16 +9,17 @@ use std::{
 };
 
 use rust_decimal::Decimal;
-use rusqlite::{params, Connection};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension};
 use time::{Date, Month, OffsetDateTime};
 use uuid::Uuid;
 
 use dblentry_core::{
-    AccountExpression, AccountType, CostMethod, LotItem,
+    AccountClass, AccountExpression, CashFlowCategory, AccountType, CostMethod, LotItem, FixedAsset, DepreciationMethod,
     CreateJournalCommand, CreateRateCommand, LedgerEntryCommand, SetRateCommand,
-    DataValue, StatementTxn,
-    StorageBackend, StorageError, TransactionId,
+    DataValue, DimensionFilter, JournalEntry, JournalLine, RateChange, StatementTxn,
+    DimensionMovement, StorageBackend, StorageError, TransactionId,
 };
+use dblentry_core::storage::ensure_period_open;
 
 pub struct SqliteStorage {
     conn: Mutex<Connection>,
@@ -55,6 +56,35 @@ impl SqliteStorage {
                 id TEXT PRIMARY KEY
             );
 
+            CREATE TABLE IF NOT EXISTS period_locks (
+                entity_id TEXT PRIMARY KEY,
+                locked_through TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS year_closes (
+                entity_id TEXT NOT NULL,
+                year_end TEXT NOT NULL,
+                journal_ids TEXT NOT NULL,
+                PRIMARY KEY (entity_id, year_end)
+            );
+
+            CREATE TABLE IF NOT EXISTS fixed_assets (
+                entity_id TEXT NOT NULL,
+                id TEXT NOT NULL,
+                cost TEXT NOT NULL,
+                in_service TEXT NOT NULL,
+                life_years INTEGER NOT NULL,
+                salvage TEXT NOT NULL,
+                method TEXT NOT NULL,
+                factor TEXT,
+                asset_account TEXT NOT NULL,
+                accumulated_account TEXT NOT NULL,
+                expense_account TEXT NOT NULL,
+                depreciated_through TEXT,
+                disposed_on TEXT,
+                PRIMARY KEY (entity_id, id)
+            );
+
             INSERT OR IGNORE INTO entities (id) VALUES ('default');
 
             CREATE TABLE IF NOT EXISTS accounts (
@@ -62,6 +92,10 @@ impl SqliteStorage {
                 account_type TEXT NOT NULL,
                 unit_rate_id TEXT,
                 entity_id TEXT NOT NULL DEFAULT 'default',
+                parent_id TEXT,
+                account_class TEXT,
+                cash_flow TEXT,
+                currency TEXT,
                 PRIMARY KEY (entity_id, id)
             );
 
@@ -70,7 +104,9 @@ impl SqliteStorage {
                 date TEXT NOT NULL,
                 value TEXT NOT NULL,
                 entity_id TEXT NOT NULL DEFAULT 'default',
-                PRIMARY KEY (entity_id, id, date)
+                dimension_key TEXT NOT NULL DEFAULT '',
+                dimension_value TEXT NOT NULL DEFAULT '',
+                PRIMARY KEY (entity_id, id, dimension_key, dimension_value, date)
             );
 
             CREATE TABLE IF NOT EXISTS journals (
@@ -80,7 +116,8 @@ impl SqliteStorage {
                 description TEXT NOT NULL,
                 amount TEXT NOT NULL,
                 created_at TEXT NOT NULL,
-                entity_id TEXT NOT NULL DEFAULT 'default'
+                entity_id TEXT NOT NULL DEFAULT 'default',
+                reverses_journal_id TEXT
             );
 
             CREATE TABLE IF NOT EXISTS journal_dimensions (
@@ -97,6 +134,8 @@ impl SqliteStorage {
                 date TEXT NOT NULL,
                 amount TEXT NOT NULL,
                 entity_id TEXT NOT NULL DEFAULT 'default',
+                currency_amount TEXT,
+                side TEXT,
                 FOREIGN KEY (journal_id) REFERENCES journals(id),
                 FOREIGN KEY (entity_id, account_id) REFERENCES accounts(entity_id, id)
             );
@@ -148,6 +187,120 @@ impl SqliteStorage {
         )
         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
+        // Databases created before journal reversals lack the link column
+        let has_reverses: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('journals') WHERE name = 'reverses_journal_id'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_reverses {
+            conn.execute("ALTER TABLE journals ADD COLUMN reverses_journal_id TEXT", [])
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Databases created before account hierarchies lack the parent column
+        let has_parent: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('accounts') WHERE name = 'parent_id'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_parent {
+            conn.execute("ALTER TABLE accounts ADD COLUMN parent_id TEXT", [])
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Databases created before balance sheet classification lack the class column
+        let has_class: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('accounts') WHERE name = 'account_class'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_class {
+            conn.execute("ALTER TABLE accounts ADD COLUMN account_class TEXT", [])
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Databases created before cash flow statements lack the category column
+        let has_cash_flow: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('accounts') WHERE name = 'cash_flow'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_cash_flow {
+            conn.execute("ALTER TABLE accounts ADD COLUMN cash_flow TEXT", [])
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Databases created before currency accounts lack the currency columns
+        let has_currency: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('accounts') WHERE name = 'currency'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_currency {
+            conn.execute_batch(
+                "
+                ALTER TABLE accounts ADD COLUMN currency TEXT;
+                ALTER TABLE ledger_entries ADD COLUMN currency_amount TEXT;
+                ",
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Databases created before legs recorded their side leave it to be inferred from the sign
+        let has_side: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('ledger_entries') WHERE name = 'side'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_side {
+            conn.execute("ALTER TABLE ledger_entries ADD COLUMN side TEXT", [])
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
+        // Databases created before per-dimension rates key rates without the dimension, so the
+        // table is rebuilt to widen its primary key
+        let has_rate_dimension: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM pragma_table_info('rates') WHERE name = 'dimension_key'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !has_rate_dimension {
+            conn.execute_batch(
+                "
+                ALTER TABLE rates RENAME TO rates_undimensioned;
+                CREATE TABLE rates (
+                    id TEXT NOT NULL,
+                    date TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    entity_id TEXT NOT NULL DEFAULT 'default',
+                    dimension_key TEXT NOT NULL DEFAULT '',
+                    dimension_value TEXT NOT NULL DEFAULT '',
+                    PRIMARY KEY (entity_id, id, dimension_key, dimension_value, date)
+                );
+                INSERT INTO rates (id, date, value, entity_id)
+                    SELECT id, date, value, entity_id FROM rates_undimensioned;
+                DROP TABLE rates_undimensioned;
+                CREATE INDEX IF NOT EXISTS idx_rates_lookup ON rates(entity_id, id, date);
+                ",
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        }
+
         Ok(())
     }
 
@@ -164,12 +317,317 @@ impl SqliteStorage {
             .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
         Ok(seq)
     }
+
+    fn period_lock(conn: &Connection, entity_id: &str) -> Result<Option<Date>, StorageError> {
+        let locked_through: Option<String> = conn
+            .query_row(
+                "SELECT locked_through FROM period_locks WHERE entity_id = ?1",
+                params![entity_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(locked_through.map(|d| str_to_date(&d)))
+    }
+
+    /// Load the journals `selection` picks, with their dimensions and legs, in date and
+    /// sequence order. `selection` is a query returning journal ids over `values`, with the
+    /// entity as `?1`; each part of the journals is loaded in one query for all of them.
+    fn read_journals(conn: &Connection, selection: &str, values: &[String]) -> Result<Vec<JournalEntry>, StorageError> {
+        let query = |sql: &str| conn.prepare(sql).map_err(|e| StorageError::DatabaseError(e.to_string()));
+
+        let mut dimensions: HashMap<String, BTreeMap<Arc<str>, Arc<DataValue>>> = HashMap::new();
+        {
+            let mut stmt = query(&format!(
+                "SELECT journal_id, dimension_key, dimension_value FROM journal_dimensions WHERE journal_id IN ({selection})"
+            ))?;
+            let rows = stmt.query_map(
+                params_from_iter(values),
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+            ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            for row in rows {
+                let (jid, k, v) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+                dimensions.entry(jid).or_default()
+                    .insert(Arc::from(k.as_str()), Arc::new(DataValue::String(Arc::from(v.as_str()))));
+            }
+        }
+
+        let mut leg_dimensions: HashMap<i64, BTreeMap<Arc<str>, Arc<DataValue>>> = HashMap::new();
+        {
+            let mut stmt = query(&format!(
+                "SELECT led.ledger_entry_id, led.dimension_key, led.dimension_value
+                 FROM ledger_entry_dimensions led
+                 JOIN ledger_entries le ON le.id = led.ledger_entry_id
+                 WHERE le.entity_id = ?1 AND le.journal_id IN ({selection})"
+            ))?;
+            let rows = stmt.query_map(
+                params_from_iter(values),
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+            ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            for row in rows {
+                let (le_id, k, v) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+                leg_dimensions.entry(le_id).or_default()
+                    .insert(Arc::from(k.as_str()), Arc::new(DataValue::String(Arc::from(v.as_str()))));
+            }
+        }
+
+        // Ledger amounts are stored signed by account type; undo that to recover the legs
+        let mut lines: HashMap<String, Vec<JournalLine>> = HashMap::new();
+        {
+            let mut stmt = query(&format!(
+                "SELECT le.journal_id, le.account_id, a.account_type, le.amount, le.currency_amount, le.id, le.side
+                 FROM ledger_entries le
+                 JOIN accounts a ON a.entity_id = le.entity_id AND a.id = le.account_id
+                 WHERE le.entity_id = ?1 AND le.journal_id IN ({selection})
+                 ORDER BY le.id"
+            ))?;
+            let rows = stmt.query_map(
+                params_from_iter(values),
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, Option<String>>(4)?, row.get::<_, i64>(5)?, row.get::<_, Option<String>>(6)?)),
+            ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            for row in rows {
+                let (jid, account_id, acct_type_str, amt_str, currency_amt_str, le_id, side) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+                let signed = Decimal::from_str(&amt_str)
+                    .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?;
+                let sign = match str_to_account_type(&acct_type_str) {
+                    AccountType::Asset | AccountType::Expense | AccountType::Statistical => Decimal::ONE,
+                    AccountType::Liability | AccountType::Equity | AccountType::Income => Decimal::NEGATIVE_ONE,
+                };
+                let raw = signed * sign;
+                let debit = match side.as_deref() {
+                    Some("DEBIT") => true,
+                    Some("CREDIT") => false,
+                    // Entries from before the side was recorded sit on the side of their sign
+                    _ => raw >= Decimal::ZERO,
+                };
+                // The transaction amount is signed like the functional one and read on the same side
+                let currency_amount = match currency_amt_str {
+                    Some(a) => {
+                        let raw_currency = Decimal::from_str(&a)
+                            .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))? * sign;
+                        Some(if debit { raw_currency } else { -raw_currency })
+                    }
+                    None => None,
+                };
+                let leg_dimensions = leg_dimensions.remove(&le_id).unwrap_or_default();
+                let journal_dimensions = dimensions.get(&jid).cloned().unwrap_or_default();
+                lines.entry(jid).or_default().push(JournalLine {
+                    account_id: Arc::from(account_id.as_str()),
+                    debit: if debit { raw } else { Decimal::ZERO },
+                    credit: if debit { Decimal::ZERO } else { -raw },
+                    currency_amount,
+                    dimensions: (leg_dimensions != journal_dimensions).then_some(leg_dimensions),
+                });
+            }
+        }
+
+        let mut reversed_by: HashMap<String, String> = HashMap::new();
+        {
+            let mut stmt = query(&format!(
+                "SELECT reverses_journal_id, id FROM journals WHERE entity_id = ?1 AND reverses_journal_id IN ({selection})"
+            ))?;
+            let rows = stmt.query_map(
+                params_from_iter(values),
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            for row in rows {
+                let (reversed, reversal) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+                reversed_by.insert(reversed, reversal);
+            }
+        }
+
+        let mut stmt = query(&format!(
+            "SELECT id, sequence, date, description, amount, created_at, reverses_journal_id FROM journals
+             WHERE entity_id = ?1 AND id IN ({selection})
+             ORDER BY date, sequence"
+        ))?;
+        let rows = stmt.query_map(
+            params_from_iter(values),
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, u64>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?, row.get::<_, String>(5)?, row.get::<_, Option<String>>(6)?)),
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let parse_id = |id: &str| Uuid::parse_str(id).ok().map(|u| u.as_u128());
+        let mut journals = Vec::new();
+        for row in rows {
+            let (jid, sequence, date_str, description, amount_str, created_at, reverses) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            journals.push(JournalEntry {
+                id: parse_id(&jid).ok_or_else(|| StorageError::DatabaseError(format!("Invalid journal id: {}", jid)))?,
+                sequence,
+                date: str_to_date(&date_str),
+                description: Arc::from(description.as_str()),
+                amount: Decimal::from_str(&amount_str)
+                    .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?,
+                dimensions: dimensions.remove(&jid).unwrap_or_default(),
+                created_at: str_to_timestamp(&created_at),
+                lines: lines.remove(&jid).unwrap_or_default(),
+                reverses: reverses.as_deref().and_then(parse_id),
+                reversed_by: reversed_by.get(&jid).and_then(|r| parse_id(r)),
+            });
+        }
+        Ok(journals)
+    }
+
+    /// Balance of the account and its descendants, summing `column` of their ledger entries.
+    fn ledger_balance(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        date: Date,
+        dimensions: &DimensionFilter,
+        column: &str,
+    ) -> Result<Decimal, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        // Verify account exists
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
+                params![entity_id, account_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let date_str = date_to_str(date);
+
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_str];
+        values.extend(dim_values);
+        let val: String = conn.query_row(
+            &format!(
+                "{ACCOUNT_SUBTREE}
+                 SELECT CAST(COALESCE(SUM(le.{column}), 0) AS TEXT)
+                 FROM ledger_entries le{joins}
+                 WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= ?3"
+            ),
+            params_from_iter(values),
+            |row| row.get(0),
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let total = Decimal::from_str(&val).unwrap_or(Decimal::ZERO);
+
+        Ok(total)
+    }
+
+    /// Running statement of the account and its descendants over `column` of their ledger entries.
+    fn ledger_statement(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        from: Bound<Date>,
+        to: Bound<Date>,
+        dimensions: &DimensionFilter,
+        column: &str,
+    ) -> Result<DataValue, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        // Verify account exists
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
+                params![entity_id, account_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        // Get the opening balance
+        let balance_date = match from {
+            Bound::Included(d) => d.previous_day().unwrap_or(d),
+            Bound::Excluded(d) => d,
+            Bound::Unbounded => Date::MIN,
+        };
+
+        // Build date range conditions
+        let (from_op, from_str) = match from {
+            Bound::Included(d) => (">=", date_to_str(d)),
+            Bound::Excluded(d) => (">", date_to_str(d)),
+            Bound::Unbounded => (">=", "0000-01-01".to_string()),
+        };
+        let (to_op, to_str) = match to {
+            Bound::Included(d) => ("<=", date_to_str(d)),
+            Bound::Excluded(d) => ("<", date_to_str(d)),
+            Bound::Unbounded => ("<=", "9999-12-31".to_string()),
+        };
+
+        // Calculate opening balance
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(balance_date)];
+        values.extend(dim_values.iter().cloned());
+        let val: String = conn.query_row(
+            &format!(
+                "{ACCOUNT_SUBTREE}
+                 SELECT CAST(COALESCE(SUM(le.{column}), 0) AS TEXT)
+                 FROM ledger_entries le{joins}
+                 WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= ?3"
+            ),
+            params_from_iter(values),
+            |row| row.get(0),
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let mut opening_balance = Decimal::from_str(&val).unwrap_or(Decimal::ZERO);
+
+        // Fetch entries in the date range
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 5);
+        let query = format!(
+            "{ACCOUNT_SUBTREE}
+             SELECT le.journal_id, le.date, j.description, le.{column}
+             FROM ledger_entries le
+             JOIN journals j ON j.id = le.journal_id{joins}
+             WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date {} ?3 AND le.date {} ?4 AND le.{column} IS NOT NULL
+             ORDER BY le.date, le.id",
+            from_op, to_op
+        );
+
+        let mut stmt = conn.prepare(&query).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), from_str, to_str];
+        values.extend(dim_values);
+        let rows: Vec<(String, String, String, String)> = stmt.query_map(
+            params_from_iter(values),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = Vec::new();
+        for (jid_str, date_str_row, desc, amt_str) in rows {
+            let amount = Decimal::from_str(&amt_str).unwrap_or(Decimal::ZERO);
+            opening_balance += amount;
+            let journal_id = Uuid::parse_str(&jid_str)
+                .map(|u| u.as_u128())
+                .unwrap_or(0);
+            result.push(StatementTxn {
+                journal_id,
+                date: str_to_date(&date_str_row),
+                description: Arc::from(desc.as_str()),
+                amount,
+                balance: opening_balance,
+            });
+        }
+
+        Ok(DataValue::Statement(result))
+    }
 }
 
 fn date_to_str(d: Date) -> String {
     format!("{:04}-{:02}-{:02}", d.year(), d.month() as u8, d.day())
 }
 
+/// Parse a comma-separated list of journal UUIDs.
+fn parse_journal_ids(s: &str) -> Result<Vec<u128>, StorageError> {
+    s.split(',')
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            Uuid::parse_str(id)
+                .map(|u| u.as_u128())
+                .map_err(|e| StorageError::DatabaseError(format!("Invalid journal id: {}", e)))
+        })
+        .collect()
+}
+
 fn str_to_date(s: &str) -> Date {
     let parts: Vec<&str> = s.split('-').collect();
     let year = parts[0].parse::<i32>().unwrap();
@@ -178,6 +636,33 @@ fn str_to_date(s: &str) -> Date {
     Date::from_calendar_date(year, Month::try_from(month).unwrap(), day).unwrap()
 }
 
+/// Parse a `created_at` value, which is stored in `OffsetDateTime`'s display
+/// format (e.g. `2024-01-31 9:05:00.123 +00:00:00`).
+fn str_to_timestamp(s: &str) -> OffsetDateTime {
+    fn parse(s: &str) -> Option<OffsetDateTime> {
+        let mut parts = s.split(' ');
+        let date = str_to_date(parts.next()?);
+        let mut clock = parts.next()?.split([':', '.']);
+        let hour = clock.next()?.parse().ok()?;
+        let minute = clock.next()?.parse().ok()?;
+        let second = clock.next()?.parse().ok()?;
+        let fraction = clock.next().unwrap_or("0");
+        let nanos = format!("{:0<9}", fraction).get(..9)?.parse().ok()?;
+        let time = time::Time::from_hms_nano(hour, minute, second, nanos).ok()?;
+        let offset = parts.next()?;
+        let sign: i8 = if offset.starts_with('-') { -1 } else { 1 };
+        let mut fields = offset.get(1..)?.split(':').map(|f| f.parse::<i8>().ok());
+        let offset = time::UtcOffset::from_hms(
+            sign * fields.next()??,
+            sign * fields.next()??,
+            sign * fields.next()??,
+        )
+        .ok()?;
+        Some(date.with_time(time).assume_offset(offset))
+    }
+    parse(s).unwrap_or(OffsetDateTime::UNIX_EPOCH)
+}
+
 fn account_type_to_str(at: &AccountType) -> &'static str {
     match at {
         AccountType::Asset => "ASSET",
@@ -185,6 +670,96 @@ fn account_type_to_str(at: &AccountType) -> &'static str {
         AccountType::Equity => "EQUITY",
         AccountType::Income => "INCOME",
         AccountType::Expense => "EXPENSE",
+        AccountType::Statistical => "STATISTICAL",
+    }
+}
+
+fn depreciation_method_to_str(method: &DepreciationMethod) -> (&'static str, Option<String>) {
+    match method {
+        DepreciationMethod::StraightLine => ("STRAIGHT_LINE", None),
+        DepreciationMethod::DecliningBalance(factor) => ("DECLINING_BALANCE", Some(factor.to_string())),
+        DepreciationMethod::SumOfYears => ("SUM_OF_YEARS", None),
+    }
+}
+
+fn str_to_depreciation_method(method: &str, factor: Option<&str>) -> Result<DepreciationMethod, StorageError> {
+    match (method, factor) {
+        ("STRAIGHT_LINE", _) => Ok(DepreciationMethod::StraightLine),
+        ("DECLINING_BALANCE", Some(factor)) => Decimal::from_str(factor)
+            .map(DepreciationMethod::DecliningBalance)
+            .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e))),
+        ("SUM_OF_YEARS", _) => Ok(DepreciationMethod::SumOfYears),
+        _ => Err(StorageError::DatabaseError(format!("Invalid depreciation method: {}", method))),
+    }
+}
+
+/// Fixed assets of the entity matching `filter` (a condition on `id`, or empty), ordered by id.
+fn query_fixed_assets(conn: &Connection, entity_id: &str, filter: &str, values: &[&str]) -> Result<Vec<FixedAsset>, StorageError> {
+    type Row = (String, String, String, u32, String, String, Option<String>, String, String, String, Option<String>, Option<String>);
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, cost, in_service, life_years, salvage, method, factor, asset_account, accumulated_account,
+                expense_account, depreciated_through, disposed_on
+         FROM fixed_assets WHERE entity_id = ?1{filter} ORDER BY id"
+    )).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+    let rows: Vec<Row> = stmt.query_map(
+        params_from_iter(std::iter::once(entity_id).chain(values.iter().copied())),
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?, row.get(6)?, row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?, row.get(11)?)),
+    )
+    .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+    .collect::<Result<Vec<_>, _>>()
+    .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+    let decimal = |s: &str| Decimal::from_str(s).map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)));
+    rows.into_iter()
+        .map(|(id, cost, in_service, life_years, salvage, method, factor, asset_account, accumulated_account, expense_account, depreciated_through, disposed_on)| {
+            Ok(FixedAsset {
+                id: Arc::from(id.as_str()),
+                cost: decimal(&cost)?,
+                in_service: str_to_date(&in_service),
+                life_years,
+                salvage: decimal(&salvage)?,
+                method: str_to_depreciation_method(&method, factor.as_deref())?,
+                asset_account: Arc::from(asset_account.as_str()),
+                accumulated_account: Arc::from(accumulated_account.as_str()),
+                expense_account: Arc::from(expense_account.as_str()),
+                depreciated_through: depreciated_through.as_deref().map(str_to_date),
+                disposed_on: disposed_on.as_deref().map(str_to_date),
+            })
+        })
+        .collect()
+}
+
+fn account_class_to_str(class: AccountClass) -> &'static str {
+    match class {
+        AccountClass::Current => "CURRENT",
+        AccountClass::NonCurrent => "NON_CURRENT",
+    }
+}
+
+fn str_to_account_class(s: &str) -> Option<AccountClass> {
+    match s {
+        "CURRENT" => Some(AccountClass::Current),
+        "NON_CURRENT" => Some(AccountClass::NonCurrent),
+        _ => None,
+    }
+}
+
+fn cash_flow_category_to_str(category: CashFlowCategory) -> &'static str {
+    match category {
+        CashFlowCategory::Cash => "CASH",
+        CashFlowCategory::Operating => "OPERATING",
+        CashFlowCategory::Investing => "INVESTING",
+        CashFlowCategory::Financing => "FINANCING",
+    }
+}
+
+fn str_to_cash_flow_category(s: &str) -> Option<CashFlowCategory> {
+    match s {
+        "CASH" => Some(CashFlowCategory::Cash),
+        "OPERATING" => Some(CashFlowCategory::Operating),
+        "INVESTING" => Some(CashFlowCategory::Investing),
+        "FINANCING" => Some(CashFlowCategory::Financing),
+        _ => None,
     }
 }
 
@@ -195,10 +770,33 @@ fn str_to_account_type(s: &str) -> AccountType {
         "EQUITY" => AccountType::Equity,
         "INCOME" => AccountType::Income,
         "EXPENSE" => AccountType::Expense,
+        "STATISTICAL" => AccountType::Statistical,
         _ => AccountType::Asset,
     }
 }
 
+/// Recursive CTE `subtree` holding the account bound to ?2 and its descendants, so
+/// that balances of parent accounts roll up their children.
+const ACCOUNT_SUBTREE: &str = "WITH RECURSIVE subtree(id) AS (
+    SELECT ?2
+    UNION ALL
+    SELECT a.id FROM accounts a JOIN subtree s ON a.parent_id = s.id WHERE a.entity_id = ?1
+)";
+
+/// Fold per-day movements (ascending) into the balance at each of the ascending `dates`.
+fn running_balances(movements: &[(Date, Decimal)], dates: &[Date]) -> Vec<Decimal> {
+    let mut result = Vec::with_capacity(dates.len());
+    let mut movements = movements.iter().peekable();
+    let mut balance = Decimal::ZERO;
+    for date in dates {
+        while let Some((_, amount)) = movements.next_if(|(day, _)| day <= date) {
+            balance += amount;
+        }
+        result.push(balance);
+    }
+    result
+}
+
 fn escape_like(s: &str) -> String {
     let mut result = String::with_capacity(s.len());
     for c in s.chars() {
@@ -213,6 +811,26 @@ fn escape_like(s: &str) -> String {
     result
 }
 
+/// JOIN clauses keeping only `owner` rows tagged with every dimension in the filter,
+/// with hierarchical prefix matching. Placeholders are numbered from `?{first}` and
+/// the returned values bind in that order after the caller's own parameters.
+fn dimension_joins(dimensions: &DimensionFilter, table: &str, fk: &str, owner: &str, first: usize) -> (String, Vec<String>) {
+    let mut sql = String::new();
+    let mut values = Vec::with_capacity(dimensions.len() * 3);
+    for (i, (key, value)) in dimensions.iter().enumerate() {
+        let p = first + i * 3;
+        sql.push_str(&format!(
+            " JOIN {table} d{i} ON d{i}.{fk} = {owner} AND d{i}.dimension_key = ?{p1}
+               AND (d{i}.dimension_value = ?{p2} OR d{i}.dimension_value LIKE ?{p3} || '/%' ESCAPE '\\')",
+            p1 = p, p2 = p + 1, p3 = p + 2
+        ));
+        let value = data_value_to_str(value);
+        let escaped = escape_like(&value);
+        values.extend([key.to_string(), value, escaped]);
+    }
+    (sql, values)
+}
+
 fn data_value_to_str(dv: &DataValue) -> String {
     match dv {
         DataValue::Null => "null".to_string(),
@@ -229,6 +847,13 @@ fn data_value_to_str(dv: &DataValue) -> String {
         DataValue::Statement(stmt) => format!("{:?}", stmt),
         DataValue::TrialBalance(items) => format!("{:?}", items),
         DataValue::Lots(lots) => format!("{:?}", lots),
+        DataValue::Journals(journals) => format!("{:?}", journals),
+        DataValue::DimensionBalances(items) => format!("{:?}", items),
+        DataValue::BalanceSheet(sheet) => format!("{:?}", sheet),
+        DataValue::CashFlowStatement(statement) => format!("{:?}", statement),
+        DataValue::BalanceSeries(items) => format!("{:?}", items),
+        DataValue::Amortization(items) => format!("{:?}", items),
+        DataValue::DepreciationSchedule(items) => format!("{:?}", items),
     }
 }
 
@@ -257,6 +882,59 @@ impl StorageBackend for SqliteStorage {
         rows.flatten().map(|id| Arc::from(id.as_str())).collect()
     }
 
+    fn lock_period(&self, entity_id: &str, through: Date) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        ensure_period_open(Self::period_lock(&conn, entity_id)?, through)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO period_locks (entity_id, locked_through) VALUES (?1, ?2)",
+            params![entity_id, date_to_str(through)],
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn unlock_period(&self, entity_id: &str) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM period_locks WHERE entity_id = ?1",
+            params![entity_id],
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn get_period_lock(&self, entity_id: &str) -> Result<Option<Date>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        Self::period_lock(&conn, entity_id)
+    }
+
+    fn record_year_close(&self, entity_id: &str, year_end: Date, journal_ids: &[u128]) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let ids: Vec<String> = journal_ids.iter().map(|id| Uuid::from_u128(*id).to_string()).collect();
+        conn.execute(
+            "INSERT OR REPLACE INTO year_closes (entity_id, year_end, journal_ids) VALUES (?1, ?2, ?3)",
+            params![entity_id, date_to_str(year_end), ids.join(",")],
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        Ok(())
+    }
+
+    fn list_year_closes(&self, entity_id: &str) -> Result<Vec<(Date, Vec<u128>)>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT year_end, journal_ids FROM year_closes WHERE entity_id = ?1 ORDER BY year_end")
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let rows = stmt
+            .query_map(params![entity_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let mut closes = Vec::new();
+        for row in rows {
+            let (year_end, ids) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            closes.push((str_to_date(&year_end), parse_journal_ids(&ids)?));
+        }
+        Ok(closes)
+    }
+
     fn entity_exists(&self, entity_id: &str) -> bool {
         let conn = self.conn.lock().unwrap();
         let count: i64 = conn
@@ -272,9 +950,40 @@ impl StorageBackend for SqliteStorage {
     fn create_account(&self, entity_id: &str, account: &AccountExpression) -> Result<(), StorageError> {
         let conn = self.conn.lock().unwrap();
         let unit_rate_id = account.unit_rate_id.as_ref().map(|s| s.to_string());
+        let parent_id = account.parent.as_ref().map(|s| s.to_string());
+        let mut class = account.class.map(account_class_to_str);
+        let mut cash_flow = account.cash_flow.map(cash_flow_category_to_str);
+        let currency = account.currency.as_ref().map(|s| s.to_string());
+        if let Some(ref parent) = parent_id {
+            let (parent_type, parent_unit_rate, parent_class, parent_cash_flow): (String, Option<String>, Option<String>, Option<String>) = conn
+                .query_row(
+                    "SELECT account_type, unit_rate_id, account_class, cash_flow FROM accounts WHERE entity_id = ?1 AND id = ?2",
+                    params![entity_id, parent],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .map_err(|_| StorageError::AccountNotFound(parent.clone()))?;
+            if parent_type != account_type_to_str(&account.account_type) {
+                return Err(StorageError::InvalidParentAccount(format!("@{} must have the same account type as @{}", account.id, parent)));
+            }
+            if parent_unit_rate.is_some() {
+                return Err(StorageError::InvalidParentAccount(format!("@{} is a unit account", parent)));
+            }
+            let has_entries: bool = conn
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM ledger_entries WHERE entity_id = ?1 AND account_id = ?2)",
+                    params![entity_id, parent],
+                    |row| row.get(0),
+                )
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            if has_entries {
+                return Err(StorageError::InvalidParentAccount(format!("@{} already has ledger entries", parent)));
+            }
+            class = class.or(parent_class.as_deref().and_then(str_to_account_class).map(account_class_to_str));
+            cash_flow = cash_flow.or(parent_cash_flow.as_deref().and_then(str_to_cash_flow_category).map(cash_flow_category_to_str));
+        }
         let rows = conn.execute(
-            "INSERT INTO accounts (id, account_type, unit_rate_id, entity_id) VALUES (?1, ?2, ?3, ?4) ON CONFLICT DO NOTHING",
-            params![account.id.as_ref(), account_type_to_str(&account.account_type), unit_rate_id, entity_id],
+            "INSERT INTO accounts (id, account_type, unit_rate_id, entity_id, parent_id, account_class, cash_flow, currency) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8) ON CONFLICT DO NOTHING",
+            params![account.id.as_ref(), account_type_to_str(&account.account_type), unit_rate_id, entity_id, parent_id, class, cash_flow, currency],
         )
         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
         if rows == 0 {
@@ -289,13 +998,21 @@ impl StorageBackend for SqliteStorage {
 
     fn set_rate(&self, entity_id: &str, command: &SetRateCommand) -> Result<(), StorageError> {
         let conn = self.conn.lock().unwrap();
+        ensure_period_open(Self::period_lock(&conn, entity_id)?, command.date)?;
+        let (dimension_key, dimension_value) = match &command.dimension {
+            Some((key, value)) => (key.to_string(), data_value_to_str(value)),
+            None => (String::new(), String::new()),
+        };
         conn.execute(
-            "INSERT OR REPLACE INTO rates (id, date, value, entity_id) VALUES (?1, ?2, ?3, ?4)",
+            "INSERT OR REPLACE INTO rates (id, date, value, entity_id, dimension_key, dimension_value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
             params![
                 command.id.as_ref(),
                 date_to_str(command.date),
                 command.rate.to_string(),
-                entity_id
+                entity_id,
+                dimension_key,
+                dimension_value
             ],
         )
         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
@@ -305,7 +1022,7 @@ impl StorageBackend for SqliteStorage {
     fn get_rate(&self, entity_id: &str, id: &str, date: Date) -> Result<Decimal, StorageError> {
         let conn = self.conn.lock().unwrap();
         let result: Result<String, _> = conn.query_row(
-            "SELECT value FROM rates WHERE entity_id = ?1 AND id = ?2 AND date <= ?3 ORDER BY date DESC LIMIT 1",
+            "SELECT value FROM rates WHERE entity_id = ?1 AND id = ?2 AND dimension_key = '' AND date <= ?3 ORDER BY date DESC LIMIT 1",
             params![entity_id, id, date_to_str(date)],
             |row| row.get(0),
         );
@@ -317,16 +1034,65 @@ impl StorageBackend for SqliteStorage {
         }
     }
 
-    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<(), StorageError> {
+    fn get_rate_changes(&self, entity_id: &str, id: &str, from: Date, to: Date) -> Result<Vec<RateChange>, StorageError> {
         let conn = self.conn.lock().unwrap();
-        let jid = Uuid::new_v4().to_string();
+        // Changes within the period plus, for each dimension value, the last one before it
+        let mut stmt = conn.prepare(
+            "SELECT r.dimension_key, r.dimension_value, r.date, r.value FROM rates r
+             WHERE r.entity_id = ?1 AND r.id = ?2 AND r.date <= ?4
+               AND (r.date > ?3 OR r.date = (
+                   SELECT MAX(p.date) FROM rates p
+                   WHERE p.entity_id = r.entity_id AND p.id = r.id AND p.dimension_key = r.dimension_key
+                     AND p.dimension_value = r.dimension_value AND p.date <= ?3))
+             ORDER BY r.date"
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let rows = stmt.query_map(
+            params![entity_id, id, date_to_str(from), date_to_str(to)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?)),
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut changes = Vec::new();
+        for row in rows {
+            let (key, value, date, rate) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            let rate = Decimal::from_str(&rate)
+                .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))?;
+            changes.push(RateChange {
+                date: str_to_date(&date).max(from),
+                rate,
+                dimension: (!key.is_empty()).then(|| (Arc::from(key.as_str()), Arc::new(DataValue::String(Arc::from(value.as_str()))))),
+            });
+        }
+        Ok(changes)
+    }
+
+    fn create_journal(&self, entity_id: &str, command: &CreateJournalCommand) -> Result<u128, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let journal_uuid = Uuid::new_v4();
+        let jid = journal_uuid.to_string();
         let seq = Self::next_sequence(&conn)?;
         let date_str = date_to_str(command.date);
         let now = OffsetDateTime::now_utc().to_string();
+        let reverses = command.reverses.map(|id| Uuid::from_u128(id).to_string());
+        ensure_period_open(Self::period_lock(&conn, entity_id)?, command.date)?;
+
+        for ledger_entry in &command.ledger_entries {
+            let (LedgerEntryCommand::Debit { account_id, .. } | LedgerEntryCommand::Credit { account_id, .. }) = ledger_entry;
+            let has_children: bool = conn
+                .query_row(
+                    "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND parent_id = ?2",
+                    params![entity_id, account_id.as_ref()],
+                    |row| row.get(0),
+                )
+                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            if has_children {
+                return Err(StorageError::ParentAccountPosting(account_id.to_string()));
+            }
+        }
 
         conn.execute(
-            "INSERT INTO journals (id, sequence, date, description, amount, created_at, entity_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-            params![jid, seq, date_str, command.description.as_ref(), command.amount.to_string(), now, entity_id],
+            "INSERT INTO journals (id, sequence, date, description, amount, created_at, entity_id, reverses_journal_id) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![jid, seq, date_str, command.description.as_ref(), command.amount.to_string(), now, entity_id, reverses],
         ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
         // Insert journal dimensions
@@ -338,10 +1104,10 @@ impl StorageBackend for SqliteStorage {
         }
 
         // Look up account types for sign adjustment
-        for entry in &command.ledger_entries {
-            let (account_id, raw_amount) = match entry {
-                LedgerEntryCommand::Debit { account_id, amount, .. } => (account_id, *amount),
-                LedgerEntryCommand::Credit { account_id, amount, .. } => (account_id, -*amount),
+        for (index, entry) in command.ledger_entries.iter().enumerate() {
+            let (account_id, raw_amount, raw_currency_amount, side) = match entry {
+                LedgerEntryCommand::Debit { account_id, amount, currency_amount, .. } => (account_id, *amount, *currency_amount, "DEBIT"),
+                LedgerEntryCommand::Credit { account_id, amount, currency_amount, .. } => (account_id, -*amount, currency_amount.map(|a| -a), "CREDIT"),
             };
 
             // Get account type for sign convention
@@ -359,20 +1125,22 @@ impl StorageBackend for SqliteStorage {
                 })?;
 
             let acct_type = str_to_account_type(&acct_type_str);
-            let signed_amount = match acct_type {
-                AccountType::Asset | AccountType::Expense => raw_amount,
-                AccountType::Liability | AccountType::Equity | AccountType::Income => -raw_amount,
+            let sign = match acct_type {
+                AccountType::Asset | AccountType::Expense | AccountType::Statistical => Decimal::ONE,
+                AccountType::Liability | AccountType::Equity | AccountType::Income => Decimal::NEGATIVE_ONE,
             };
+            let signed_amount = raw_amount * sign;
+            let signed_currency_amount = raw_currency_amount.map(|a| (a * sign).to_string());
 
             conn.execute(
-                "INSERT INTO ledger_entries (journal_id, account_id, date, amount, entity_id) VALUES (?1, ?2, ?3, ?4, ?5)",
-                params![jid, account_id.as_ref(), date_str, signed_amount.to_string(), entity_id],
+                "INSERT INTO ledger_entries (journal_id, account_id, date, amount, entity_id, currency_amount, side) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![jid, account_id.as_ref(), date_str, signed_amount.to_string(), entity_id, signed_currency_amount, side],
             ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
             let le_id = conn.last_insert_rowid();
 
             // Copy dimensions to ledger entry
-            for (k, v) in &command.dimensions {
+            for (k, v) in command.entry_dimensions(index) {
                 conn.execute(
                     "INSERT INTO ledger_entry_dimensions (ledger_entry_id, dimension_key, dimension_value) VALUES (?1, ?2, ?3)",
                     params![le_id, k.as_ref(), data_value_to_str(v)],
@@ -380,7 +1148,7 @@ impl StorageBackend for SqliteStorage {
             }
 
             // Handle lot creation for debits with units
-            if let LedgerEntryCommand::Debit { account_id, amount, units: Some(unit_count) } = entry {
+            if let LedgerEntryCommand::Debit { account_id, amount, units: Some(unit_count), .. } = entry {
                 let unit_rate_id: Option<String> = conn.query_row(
                     "SELECT unit_rate_id FROM accounts WHERE entity_id = ?1 AND id = ?2",
                     params![entity_id, account_id.as_ref()],
@@ -399,7 +1167,7 @@ impl StorageBackend for SqliteStorage {
                     ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
                     let lot_id = conn.last_insert_rowid();
-                    for (k, v) in &command.dimensions {
+                    for (k, v) in command.entry_dimensions(index) {
                         conn.execute(
                             "INSERT INTO lot_dimensions (lot_id, dimension_key, dimension_value) VALUES (?1, ?2, ?3)",
                             params![lot_id, k.as_ref(), data_value_to_str(v)],
@@ -457,19 +1225,202 @@ impl StorageBackend for SqliteStorage {
             }
         }
 
-        Ok(())
+        Ok(journal_uuid.as_u128())
+    }
+
+    fn get_journal(&self, entity_id: &str, journal_id: u128) -> Result<JournalEntry, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let jid = Uuid::from_u128(journal_id).to_string();
+        Self::read_journals(&conn, "SELECT id FROM journals WHERE entity_id = ?1 AND id = ?2", &[entity_id.to_string(), jid.clone()])?
+            .pop()
+            .ok_or(StorageError::JournalNotFound(jid))
+    }
+
+    fn list_journals(&self, entity_id: &str, from: Date, to: Date, dimensions: &DimensionFilter) -> Result<Vec<JournalEntry>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        let (joins, dim_values) = dimension_joins(dimensions, "journal_dimensions", "journal_id", "j.id", 4);
+        let selection = format!("SELECT j.id FROM journals j{joins} WHERE j.entity_id = ?1 AND j.date >= ?2 AND j.date <= ?3");
+        let mut values = vec![entity_id.to_string(), date_to_str(from), date_to_str(to)];
+        values.extend(dim_values);
+        Self::read_journals(&conn, &selection, &values)
+    }
+
+    fn get_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
+        self.ledger_balance(entity_id, account_id, date, dimensions, "amount")
+    }
+
+    fn get_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError> {
+        self.ledger_statement(entity_id, account_id, from, to, dimensions, "amount")
+    }
+
+    fn get_currency_balance(&self, entity_id: &str, account_id: &str, date: Date, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
+        self.ledger_balance(entity_id, account_id, date, dimensions, "currency_amount")
+    }
+
+    fn get_currency_statement(&self, entity_id: &str, account_id: &str, from: Bound<Date>, to: Bound<Date>, dimensions: &DimensionFilter) -> Result<DataValue, StorageError> {
+        self.ledger_statement(entity_id, account_id, from, to, dimensions, "currency_amount")
+    }
+
+    fn get_dimension_values(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        dimension_key: Arc<str>,
+        from: Date,
+        to: Date,
+    ) -> Result<HashSet<Arc<DataValue>>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT led.dimension_value
+             FROM ledger_entries le
+             JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
+             WHERE le.entity_id = ?1 AND le.account_id = ?2 AND led.dimension_key = ?3
+               AND le.date >= ?4 AND le.date <= ?5"
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let rows = stmt.query_map(
+            params![entity_id, account_id, dimension_key.as_ref(), date_to_str(from), date_to_str(to)],
+            |row| row.get::<_, String>(0),
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = HashSet::new();
+        for row in rows {
+            let val = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            result.insert(Arc::new(DataValue::String(Arc::from(val.as_str()))));
+        }
+        Ok(result)
+    }
+
+    fn get_dimension_combinations(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        dimension_keys: &[Arc<str>],
+        from: Date,
+        to: Date,
+    ) -> Result<HashSet<Vec<Arc<DataValue>>>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
+                params![entity_id, account_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+        if dimension_keys.is_empty() {
+            return Ok(HashSet::from([Vec::new()]));
+        }
+
+        let mut columns = Vec::with_capacity(dimension_keys.len());
+        let mut joins = String::new();
+        for i in 0..dimension_keys.len() {
+            columns.push(format!("d{i}.dimension_value"));
+            joins.push_str(&format!(
+                "\n             JOIN ledger_entry_dimensions d{i} ON d{i}.ledger_entry_id = le.id AND d{i}.dimension_key = ?{}",
+                i + 5
+            ));
+        }
+        let mut stmt = conn.prepare(&format!(
+            "{ACCOUNT_SUBTREE}
+             SELECT DISTINCT {}
+             FROM ledger_entries le{joins}
+             WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date >= ?3 AND le.date <= ?4",
+            columns.join(", ")
+        )).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(from), date_to_str(to)];
+        values.extend(dimension_keys.iter().map(|key| key.to_string()));
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            (0..dimension_keys.len())
+                .map(|i| row.get::<_, String>(i).map(|v| Arc::new(DataValue::String(Arc::from(v.as_str())))))
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = HashSet::new();
+        for row in rows {
+            result.insert(row.map_err(|e| StorageError::DatabaseError(e.to_string()))?);
+        }
+        Ok(result)
+    }
+
+    fn get_dimension_movements(
+        &self,
+        entity_id: &str,
+        account_id: &str,
+        dimension_keys: &[Arc<str>],
+        from: Date,
+        to: Date,
+    ) -> Result<Vec<DimensionMovement>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
+                params![entity_id, account_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let mut columns = String::new();
+        let mut joins = String::new();
+        for i in 0..dimension_keys.len() {
+            columns.push_str(&format!(", d{i}.dimension_value"));
+            joins.push_str(&format!(
+                "\n             JOIN ledger_entry_dimensions d{i} ON d{i}.ledger_entry_id = le.id AND d{i}.dimension_key = ?{}",
+                i + 5
+            ));
+        }
+        // Earlier entries are summed into the opening day rather than returned one by one
+        let mut stmt = conn.prepare(&format!(
+            "{ACCOUNT_SUBTREE}
+             SELECT MAX(le.date, ?3) AS day{columns}, CAST(SUM(le.amount) AS TEXT)
+             FROM ledger_entries le{joins}
+             WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= ?4
+             GROUP BY day{columns}
+             ORDER BY day"
+        )).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(from), date_to_str(to)];
+        values.extend(dimension_keys.iter().map(|key| key.to_string()));
+        let keys = dimension_keys.len();
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            let date = row.get::<_, String>(0)?;
+            let combination = (1..=keys)
+                .map(|i| row.get::<_, String>(i).map(|v| Arc::new(DataValue::String(Arc::from(v.as_str())))))
+                .collect::<Result<Vec<_>, _>>()?;
+            let amount = row.get::<_, String>(keys + 1)?;
+            Ok((date, combination, amount))
+        })
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut result = Vec::new();
+        for row in rows {
+            let (date, combination, amount) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            result.push((str_to_date(&date), combination, Decimal::from_str(&amount).unwrap_or(Decimal::ZERO)));
+        }
+        Ok(result)
     }
 
-    fn get_balance(
+    fn get_balance_series(
         &self,
         entity_id: &str,
         account_id: &str,
-        date: Date,
-        dimension: Option<&(Arc<str>, Arc<DataValue>)>,
-    ) -> Result<Decimal, StorageError> {
+        dates: &[Date],
+        dimensions: &DimensionFilter,
+    ) -> Result<Vec<Decimal>, StorageError> {
         let conn = self.conn.lock().unwrap();
 
-        // Verify account exists
         let exists: bool = conn
             .query_row(
                 "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
@@ -480,53 +1431,46 @@ impl StorageBackend for SqliteStorage {
         if !exists {
             return Err(StorageError::AccountNotFound(account_id.to_string()));
         }
-
-        let date_str = date_to_str(date);
-
-        let total: Decimal = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let mut stmt = conn.prepare(
-                    "SELECT CAST(COALESCE(SUM(le.amount), 0) AS TEXT)
-                     FROM ledger_entries le
-                     JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                     WHERE le.entity_id = ?1 AND le.account_id = ?2 AND le.date <= ?3
-                       AND led.dimension_key = ?4 AND (led.dimension_value = ?5 OR led.dimension_value LIKE ?6 || '/%' ESCAPE '\\')"
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                let val: String = stmt.query_row(
-                    params![entity_id, account_id, date_str, dim_key.as_ref(), dim_val_str, escape_like(&dim_val_str)],
-                    |row| row.get(0),
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                Decimal::from_str(&val).unwrap_or(Decimal::ZERO)
-            }
-            None => {
-                let mut stmt = conn.prepare(
-                    "SELECT CAST(COALESCE(SUM(le.amount), 0) AS TEXT)
-                     FROM ledger_entries le
-                     WHERE le.entity_id = ?1 AND le.account_id = ?2 AND le.date <= ?3"
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                let val: String = stmt.query_row(
-                    params![entity_id, account_id, date_str],
-                    |row| row.get(0),
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                Decimal::from_str(&val).unwrap_or(Decimal::ZERO)
-            }
+        let Some(last) = dates.last() else {
+            return Ok(Vec::new());
         };
 
-        Ok(total)
+        // One query for the daily movements up to the last date, folded into running balances
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), date_to_str(*last)];
+        values.extend(dim_values);
+        let mut stmt = conn.prepare(&format!(
+            "{ACCOUNT_SUBTREE}
+             SELECT le.date, CAST(SUM(le.amount) AS TEXT)
+             FROM ledger_entries le{joins}
+             WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date <= ?3
+             GROUP BY le.date
+             ORDER BY le.date"
+        )).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let rows = stmt.query_map(
+            params_from_iter(values),
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+
+        let mut movements = Vec::new();
+        for row in rows {
+            let (date, amount) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            movements.push((str_to_date(&date), Decimal::from_str(&amount).unwrap_or(Decimal::ZERO)));
+        }
+        Ok(running_balances(&movements, dates))
     }
 
-    fn get_statement(
+    fn get_turnover(
         &self,
         entity_id: &str,
         account_id: &str,
         from: Bound<Date>,
-        to: Bound<Date>,
-        dimension: Option<&(Arc<str>, Arc<DataValue>)>,
-    ) -> Result<DataValue, StorageError> {
+        to: Date,
+        dimensions: &DimensionFilter,
+    ) -> Result<(Decimal, Decimal), StorageError> {
         let conn = self.conn.lock().unwrap();
 
-        // Verify account exists
         let exists: bool = conn
             .query_row(
                 "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
@@ -538,149 +1482,93 @@ impl StorageBackend for SqliteStorage {
             return Err(StorageError::AccountNotFound(account_id.to_string()));
         }
 
-        // Get the opening balance
-        let balance_date = match from {
-            Bound::Included(d) => d.previous_day().unwrap_or(d),
-            Bound::Excluded(d) => d,
-            Bound::Unbounded => Date::MIN,
-        };
-
-        // Build date range conditions
         let (from_op, from_str) = match from {
             Bound::Included(d) => (">=", date_to_str(d)),
             Bound::Excluded(d) => (">", date_to_str(d)),
             Bound::Unbounded => (">=", "0000-01-01".to_string()),
         };
-        let (to_op, to_str) = match to {
-            Bound::Included(d) => ("<=", date_to_str(d)),
-            Bound::Excluded(d) => ("<", date_to_str(d)),
-            Bound::Unbounded => ("<=", "9999-12-31".to_string()),
-        };
 
-        // Calculate opening balance
-        let mut opening_balance = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let val: String = conn.query_row(
-                    "SELECT CAST(COALESCE(SUM(le.amount), 0) AS TEXT)
+        // Entries are stored in the account's normal sign; flip credit-normal accounts
+        // back so that positive amounts are debits, then count each on the side it was
+        // posted on. Entries from before the side was recorded fall back to their sign.
+        let (joins, dim_values) = dimension_joins(dimensions, "ledger_entry_dimensions", "ledger_entry_id", "le.id", 5);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), from_str, date_to_str(to)];
+        values.extend(dim_values);
+        let (debits, credits): (String, String) = conn.query_row(
+            &format!(
+                "{ACCOUNT_SUBTREE},
+                 posted(amount, side) AS (
+                     SELECT CASE WHEN a.account_type IN ('ASSET', 'EXPENSE', 'STATISTICAL') THEN CAST(le.amount AS NUMERIC) ELSE -CAST(le.amount AS NUMERIC) END, le.side
                      FROM ledger_entries le
-                     JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                     WHERE le.entity_id = ?1 AND le.account_id = ?2 AND le.date <= ?3
-                       AND led.dimension_key = ?4 AND (led.dimension_value = ?5 OR led.dimension_value LIKE ?6 || '/%' ESCAPE '\\')",
-                    params![entity_id, account_id, date_to_str(balance_date), dim_key.as_ref(), dim_val_str, escape_like(&dim_val_str)],
-                    |row| row.get(0),
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                Decimal::from_str(&val).unwrap_or(Decimal::ZERO)
-            }
-            None => {
-                let val: String = conn.query_row(
-                    "SELECT CAST(COALESCE(SUM(le.amount), 0) AS TEXT)
-                     FROM ledger_entries le
-                     WHERE le.entity_id = ?1 AND le.account_id = ?2 AND le.date <= ?3",
-                    params![entity_id, account_id, date_to_str(balance_date)],
-                    |row| row.get(0),
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                Decimal::from_str(&val).unwrap_or(Decimal::ZERO)
-            }
-        };
-
-        // Fetch entries in the date range
-        let query = match dimension {
-            Some(_) => format!(
-                "SELECT le.journal_id, le.date, j.description, le.amount
-                 FROM ledger_entries le
-                 JOIN journals j ON j.id = le.journal_id
-                 JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-                 WHERE le.entity_id = ?1 AND le.account_id = ?2 AND le.date {} ?3 AND le.date {} ?4
-                   AND led.dimension_key = ?5 AND (led.dimension_value = ?6 OR led.dimension_value LIKE ?7 || '/%' ESCAPE '\\')
-                 ORDER BY le.date, le.id",
-                from_op, to_op
-            ),
-            None => format!(
-                "SELECT le.journal_id, le.date, j.description, le.amount
-                 FROM ledger_entries le
-                 JOIN journals j ON j.id = le.journal_id
-                 WHERE le.entity_id = ?1 AND le.account_id = ?2 AND le.date {} ?3 AND le.date {} ?4
-                 ORDER BY le.date, le.id",
-                from_op, to_op
+                     JOIN accounts a ON a.entity_id = le.entity_id AND a.id = le.account_id{joins}
+                     WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND le.date {from_op} ?3 AND le.date <= ?4
+                 )
+                 SELECT CAST(COALESCE(SUM(CASE WHEN side = 'DEBIT' OR (side IS NULL AND amount > 0) THEN amount ELSE 0 END), 0) AS TEXT),
+                        CAST(COALESCE(SUM(CASE WHEN side = 'CREDIT' OR (side IS NULL AND amount < 0) THEN -amount ELSE 0 END), 0) AS TEXT)
+                 FROM posted"
             ),
-        };
-
-        let mut stmt = conn.prepare(&query).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-
-        let row_mapper = |row: &rusqlite::Row| -> rusqlite::Result<(String, String, String, String)> {
-            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
-        };
-
-        let rows: Vec<(String, String, String, String)> = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                stmt.query_map(
-                    params![entity_id, account_id, from_str, to_str, dim_key.as_ref(), dim_val_str, escape_like(&dim_val_str)],
-                    row_mapper,
-                )
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-            }
-            None => {
-                stmt.query_map(
-                    params![entity_id, account_id, from_str, to_str],
-                    row_mapper,
-                )
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-            }
-        };
-
-        let mut result = Vec::new();
-        for (jid_str, date_str_row, desc, amt_str) in rows {
-            let amount = Decimal::from_str(&amt_str).unwrap_or(Decimal::ZERO);
-            opening_balance += amount;
-            let journal_id = Uuid::parse_str(&jid_str)
-                .map(|u| u.as_u128())
-                .unwrap_or(0);
-            result.push(StatementTxn {
-                journal_id,
-                date: str_to_date(&date_str_row),
-                description: Arc::from(desc.as_str()),
-                amount,
-                balance: opening_balance,
-            });
-        }
+            params_from_iter(values),
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
-        Ok(DataValue::Statement(result))
+        Ok((
+            Decimal::from_str(&debits).unwrap_or(Decimal::ZERO),
+            Decimal::from_str(&credits).unwrap_or(Decimal::ZERO),
+        ))
     }
 
-    fn get_dimension_values(
+    fn get_balances_by_dimension(
         &self,
         entity_id: &str,
         account_id: &str,
-        dimension_key: Arc<str>,
-        from: Date,
+        dimension_key: &str,
+        from: Bound<Date>,
         to: Date,
-    ) -> Result<HashSet<Arc<DataValue>>, StorageError> {
+    ) -> Result<Vec<(Arc<DataValue>, Decimal)>, StorageError> {
         let conn = self.conn.lock().unwrap();
 
-        let mut stmt = conn.prepare(
-            "SELECT DISTINCT led.dimension_value
+        let exists: bool = conn
+            .query_row(
+                "SELECT COUNT(*) > 0 FROM accounts WHERE entity_id = ?1 AND id = ?2",
+                params![entity_id, account_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if !exists {
+            return Err(StorageError::AccountNotFound(account_id.to_string()));
+        }
+
+        let (from_op, from_str) = match from {
+            Bound::Included(d) => (">=", date_to_str(d)),
+            Bound::Excluded(d) => (">", date_to_str(d)),
+            Bound::Unbounded => (">=", "0000-01-01".to_string()),
+        };
+
+        let mut stmt = conn.prepare(&format!(
+            "{ACCOUNT_SUBTREE}
+             SELECT led.dimension_value, CAST(SUM(le.amount) AS TEXT)
              FROM ledger_entries le
              JOIN ledger_entry_dimensions led ON led.ledger_entry_id = le.id
-             WHERE le.entity_id = ?1 AND le.account_id = ?2 AND led.dimension_key = ?3
-               AND le.date >= ?4 AND le.date <= ?5"
-        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+             WHERE le.entity_id = ?1 AND le.account_id IN (SELECT id FROM subtree) AND led.dimension_key = ?3
+               AND le.date {} ?4 AND le.date <= ?5
+             GROUP BY led.dimension_value
+             ORDER BY led.dimension_value",
+            from_op
+        )).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
         let rows = stmt.query_map(
-            params![entity_id, account_id, dimension_key.as_ref(), date_to_str(from), date_to_str(to)],
-            |row| row.get::<_, String>(0),
+            params![entity_id, account_id, dimension_key, from_str, date_to_str(to)],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
         )
         .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
-        let mut result = HashSet::new();
+        let mut result = Vec::new();
         for row in rows {
-            let val = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-            result.insert(Arc::new(DataValue::String(Arc::from(val.as_str()))));
+            let (value, amount) = row.map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+            result.push((
+                Arc::new(DataValue::String(Arc::from(value.as_str()))),
+                Decimal::from_str(&amount).unwrap_or(Decimal::ZERO),
+            ));
         }
         Ok(result)
     }
@@ -756,47 +1644,79 @@ impl StorageBackend for SqliteStorage {
         Ok(())
     }
 
-    fn get_lots(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Vec<LotItem>, StorageError> {
+    fn create_fixed_asset(&self, entity_id: &str, asset: &FixedAsset) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let (method, factor) = depreciation_method_to_str(&asset.method);
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO fixed_assets (entity_id, id, cost, in_service, life_years, salvage, method, factor,
+                asset_account, accumulated_account, expense_account, depreciated_through, disposed_on)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                entity_id,
+                asset.id.as_ref(),
+                asset.cost.to_string(),
+                date_to_str(asset.in_service),
+                asset.life_years,
+                asset.salvage.to_string(),
+                method,
+                factor,
+                asset.asset_account.as_ref(),
+                asset.accumulated_account.as_ref(),
+                asset.expense_account.as_ref(),
+                asset.depreciated_through.map(date_to_str),
+                asset.disposed_on.map(date_to_str),
+            ],
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if inserted == 0 {
+            return Err(StorageError::DuplicateFixedAsset(asset.id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn get_fixed_asset(&self, entity_id: &str, asset_id: &str) -> Result<FixedAsset, StorageError> {
         let conn = self.conn.lock().unwrap();
+        query_fixed_assets(&conn, entity_id, " AND id = ?2", &[asset_id])?
+            .pop()
+            .ok_or_else(|| StorageError::FixedAssetNotFound(asset_id.to_string()))
+    }
 
-        let lot_rows: Vec<(i64, String, String, String)> = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                let mut stmt = conn.prepare(
-                    "SELECT l.id, l.date, l.units_remaining, l.cost_per_unit FROM lots l
-                     WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0
-                       AND EXISTS (
-                         SELECT 1 FROM lot_dimensions ld
-                         WHERE ld.lot_id = l.id AND ld.dimension_key = ?3
-                           AND (ld.dimension_value = ?4 OR ld.dimension_value LIKE ?5 || '/%' ESCAPE '\\')
-                       )
-                     ORDER BY l.date ASC"
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                let rows = stmt.query_map(
-                    params![entity_id, account_id, dim_key.as_ref(), dim_val_str, escape_like(&dim_val_str)],
-                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
-                )
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                rows
-            }
-            None => {
-                let mut stmt = conn.prepare(
-                    "SELECT l.id, l.date, l.units_remaining, l.cost_per_unit FROM lots l
-                     WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0
-                     ORDER BY l.date ASC"
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                let rows = stmt.query_map(
-                    params![entity_id, account_id],
-                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
-                )
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-                rows
-            }
-        };
+    fn list_fixed_assets(&self, entity_id: &str) -> Result<Vec<FixedAsset>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+        query_fixed_assets(&conn, entity_id, "", &[])
+    }
+
+    fn update_fixed_asset(&self, entity_id: &str, asset_id: &str, depreciated_through: Option<Date>, disposed_on: Option<Date>) -> Result<(), StorageError> {
+        let conn = self.conn.lock().unwrap();
+        let updated = conn.execute(
+            "UPDATE fixed_assets SET depreciated_through = ?3, disposed_on = ?4 WHERE entity_id = ?1 AND id = ?2",
+            params![entity_id, asset_id, depreciated_through.map(date_to_str), disposed_on.map(date_to_str)],
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        if updated == 0 {
+            return Err(StorageError::FixedAssetNotFound(asset_id.to_string()));
+        }
+        Ok(())
+    }
+
+    fn get_lots(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Vec<LotItem>, StorageError> {
+        let conn = self.conn.lock().unwrap();
+
+        let (joins, dim_values) = dimension_joins(dimensions, "lot_dimensions", "lot_id", "l.id", 3);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT l.id, l.date, l.units_remaining, l.cost_per_unit FROM lots l{joins}
+             WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0
+             ORDER BY l.date ASC"
+        )).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
+        let mut values = vec![entity_id.to_string(), account_id.to_string()];
+        values.extend(dim_values);
+        let lot_rows: Vec<(i64, String, String, String)> = stmt.query_map(
+            params_from_iter(values),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
         let mut result = Vec::new();
         for (lot_id, date_str, units_str, cpu_str) in lot_rows {
@@ -831,40 +1751,28 @@ impl StorageBackend for SqliteStorage {
         Ok(result)
     }
 
-    fn get_total_units(&self, entity_id: &str, account_id: &str, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<Decimal, StorageError> {
+    fn get_total_units(&self, entity_id: &str, account_id: &str, dimensions: &DimensionFilter) -> Result<Decimal, StorageError> {
         let conn = self.conn.lock().unwrap();
 
-        let total_str: String = match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                conn.query_row(
-                    "SELECT CAST(COALESCE(SUM(CAST(l.units_remaining AS REAL)), 0) AS TEXT) FROM lots l
-                     WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0
-                       AND EXISTS (
-                         SELECT 1 FROM lot_dimensions ld
-                         WHERE ld.lot_id = l.id AND ld.dimension_key = ?3
-                           AND (ld.dimension_value = ?4 OR ld.dimension_value LIKE ?5 || '/%' ESCAPE '\\')
-                       )",
-                    params![entity_id, account_id, dim_key.as_ref(), dim_val_str, escape_like(&dim_val_str)],
-                    |row| row.get(0),
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?
-            }
-            None => {
-                conn.query_row(
-                    "SELECT CAST(COALESCE(SUM(CAST(l.units_remaining AS REAL)), 0) AS TEXT) FROM lots l
-                     WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0",
-                    params![entity_id, account_id],
-                    |row| row.get(0),
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?
-            }
-        };
+        let (joins, dim_values) = dimension_joins(dimensions, "lot_dimensions", "lot_id", "l.id", 3);
+        let mut values = vec![entity_id.to_string(), account_id.to_string()];
+        values.extend(dim_values);
+        let total_str: String = conn.query_row(
+            &format!(
+                "SELECT CAST(COALESCE(SUM(CAST(l.units_remaining AS REAL)), 0) AS TEXT) FROM lots l{joins}
+                 WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0"
+            ),
+            params_from_iter(values),
+            |row| row.get(0),
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
         Decimal::from_str(&total_str)
             .map_err(|e| StorageError::DatabaseError(format!("Invalid decimal: {}", e)))
     }
 
-    fn deplete_lots(&self, entity_id: &str, account_id: &str, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError> {
+    fn deplete_lots(&self, entity_id: &str, account_id: &str, date: Date, units: Decimal, method: &CostMethod, dimensions: &BTreeMap<Arc<str>, Arc<DataValue>>) -> Result<Decimal, StorageError> {
         let conn = self.conn.lock().unwrap();
+        ensure_period_open(Self::period_lock(&conn, entity_id)?, date)?;
 
         let order = match method {
             CostMethod::Fifo => "ASC",
@@ -1010,35 +1918,25 @@ impl StorageBackend for SqliteStorage {
         }
     }
 
-    fn split_lots(&self, entity_id: &str, account_id: &str, new_per_old: Decimal, dimension: Option<&(Arc<str>, Arc<DataValue>)>) -> Result<(), StorageError> {
+    fn split_lots(&self, entity_id: &str, account_id: &str, date: Date, new_per_old: Decimal, dimensions: &DimensionFilter) -> Result<(), StorageError> {
         let conn = self.conn.lock().unwrap();
+        ensure_period_open(Self::period_lock(&conn, entity_id)?, date)?;
 
-        match dimension {
-            Some((dim_key, dim_val)) => {
-                let dim_val_str = data_value_to_str(dim_val);
-                conn.execute(
-                    "UPDATE lots SET
-                        units_remaining = CAST(CAST(units_remaining AS REAL) * CAST(?3 AS REAL) AS TEXT),
-                        cost_per_unit = CAST(CAST(cost_per_unit AS REAL) / CAST(?3 AS REAL) AS TEXT)
-                     WHERE entity_id = ?1 AND account_id = ?2 AND CAST(units_remaining AS REAL) > 0
-                       AND EXISTS (
-                         SELECT 1 FROM lot_dimensions ld
-                         WHERE ld.lot_id = lots.id AND ld.dimension_key = ?4
-                           AND (ld.dimension_value = ?5 OR ld.dimension_value LIKE ?6 || '/%' ESCAPE '\\')
-                       )",
-                    params![entity_id, account_id, new_per_old.to_string(), dim_key.as_ref(), dim_val_str, escape_like(&dim_val_str)],
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-            }
-            None => {
-                conn.execute(
-                    "UPDATE lots SET
-                        units_remaining = CAST(CAST(units_remaining AS REAL) * CAST(?3 AS REAL) AS TEXT),
-                        cost_per_unit = CAST(CAST(cost_per_unit AS REAL) / CAST(?3 AS REAL) AS TEXT)
-                     WHERE entity_id = ?1 AND account_id = ?2 AND CAST(units_remaining AS REAL) > 0",
-                    params![entity_id, account_id, new_per_old.to_string()],
-                ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
-            }
-        }
+        let (joins, dim_values) = dimension_joins(dimensions, "lot_dimensions", "lot_id", "l.id", 4);
+        let mut values = vec![entity_id.to_string(), account_id.to_string(), new_per_old.to_string()];
+        values.extend(dim_values);
+        conn.execute(
+            &format!(
+                "UPDATE lots SET
+                    units_remaining = CAST(CAST(units_remaining AS REAL) * CAST(?3 AS REAL) AS TEXT),
+                    cost_per_unit = CAST(CAST(cost_per_unit AS REAL) / CAST(?3 AS REAL) AS TEXT)
+                 WHERE id IN (
+                     SELECT l.id FROM lots l{joins}
+                     WHERE l.entity_id = ?1 AND l.account_id = ?2 AND CAST(l.units_remaining AS REAL) > 0
+                 )"
+            ),
+            params_from_iter(values),
+        ).map_err(|e| StorageError::DatabaseError(e.to_string()))?;
 
         Ok(())
     }
@@ -1056,6 +1954,68 @@ impl StorageBackend for SqliteStorage {
         }
     }
 
+    fn get_parent_account(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>> {
+        let conn = self.conn.lock().unwrap();
+        let result: Result<Option<String>, _> = conn.query_row(
+            "SELECT parent_id FROM accounts WHERE entity_id = ?1 AND id = ?2",
+            params![entity_id, account_id],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(Some(id)) => Some(Arc::from(id.as_str())),
+            _ => None,
+        }
+    }
+
+    fn get_account_type(&self, entity_id: &str, account_id: &str) -> Option<AccountType> {
+        let conn = self.conn.lock().unwrap();
+        let result: Result<String, _> = conn.query_row(
+            "SELECT account_type FROM accounts WHERE entity_id = ?1 AND id = ?2",
+            params![entity_id, account_id],
+            |row| row.get(0),
+        );
+        result.ok().map(|account_type| str_to_account_type(&account_type))
+    }
+
+    fn get_account_class(&self, entity_id: &str, account_id: &str) -> Option<AccountClass> {
+        let conn = self.conn.lock().unwrap();
+        let result: Result<Option<String>, _> = conn.query_row(
+            "SELECT account_class FROM accounts WHERE entity_id = ?1 AND id = ?2",
+            params![entity_id, account_id],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(Some(class)) => str_to_account_class(&class),
+            _ => None,
+        }
+    }
+
+    fn get_cash_flow_category(&self, entity_id: &str, account_id: &str) -> Option<CashFlowCategory> {
+        let conn = self.conn.lock().unwrap();
+        let result: Result<Option<String>, _> = conn.query_row(
+            "SELECT cash_flow FROM accounts WHERE entity_id = ?1 AND id = ?2",
+            params![entity_id, account_id],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(Some(category)) => str_to_cash_flow_category(&category),
+            _ => None,
+        }
+    }
+
+    fn get_account_currency(&self, entity_id: &str, account_id: &str) -> Option<Arc<str>> {
+        let conn = self.conn.lock().unwrap();
+        let result: Result<Option<String>, _> = conn.query_row(
+            "SELECT currency FROM accounts WHERE entity_id = ?1 AND id = ?2",
+            params![entity_id, account_id],
+            |row| row.get(0),
+        );
+        match result {
+            Ok(Some(currency)) => Some(Arc::from(currency.as_str())),
+            _ => None,
+        }
+    }
+
     fn is_unit_account(&self, entity_id: &str, account_id: &str) -> bool {
         let conn = self.conn.lock().unwrap();
         let result: Result<bool, _> = conn.query_row(
@@ -1082,6 +2042,10 @@ mod tests {
                 id: Arc::from("bank"),
                 account_type: AccountType::Asset,
                 unit_rate_id: None,
+                parent: None,
+                class: None,
+                cash_flow: None,
+                currency: None,
             })
             .unwrap();
         storage
@@ -1089,6 +2053,10 @@ mod tests {
                 id: Arc::from("equity"),
                 account_type: AccountType::Equity,
                 unit_rate_id: None,
+                parent: None,
+                class: None,
+                cash_flow: None,
+                currency: None,
             })
             .unwrap();
 
@@ -1103,25 +2071,29 @@ mod tests {
                     account_id: Arc::from("equity"),
                     amount: Decimal::from(1000),
                     units: None,
+                    currency_amount: None,
                 },
                 LedgerEntryCommand::Debit {
                     account_id: Arc::from("bank"),
                     amount: Decimal::from(1000),
                     units: None,
+                    currency_amount: None,
                 },
             ],
             dimensions: BTreeMap::new(),
+            leg_dimensions: BTreeMap::new(),
+            reverses: None,
         };
         storage.create_journal("default", &cmd).unwrap();
 
         // Check balance
         let bal = storage
-            .get_balance("default", "bank", date, None)
+            .get_balance("default", "bank", date, &DimensionFilter::new())
             .unwrap();
         assert_eq!(bal, Decimal::from(1000));
 
         let eq_bal = storage
-            .get_balance("default", "equity", date, None)
+            .get_balance("default", "equity", date, &DimensionFilter::new())
             .unwrap();
         assert_eq!(eq_bal, Decimal::from(1000));
     }
@@ -1135,6 +2107,10 @@ mod tests {
                 id: Arc::from("bank"),
                 account_type: AccountType::Asset,
                 unit_rate_id: None,
+                parent: None,
+                class: None,
+                cash_flow: None,
+                currency: None,
             })
             .unwrap();
         storage
@@ -1142,6 +2118,10 @@ mod tests {
                 id: Arc::from("equity"),
                 account_type: AccountType::Equity,
                 unit_rate_id: None,
+                parent: None,
+                class: None,
+                cash_flow: None,
+                currency: None,
             })
             .unwrap();
 
@@ -1158,20 +2138,75 @@ mod tests {
                         account_id: Arc::from("equity"),
                         amount: Decimal::from(500),
                         units: None,
+                        currency_amount: None,
                     },
                     LedgerEntryCommand::Debit {
                         account_id: Arc::from("bank"),
                         amount: Decimal::from(500),
                         units: None,
+                        currency_amount: None,
                     },
                 ],
                 dimensions: BTreeMap::new(),
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
             })
             .unwrap();
         storage.rollback_transaction(tx_id).unwrap();
 
-        let bal = storage.get_balance("default", "bank", date, None).unwrap();
+        let bal = storage.get_balance("default", "bank", date, &DimensionFilter::new()).unwrap();
         assert_eq!(bal, Decimal::ZERO, "Balance should be 0 after rollback");
     }
-}
 
+    #[test]
+    fn test_sqlite_get_journal_recovers_legs() {
+        let storage = SqliteStorage::new(":memory:").unwrap();
+        for (id, account_type) in [("bank", AccountType::Asset), ("equity", AccountType::Equity)] {
+            storage
+                .create_account("default", &AccountExpression {
+                    id: Arc::from(id),
+                    account_type,
+                    unit_rate_id: None,
+                    parent: None,
+                    class: None,
+                    cash_flow: None,
+                    currency: None,
+                })
+                .unwrap();
+        }
+
+        let date = Date::from_calendar_date(2023, Month::January, 1).unwrap();
+        let jid = storage
+            .create_journal("default", &CreateJournalCommand {
+                date,
+                description: Arc::from("Investment"),
+                amount: Decimal::from(750),
+                ledger_entries: vec![
+                    LedgerEntryCommand::Debit {
+                        account_id: Arc::from("bank"),
+                        amount: Decimal::from(750),
+                        units: None,
+                        currency_amount: None,
+                    },
+                    LedgerEntryCommand::Credit {
+                        account_id: Arc::from("equity"),
+                        amount: Decimal::from(750),
+                        units: None,
+                        currency_amount: None,
+                    },
+                ],
+                dimensions: BTreeMap::new(),
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
+            })
+            .unwrap();
+
+        let journal = storage.get_journal("default", jid).unwrap();
+        assert_eq!(journal.lines, vec![
+            JournalLine { account_id: Arc::from("bank"), debit: Decimal::from(750), credit: Decimal::ZERO, currency_amount: None, dimensions: None },
+            JournalLine { account_id: Arc::from("equity"), debit: Decimal::ZERO, credit: Decimal::from(750), currency_amount: None, dimensions: None },
+        ]);
+        assert_ne!(journal.created_at, OffsetDateTime::UNIX_EPOCH);
+        assert!(matches!(storage.get_journal("default", jid + 1), Err(StorageError::JournalNotFound(_))));
+    }
+}
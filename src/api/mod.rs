@@ -1,7 +1,21 @@
+use axum::http::StatusCode;
 use serde::Serialize;
 
+use crate::evaluator::EvaluationError;
+use crate::storage::StorageError;
+
 pub mod v1;
 
+/// HTTP status for a failed FQL execution.
+pub fn execution_error_status(e: &EvaluationError) -> StatusCode {
+    match e {
+        EvaluationError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+        EvaluationError::StorageError(StorageError::JournalNotFound(_)) => StatusCode::NOT_FOUND,
+        EvaluationError::StorageError(StorageError::PeriodLocked(_)) => StatusCode::CONFLICT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 /// Text-format FQL response (backwards-compatible with unversioned `/fql` endpoint).
 #[derive(Serialize)]
 pub struct TextFqlResponse {
@@ -6,10 +6,12 @@ use axum::{
     response::IntoResponse,
     Extension, Json,
 };
+use dblentry_core::params::bind_params;
 use metrics::{counter, histogram};
 
 use crate::{
-    api::{TextFqlResponse, TextFqlMetadata},
+    api::{execution_error_status, TextFqlResponse, TextFqlMetadata},
+    auth::CallerIdentity,
     display::format_execution_result,
     evaluator::QueryVariables,
     idempotency::{IdempotencyStore, IdempotencyCheck},
@@ -19,7 +21,7 @@ use crate::{
 
 use super::mappers;
 
-use super::types::{BatchFqlRequest, BatchFqlResponse, BatchResultEntry, FqlMetadataDto, ResultEntryDto};
+use super::types::{ApiErrorDto, BatchFqlRequest, BatchFqlResponse, BatchResultEntry, FqlMetadataDto, FqlRequestV1, ResultEntryDto};
 
 fn wants_json(headers: &HeaderMap) -> bool {
     headers
@@ -35,11 +37,63 @@ fn set_idempotency_header(response: &mut axum::response::Response, key: &str) {
     }
 }
 
+fn is_json_body(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.starts_with("application/json"))
+        .unwrap_or(false)
+}
+
+/// Split a request body into FQL text and bound parameters.
+/// JSON bodies carry `{"query": ..., "params": {...}}`; anything else is raw FQL.
+fn read_fql_body(headers: &HeaderMap, body: &str) -> Result<(String, QueryVariables), ApiErrorDto> {
+    if !is_json_body(headers) {
+        return Ok((body.to_string(), QueryVariables::new()));
+    }
+    let req: FqlRequestV1 = serde_json::from_str(body).map_err(|e| ApiErrorDto {
+        code: "INVALID_REQUEST".to_string(),
+        message: format!("Invalid JSON body: {}", e),
+        details: None,
+    })?;
+    let variables = bind_params(&req.params).map_err(|e| mappers::map_param_error(&e))?;
+    Ok((req.query, variables))
+}
+
+fn bad_request_response(
+    headers: &HeaderMap,
+    idempotency: &IdempotencyStore,
+    idempotency_key: Option<&str>,
+    error: ApiErrorDto,
+) -> axum::response::Response {
+    if wants_json(headers) {
+        let resp = mappers::error_response(error);
+        let json_value = serde_json::to_value(&resp).unwrap();
+        if let Some(key) = idempotency_key {
+            idempotency.set(key.to_string(), json_value.clone(), 400);
+        }
+        let mut response = (StatusCode::BAD_REQUEST, Json(json_value)).into_response();
+        if let Some(key) = idempotency_key {
+            set_idempotency_header(&mut response, key);
+        }
+        response
+    } else {
+        let resp = TextFqlResponse {
+            success: false,
+            results: vec![],
+            error: Some(error.message),
+            metadata: TextFqlMetadata { statements_executed: 0, journals_created: 0 },
+        };
+        (StatusCode::BAD_REQUEST, Json(resp)).into_response()
+    }
+}
+
 pub async fn fql_handler_v1(
     State(exec): State<Arc<StatementExecutor>>,
     Extension(idempotency): Extension<Arc<IdempotencyStore>>,
+    Extension(caller): Extension<CallerIdentity>,
     headers: HeaderMap,
-    query: String,
+    body: String,
 ) -> impl IntoResponse {
     counter!("fql_requests_total", 1);
     let start = std::time::Instant::now();
@@ -70,6 +124,15 @@ pub async fn fql_handler_v1(
         }
     }
 
+    let (query, variables) = match read_fql_body(&headers, &body) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            tracing::warn!("Invalid FQL request: {}", error.message);
+            counter!("fql_errors_total", 1, "type" => "request");
+            return bad_request_response(&headers, &idempotency, idempotency_key.as_deref(), error);
+        }
+    };
+
     let statements = match lexer::parse(&query) {
         Ok(s) => s,
         Err(e) => {
@@ -77,32 +140,14 @@ pub async fn fql_handler_v1(
             counter!("fql_errors_total", 1, "type" => "parse");
             let duration = start.elapsed();
             histogram!("fql_request_duration_seconds", duration.as_secs_f64());
-
-            if wants_json(&headers) {
-                let resp = mappers::error_response(mappers::map_parse_error(&format!("{}", e)));
-                let json_value = serde_json::to_value(&resp).unwrap();
-                if let Some(ref key) = idempotency_key {
-                    idempotency.set(key.clone(), json_value.clone(), 400);
-                }
-                let mut response = (StatusCode::BAD_REQUEST, Json(json_value)).into_response();
-                if let Some(ref key) = idempotency_key {
-                    set_idempotency_header(&mut response, key);
-                }
-                return response;
-            } else {
-                let resp = TextFqlResponse {
-                    success: false,
-                    results: vec![],
-                    error: Some(format!("Parse error: {}", e)),
-                    metadata: TextFqlMetadata { statements_executed: 0, journals_created: 0 },
-                };
-                return (StatusCode::BAD_REQUEST, Json(resp)).into_response();
-            }
+            let error = mappers::map_parse_error(&format!("{}", e));
+            return bad_request_response(&headers, &idempotency, idempotency_key.as_deref(), error);
         }
     };
 
     let eff_date = time::OffsetDateTime::now_utc().date();
-    let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+    let mut context = ExecutionContext::new(eff_date, variables);
+    context.role = caller.role;
 
     match exec.execute_script(&mut context, &statements) {
         Ok(script_results) => {
@@ -156,14 +201,15 @@ pub async fn fql_handler_v1(
             counter!("fql_errors_total", 1, "type" => "execution");
             let duration = start.elapsed();
             histogram!("fql_request_duration_seconds", duration.as_secs_f64());
+            let status = execution_error_status(&e);
 
             if wants_json(&headers) {
                 let resp = mappers::error_response(mappers::map_evaluation_error(&e));
                 let json_value = serde_json::to_value(&resp).unwrap();
                 if let Some(ref key) = idempotency_key {
-                    idempotency.set(key.clone(), json_value.clone(), 500);
+                    idempotency.set(key.clone(), json_value.clone(), status.as_u16());
                 }
-                let mut response = (StatusCode::INTERNAL_SERVER_ERROR, Json(json_value)).into_response();
+                let mut response = (status, Json(json_value)).into_response();
                 if let Some(ref key) = idempotency_key {
                     set_idempotency_header(&mut response, key);
                 }
@@ -175,7 +221,7 @@ pub async fn fql_handler_v1(
                     error: Some(format!("{}", e)),
                     metadata: TextFqlMetadata { statements_executed: 0, journals_created: 0 },
                 };
-                (StatusCode::INTERNAL_SERVER_ERROR, Json(resp)).into_response()
+                (status, Json(resp)).into_response()
             }
         }
     }
@@ -183,6 +229,7 @@ pub async fn fql_handler_v1(
 
 pub async fn batch_fql_handler(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Json(req): Json<BatchFqlRequest>,
 ) -> impl IntoResponse {
     counter!("fql_requests_total", 1);
@@ -197,8 +244,21 @@ pub async fn batch_fql_handler(
         }));
     }
 
+    let variables = match bind_params(&req.params) {
+        Ok(v) => v,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(BatchFqlResponse {
+                success: false,
+                results: vec![],
+                error: Some(e),
+                metadata: FqlMetadataDto { statements_executed: 0, journals_created: 0 },
+            }));
+        }
+    };
+
     let eff_date = time::OffsetDateTime::now_utc().date();
-    let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+    let mut context = ExecutionContext::new(eff_date, variables);
+    context.role = caller.role;
     let mut results = Vec::new();
     let mut total_journals = 0usize;
     let mut total_statements = 0usize;
@@ -277,7 +337,7 @@ pub async fn batch_fql_handler(
                 let duration = start.elapsed();
                 histogram!("fql_request_duration_seconds", duration.as_secs_f64());
                 counter!("fql_errors_total", 1, "type" => "execution");
-                return (StatusCode::INTERNAL_SERVER_ERROR, Json(BatchFqlResponse {
+                return (execution_error_status(&e), Json(BatchFqlResponse {
                     success: false,
                     results: vec![],
                     error: Some(format!("{}", e)),
@@ -291,6 +351,7 @@ pub async fn batch_fql_handler(
             match lexer::parse(&entry.fql) {
                 Ok(stmts) => {
                     let mut entry_context = ExecutionContext::new(eff_date, context.variables.clone());
+                    entry_context.role = caller.role;
                     match exec.execute_script(&mut entry_context, &stmts) {
                         Ok(script_results) => {
                             let mut entry_data = Vec::new();
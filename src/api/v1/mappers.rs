@@ -1,8 +1,9 @@
-use dblentry_core::models::{AccountType, DataValue, LotItem, StatementTxn, TrialBalanceItem};
+use dblentry_core::models::{read::JournalEntry, BalanceSheet, CashFlowStatement, DataValue, LotItem, StatementTxn, TrialBalanceItem};
 use dblentry_core::storage::StorageError;
 
 use crate::display::format_data_value;
 use crate::evaluator::EvaluationError;
+use crate::functions::format_journal_id;
 use crate::statement_executor::ExecutionResult;
 
 use super::types::*;
@@ -68,18 +69,112 @@ pub fn map_data_value(value: &DataValue) -> DataValueDto {
         DataValue::Lots(lots) => {
             DataValueDto::Lots(lots.iter().map(map_lot_item).collect())
         }
+        DataValue::Journals(journals) => {
+            DataValueDto::Journals(journals.iter().map(map_journal).collect())
+        }
+        DataValue::DimensionBalances(items) => {
+            DataValueDto::DimensionBalances(
+                items.iter()
+                    .map(|item| DimensionBalanceDto {
+                        dimension: item.dimension.to_string(),
+                        value: format_data_value(&item.value),
+                        amount: item.amount.to_string(),
+                    })
+                    .collect(),
+            )
+        }
+        DataValue::BalanceSheet(sheet) => DataValueDto::BalanceSheet(map_balance_sheet(sheet)),
+        DataValue::CashFlowStatement(statement) => DataValueDto::CashFlowStatement(map_cash_flow_statement(statement)),
+        DataValue::BalanceSeries(items) => DataValueDto::BalanceSeries(
+            items.iter()
+                .map(|item| BalanceSeriesItemDto {
+                    period_end: item.period_end.to_string(),
+                    balance: item.balance.to_string(),
+                    change: item.change.to_string(),
+                })
+                .collect(),
+        ),
+        DataValue::Amortization(items) => DataValueDto::Amortization(
+            items.iter()
+                .map(|item| AmortizationItemDto {
+                    number: item.number,
+                    date: item.date.to_string(),
+                    payment: item.payment.to_string(),
+                    interest: item.interest.to_string(),
+                    principal: item.principal.to_string(),
+                    balance: item.balance.to_string(),
+                })
+                .collect(),
+        ),
+        DataValue::DepreciationSchedule(items) => DataValueDto::DepreciationSchedule(
+            items.iter()
+                .map(|item| DepreciationItemDto {
+                    period_end: item.period_end.to_string(),
+                    depreciation: item.depreciation.to_string(),
+                    accumulated: item.accumulated.to_string(),
+                    book_value: item.book_value.to_string(),
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn enum_str<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.as_str().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+fn map_balance_sheet(sheet: &BalanceSheet) -> BalanceSheetDto {
+    BalanceSheetDto {
+        date: sheet.date.to_string(),
+        sections: sheet.sections.iter()
+            .map(|section| BalanceSheetSectionDto {
+                account_type: enum_str(&section.account_type),
+                class: section.class.as_ref().map(enum_str),
+                lines: section.lines.iter()
+                    .map(|line| BalanceSheetLineDto {
+                        account_id: line.account_id.to_string(),
+                        balance: line.balance.to_string(),
+                    })
+                    .collect(),
+                subtotal: section.subtotal.to_string(),
+            })
+            .collect(),
+        total_assets: sheet.total_assets.to_string(),
+        total_liabilities: sheet.total_liabilities.to_string(),
+        total_equity: sheet.total_equity.to_string(),
+        balanced: sheet.balanced,
+    }
+}
+
+fn map_cash_flow_statement(statement: &CashFlowStatement) -> CashFlowStatementDto {
+    CashFlowStatementDto {
+        from: statement.from.to_string(),
+        to: statement.to.to_string(),
+        net_income: statement.net_income.to_string(),
+        sections: statement.sections.iter()
+            .map(|section| CashFlowSectionDto {
+                category: enum_str(&section.category),
+                lines: section.lines.iter()
+                    .map(|line| CashFlowLineDto {
+                        account_id: line.account_id.to_string(),
+                        amount: line.amount.to_string(),
+                    })
+                    .collect(),
+                subtotal: section.subtotal.to_string(),
+            })
+            .collect(),
+        net_change: statement.net_change.to_string(),
+        opening_cash: statement.opening_cash.to_string(),
+        closing_cash: statement.closing_cash.to_string(),
+        reconciled: statement.reconciled,
     }
 }
 
 fn map_trial_balance_item(item: &TrialBalanceItem) -> TrialBalanceItemDto {
-    let (debit, credit) = match item.account_type {
-        AccountType::Asset | AccountType::Expense => {
-            (Some(item.balance.to_string()), None)
-        }
-        AccountType::Liability | AccountType::Equity | AccountType::Income => {
-            (None, Some(item.balance.to_string()))
-        }
-    };
+    let (debit, credit) = item.debit_credit();
 
     TrialBalanceItemDto {
         account_id: item.account_id.to_string(),
@@ -88,14 +183,16 @@ fn map_trial_balance_item(item: &TrialBalanceItem) -> TrialBalanceItemDto {
             .and_then(|v| v.as_str().map(|s| s.to_string()))
             .unwrap_or_default(),
         balance: item.balance.to_string(),
-        debit,
-        credit,
+        debit: debit.map(|d| d.to_string()),
+        credit: credit.map(|c| c.to_string()),
+        depth: item.depth,
+        subtotal: item.subtotal,
     }
 }
 
 fn map_statement_txn(txn: &StatementTxn) -> StatementTxnDto {
     StatementTxnDto {
-        journal_id: txn.journal_id.to_string(),
+        journal_id: format_journal_id(txn.journal_id),
         date: txn.date.to_string(),
         description: txn.description.to_string(),
         amount: txn.amount.to_string(),
@@ -115,6 +212,28 @@ fn map_lot_item(lot: &LotItem) -> LotItemDto {
     }
 }
 
+fn map_journal(journal: &JournalEntry) -> JournalDto {
+    JournalDto {
+        id: format_journal_id(journal.id),
+        sequence: journal.sequence,
+        date: journal.date.to_string(),
+        description: journal.description.to_string(),
+        amount: journal.amount.to_string(),
+        dimensions: journal.dimensions.iter()
+            .map(|(k, v)| (k.to_string(), format_data_value(v)))
+            .collect(),
+        lines: journal.lines.iter()
+            .map(|line| JournalLineDto {
+                account_id: line.account_id.to_string(),
+                debit: line.debit.to_string(),
+                credit: line.credit.to_string(),
+            })
+            .collect(),
+        reverses: journal.reverses.map(format_journal_id),
+        reversed_by: journal.reversed_by.map(format_journal_id),
+    }
+}
+
 pub fn error_response(error: ApiErrorDto) -> FqlResponseV1 {
     FqlResponseV1 {
         success: false,
@@ -150,9 +269,10 @@ pub fn map_evaluation_error(e: &EvaluationError) -> ApiErrorDto {
         },
         EvaluationError::UnknownFunction(name) => {
             let known = vec![
-                "balance", "statement", "trial_balance", "income_statement",
+                "balance", "statement", "trial_balance", "income_statement", "balance_sheet", "cash_flow_statement",
                 "account_count", "convert", "fx_rate", "round", "abs", "min",
                 "max", "units", "market_value", "unrealized_gain", "cost_basis", "lots",
+                "journal", "journals", "balance_by", "activity_by", "debits", "credits", "activity", "balance_series", "average_balance", "depreciation_schedule",
             ];
             let suggestion = find_closest_match(name, &known);
             ApiErrorDto {
@@ -181,6 +301,11 @@ pub fn map_evaluation_error(e: &EvaluationError) -> ApiErrorDto {
             message: e.to_string(),
             details: None,
         },
+        EvaluationError::PermissionDenied(_) => ApiErrorDto {
+            code: "FORBIDDEN".to_string(),
+            message: e.to_string(),
+            details: None,
+        },
         EvaluationError::General(msg) => ApiErrorDto {
             code: "GENERAL_ERROR".to_string(),
             message: msg.clone(),
@@ -201,6 +326,11 @@ pub fn map_storage_error(e: &StorageError) -> ApiErrorDto {
             message: e.to_string(),
             details: None,
         },
+        StorageError::JournalNotFound(_) => ApiErrorDto {
+            code: "JOURNAL_NOT_FOUND".to_string(),
+            message: e.to_string(),
+            details: None,
+        },
         StorageError::EntityNotFound(_) => ApiErrorDto {
             code: "ENTITY_NOT_FOUND".to_string(),
             message: e.to_string(),
@@ -221,6 +351,21 @@ pub fn map_storage_error(e: &StorageError) -> ApiErrorDto {
             message: e.to_string(),
             details: None,
         },
+        StorageError::InvalidParentAccount(_) => ApiErrorDto {
+            code: "INVALID_PARENT_ACCOUNT".to_string(),
+            message: e.to_string(),
+            details: None,
+        },
+        StorageError::ParentAccountPosting(_) => ApiErrorDto {
+            code: "PARENT_ACCOUNT_POSTING".to_string(),
+            message: e.to_string(),
+            details: None,
+        },
+        StorageError::PeriodLocked(_) => ApiErrorDto {
+            code: "PERIOD_LOCKED".to_string(),
+            message: e.to_string(),
+            details: None,
+        },
         StorageError::DatabaseError(_) => ApiErrorDto {
             code: "STORAGE_ERROR".to_string(),
             message: "Internal storage error".to_string(),
@@ -234,6 +379,14 @@ pub fn map_storage_error(e: &StorageError) -> ApiErrorDto {
     }
 }
 
+pub fn map_param_error(error_str: &str) -> ApiErrorDto {
+    ApiErrorDto {
+        code: "INVALID_PARAMETER".to_string(),
+        message: error_str.to_string(),
+        details: None,
+    }
+}
+
 pub fn map_parse_error(error_str: &str) -> ApiErrorDto {
     let mut line = None;
     let mut column = None;
@@ -382,6 +535,13 @@ mod tests {
         assert!(err.message.contains("something went wrong"));
     }
 
+    #[test]
+    fn test_map_evaluation_error_permission_denied() {
+        let err = map_evaluation_error(&EvaluationError::PermissionDenied("role 'reader' cannot run CREATE JOURNAL".to_string()));
+        assert_eq!(err.code, "FORBIDDEN");
+        assert!(err.message.contains("reader"));
+    }
+
     #[test]
     fn test_map_evaluation_error_unknown_identifier() {
         let err = map_evaluation_error(&EvaluationError::UnknownIdentifier("x".to_string()));
@@ -394,6 +554,12 @@ mod tests {
         assert_eq!(err.code, "RATE_NOT_FOUND");
     }
 
+    #[test]
+    fn test_map_storage_error_journal_not_found() {
+        let err = map_storage_error(&StorageError::JournalNotFound("abc".to_string()));
+        assert_eq!(err.code, "JOURNAL_NOT_FOUND");
+    }
+
     #[test]
     fn test_map_storage_error_no_active_transaction() {
         let err = map_storage_error(&StorageError::NoActiveTransaction);
@@ -425,6 +591,13 @@ mod tests {
         assert_eq!(err.code, "STORAGE_ERROR");
     }
 
+    #[test]
+    fn test_map_param_error() {
+        let err = map_param_error("parameter $amount: invalid money value 'abc'");
+        assert_eq!(err.code, "INVALID_PARAMETER");
+        assert!(err.message.contains("$amount"));
+    }
+
     #[test]
     fn test_levenshtein_exact() {
         assert_eq!(levenshtein("hello", "hello"), 0);
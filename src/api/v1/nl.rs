@@ -11,6 +11,7 @@ use std::sync::Arc;
 use axum::{extract::State, Extension};
 #[cfg(feature = "nl")]
 use crate::{
+    auth::CallerIdentity,
     display::format_execution_result,
     config::NlConfig,
     evaluator::QueryVariables,
@@ -114,6 +115,7 @@ pub async fn nl_handler(
     State(exec): State<Arc<StatementExecutor>>,
     Extension(nl_state): Extension<NlState>,
     Extension(schema_state): Extension<SchemaState>,
+    Extension(caller): Extension<CallerIdentity>,
     Json(req): Json<NlRequest>,
 ) -> impl IntoResponse {
     if !nl_state.config.enabled {
@@ -204,6 +206,7 @@ pub async fn nl_handler(
             Ok(statements) => {
                 let eff_date = time::OffsetDateTime::now_utc().date();
                 let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+                context.role = caller.role;
                 if let Some(ref entity) = req.entity {
                     context.entity_id = Arc::from(entity.as_str());
                 }
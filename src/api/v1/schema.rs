@@ -10,6 +10,7 @@ use serde::Serialize;
 
 use crate::function_registry::FunctionRegistry;
 use crate::storage::StorageBackend;
+use dblentry_core::AccountClass;
 
 #[derive(Clone)]
 pub struct SchemaState {
@@ -37,6 +38,14 @@ pub struct AccountInfo {
     pub is_unit_account: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unit_rate_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// `current` or `non_current` for classified asset and liability accounts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    /// `cash`, `operating`, `investing` or `financing` for tagged accounts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cash_flow: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -48,10 +57,12 @@ pub struct FunctionInfo {
 
 fn get_function_info(name: &str) -> FunctionInfo {
     let (signature, description) = match name {
-        "balance" => ("balance(@account, date, [dimension])", "Get account balance at a date"),
-        "statement" => ("statement(@account, from, to, [dimension])", "Get transaction statement for a period"),
-        "trial_balance" => ("trial_balance(date)", "Get all account balances at a date"),
+        "balance" => ("balance(@account, date, [transaction | functional], [dimension, ...])", "Get account balance at a date"),
+        "statement" => ("statement(@account, from, to, [transaction | functional], [dimension, ...])", "Get transaction statement for a period"),
+        "trial_balance" => ("trial_balance(date, [tree])", "Get all account balances at a date"),
         "income_statement" => ("income_statement(from, to)", "Get income and expense totals for a period"),
+        "balance_sheet" => ("balance_sheet(date, [dimension, ...])", "Get assets, liabilities and equity at a date"),
+        "cash_flow_statement" => ("cash_flow_statement(from, to)", "Get operating, investing and financing cash flows for a period"),
         "account_count" => ("account_count()", "Get total number of accounts"),
         "convert" => ("convert(amount, rate_id, date)", "Convert amount using a rate at a date"),
         "fx_rate" => ("fx_rate(rate_id, date)", "Get rate value at a date"),
@@ -59,11 +70,21 @@ fn get_function_info(name: &str) -> FunctionInfo {
         "abs" => ("abs(value)", "Absolute value"),
         "min" => ("min(a, b)", "Minimum of two values"),
         "max" => ("max(a, b)", "Maximum of two values"),
-        "units" => ("units(@account, date, [dimension])", "Get total units held in account"),
-        "market_value" => ("market_value(@account, date, [dimension])", "Get market value (units × rate)"),
-        "unrealized_gain" => ("unrealized_gain(@account, date, [dimension])", "Get unrealized gain (market value - cost basis)"),
-        "cost_basis" => ("cost_basis(@account, date, [dimension])", "Get weighted average cost per unit"),
-        "lots" => ("lots(@account, date, [dimension])", "Get individual lot positions"),
+        "units" => ("units(@account, date, [dimension, ...])", "Get total units held in account"),
+        "market_value" => ("market_value(@account, date, [dimension, ...])", "Get market value (units × rate)"),
+        "unrealized_gain" => ("unrealized_gain(@account, date, [dimension, ...])", "Get unrealized gain (market value - cost basis)"),
+        "cost_basis" => ("cost_basis(@account, date, [dimension, ...])", "Get weighted average cost per unit"),
+        "lots" => ("lots(@account, date, [dimension, ...])", "Get individual lot positions"),
+        "journal" => ("journal(id)", "Get a journal with its debit/credit legs"),
+        "journals" => ("journals(from, to, [dimension, ...])", "Get all journals in a period with their legs"),
+        "balance_by" => ("balance_by(@account, date, key)", "Get balance per value of a dimension"),
+        "balance_series" => ("balance_series(@account, from, to, period, [dimension, ...])", "Get balance and change at each DAILY/MONTHLY/QUARTERLY/YEARLY period end"),
+        "average_balance" => ("average_balance(@account, from, to, [dimension, ...])", "Get the average end-of-day balance over a period"),
+        "depreciation_schedule" => ("depreciation_schedule(asset)", "Get a fixed asset's monthly depreciation, accumulated depreciation and book value"),
+        "debits" => ("debits(@account, from, to, [dimension, ...])", "Get total debits posted in a period"),
+        "credits" => ("credits(@account, from, to, [dimension, ...])", "Get total credits posted in a period"),
+        "activity" => ("activity(@account, from, to, [dimension, ...])", "Get net movement in a period"),
+        "activity_by" => ("activity_by(@account, from, to, key)", "Get net movement per value of a dimension for a period"),
         _ => (name, "Custom function"),
     };
     FunctionInfo {
@@ -122,11 +143,22 @@ pub async fn schema_entity(
                 .storage
                 .get_unit_rate_id(&entity_id, &id_str)
                 .map(|r| r.to_string());
+            let parent_id = schema_state
+                .storage
+                .get_parent_account(&entity_id, &id_str)
+                .map(|p| p.to_string());
             AccountInfo {
                 id: id_str,
                 account_type: format!("{:?}", account_type).to_lowercase(),
                 is_unit_account: is_unit,
                 unit_rate_id,
+                parent_id,
+                class: schema_state.storage.get_account_class(&entity_id, id.as_ref()).map(|c| match c {
+                    AccountClass::Current => "current".to_string(),
+                    AccountClass::NonCurrent => "non_current".to_string(),
+                }),
+                cash_flow: schema_state.storage.get_cash_flow_category(&entity_id, id.as_ref())
+                    .map(|c| format!("{:?}", c).to_lowercase()),
             }
         })
         .collect();
@@ -1,5 +1,15 @@
+use dblentry_core::params::FqlParams;
 use serde::{Deserialize, Serialize};
 
+/// JSON body form of `/api/v1/fql`, sent with `Content-Type: application/json`.
+#[derive(Deserialize)]
+pub struct FqlRequestV1 {
+    pub query: String,
+    /// Typed values bound to `$name` references in the query.
+    #[serde(default)]
+    pub params: FqlParams,
+}
+
 #[derive(Serialize)]
 pub struct ApiErrorDto {
     pub code: String,
@@ -65,6 +75,20 @@ pub enum DataValueDto {
     TrialBalance(Vec<TrialBalanceItemDto>),
     #[serde(rename = "lots")]
     Lots(Vec<LotItemDto>),
+    #[serde(rename = "journals")]
+    Journals(Vec<JournalDto>),
+    #[serde(rename = "dimension_balances")]
+    DimensionBalances(Vec<DimensionBalanceDto>),
+    #[serde(rename = "balance_sheet")]
+    BalanceSheet(BalanceSheetDto),
+    #[serde(rename = "cash_flow_statement")]
+    CashFlowStatement(CashFlowStatementDto),
+    #[serde(rename = "balance_series")]
+    BalanceSeries(Vec<BalanceSeriesItemDto>),
+    #[serde(rename = "amortization")]
+    Amortization(Vec<AmortizationItemDto>),
+    #[serde(rename = "depreciation_schedule")]
+    DepreciationSchedule(Vec<DepreciationItemDto>),
 }
 
 #[derive(Serialize)]
@@ -76,6 +100,91 @@ pub struct TrialBalanceItemDto {
     pub debit: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub credit: Option<String>,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub depth: usize,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub subtotal: bool,
+}
+
+#[derive(Serialize)]
+pub struct BalanceSheetDto {
+    pub date: String,
+    pub sections: Vec<BalanceSheetSectionDto>,
+    pub total_assets: String,
+    pub total_liabilities: String,
+    pub total_equity: String,
+    pub balanced: bool,
+}
+
+#[derive(Serialize)]
+pub struct BalanceSheetSectionDto {
+    pub account_type: String,
+    /// `current` or `non_current`; absent for unclassified sections.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class: Option<String>,
+    pub lines: Vec<BalanceSheetLineDto>,
+    pub subtotal: String,
+}
+
+#[derive(Serialize)]
+pub struct BalanceSheetLineDto {
+    pub account_id: String,
+    pub balance: String,
+}
+
+#[derive(Serialize)]
+pub struct CashFlowStatementDto {
+    pub from: String,
+    pub to: String,
+    pub net_income: String,
+    pub sections: Vec<CashFlowSectionDto>,
+    pub net_change: String,
+    pub opening_cash: String,
+    pub closing_cash: String,
+    pub reconciled: bool,
+}
+
+#[derive(Serialize)]
+pub struct CashFlowSectionDto {
+    /// `operating`, `investing` or `financing`.
+    pub category: String,
+    pub lines: Vec<CashFlowLineDto>,
+    pub subtotal: String,
+}
+
+#[derive(Serialize)]
+pub struct CashFlowLineDto {
+    pub account_id: String,
+    pub amount: String,
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+#[derive(Serialize)]
+pub struct BalanceSeriesItemDto {
+    pub period_end: String,
+    pub balance: String,
+    pub change: String,
+}
+
+#[derive(Serialize)]
+pub struct DepreciationItemDto {
+    pub period_end: String,
+    pub depreciation: String,
+    pub accumulated: String,
+    pub book_value: String,
+}
+
+#[derive(Serialize)]
+pub struct AmortizationItemDto {
+    pub number: u32,
+    pub date: String,
+    pub payment: String,
+    pub interest: String,
+    pub principal: String,
+    pub balance: String,
 }
 
 #[derive(Serialize)]
@@ -109,6 +218,36 @@ pub struct LotItemDto {
     pub dimensions: std::collections::HashMap<String, String>,
 }
 
+#[derive(Serialize)]
+pub struct DimensionBalanceDto {
+    pub dimension: String,
+    pub value: String,
+    pub amount: String,
+}
+
+#[derive(Serialize)]
+pub struct JournalDto {
+    pub id: String,
+    pub sequence: u64,
+    pub date: String,
+    pub description: String,
+    pub amount: String,
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub dimensions: std::collections::BTreeMap<String, String>,
+    pub lines: Vec<JournalLineDto>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reverses: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reversed_by: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct JournalLineDto {
+    pub account_id: String,
+    pub debit: String,
+    pub credit: String,
+}
+
 #[derive(Serialize)]
 pub struct FqlMetadataDto {
     pub statements_executed: usize,
@@ -124,6 +263,9 @@ pub struct BatchFqlRequest {
     pub statements: Vec<BatchStatementEntry>,
     #[serde(default = "default_true")]
     pub transaction: bool,
+    /// Typed values bound to `$name` references in every statement.
+    #[serde(default)]
+    pub params: FqlParams,
 }
 
 #[derive(Deserialize)]
@@ -3,7 +3,7 @@ use std::{sync::Arc, collections::BTreeMap};
 use time::Date;
 
 // Re-export from dblentry-core so all existing crate::ast::AccountType references work
-pub use dblentry_core::models::{AccountType, AccountExpression, CostMethod};
+pub use dblentry_core::models::{AccountClass, AccountType, CashFlowCategory, AccountExpression, CostMethod};
 
 
 #[derive(Debug, Clone, PartialEq)]
@@ -13,8 +13,19 @@ pub enum Statement{
     Set(SetCommand),
     Accrue(AccrueCommand),
     Distribute(DistributeCommand),
+    Allocate(AllocateCommand),
+    Amortize(AmortizeCommand),
+    Depreciate(DepreciateCommand),
+    Dispose(DisposeCommand),
     Sell(SellCommand),
     Split(SplitCommand),
+    Reverse(ReverseCommand),
+    Let(LetCommand),
+    /// Close all dates on or before the given date to further changes.
+    LockPeriod(Expression),
+    /// Reopen a locked period.
+    Unlock,
+    CloseYear(CloseYearCommand),
     UseEntity(Arc<str>),
     Begin,
     Commit,
@@ -26,6 +37,7 @@ pub enum CreateCommand {
     Account(AccountExpression),
     Journal(JournalExpression),
     Rate(CreateRateExpression),
+    Asset(CreateAssetExpression),
     Entity(Arc<str>),
 }
 
@@ -37,30 +49,106 @@ pub enum SetCommand {
 #[derive(Debug, Clone, PartialEq)]
 pub struct AccrueCommand {
     pub account_id: Arc<str>,
-    pub rate_id: Arc<str>,
+    /// Annual rate; identifiers that are not query variables name rates, e.g. `prime + 2.5%`.
+    pub rate: Expression,
     pub compounding: Option<Compounding>,
+    /// `ON AVERAGE BALANCE`: simple interest on the average daily balance instead of
+    /// accruing day by day.
+    pub average_balance: bool,
+    /// Defaults to ACT/365F.
+    pub day_count: Option<DayCount>,
     pub start_date: Expression,
     pub end_date: Expression,
-    pub by_dimension: Arc<str>,
+    /// One journal per distinct combination of these dimensions' values; empty accrues
+    /// on the whole account balance.
+    pub by_dimensions: Vec<Arc<str>>,
     pub into_journal: IntoJournalExpression,
 }
 
+/// CLOSE YEAR ENDING date INTO @equity [BY dimension]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloseYearCommand {
+    pub year_end: Expression,
+    pub retained_earnings: Arc<str>,
+    /// Close each value of this dimension in its own journal.
+    pub by_dimension: Option<Arc<str>>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct DistributeCommand {
     pub amount: Expression,
-    pub start_date: Expression,
-    pub end_date: Expression,
-    pub period: Period,
-    pub prorate: bool,
+    pub schedule: DistributeSchedule,
     pub dimensions: BTreeMap<Arc<str>, Expression>,
     pub operations: Vec<LedgerOperation>,
     pub description: Expression,
 }
 
+/// When DISTRIBUTE posts and how much goes in each journal.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DistributeSchedule {
+    /// FROM start TO end PERIOD p: one journal at the end of each period.
+    Periods {
+        start_date: Expression,
+        end_date: Expression,
+        period: Period,
+        /// Count periods from the start date instead of cutting on calendar boundaries.
+        anchored: bool,
+        prorate: bool,
+    },
+    /// ON [dates] WEIGHTS [weights]: one journal per date, split by weight or evenly.
+    Dates {
+        dates: Expression,
+        weights: Option<Expression>,
+    },
+}
+
+/// ALLOCATE amount ON date ACROSS dimension BY driver: split an amount over the values of a
+/// dimension, one journal per value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllocateCommand {
+    pub amount: Expression,
+    pub date: Expression,
+    pub dimension: Arc<str>,
+    pub driver: AllocationDriver,
+    pub dimensions: BTreeMap<Arc<str>, Expression>,
+    pub operations: Vec<LedgerOperation>,
+    pub description: Expression,
+}
+
+/// How ALLOCATE weighs the values of its dimension.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AllocationDriver {
+    /// WEIGHTS {value: weight, ...}
+    Weights(Vec<(Expression, Expression)>),
+    /// A per-value table such as balance_by(@revenue, date, Department).
+    Expression(Expression),
+}
+
+/// AMORTIZE principal AT RATE rate OVER n period FROM date: level payments split into
+/// interest and principal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmortizeCommand {
+    pub principal: Expression,
+    /// Annual rate; identifiers that are not query variables name rates.
+    pub rate: Expression,
+    pub payments: Expression,
+    pub period: Period,
+    /// The loan start; the first payment falls one period later.
+    pub start_date: Expression,
+    pub dimensions: BTreeMap<Arc<str>, Expression>,
+    /// Return the schedule without posting.
+    pub preview: bool,
+    /// Payment journal description and legs, which read `payment`, `interest` and `principal`.
+    pub journal: Option<(Expression, Vec<LedgerOperation>)>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Period {
+    Daily,
+    Weekly,
     Monthly,
     Quarterly,
+    SemiAnnually,
     Yearly,
 }
 
@@ -68,6 +156,24 @@ pub enum Period {
 pub enum Compounding {
     Continuous,
     Daily,
+    Monthly,
+    Quarterly,
+    Annually,
+    /// Interest never earns interest within the run.
+    Simple,
+}
+
+/// How much of a year one day of interest represents.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DayCount {
+    /// ACT/365F: every day is 1/365 of a year.
+    Act365Fixed,
+    /// ACT/360: every day is 1/360 of a year.
+    Act360,
+    /// ACT/ACT: 1/365, or 1/366 in leap years.
+    ActAct,
+    /// 30/360: every month counts as 30 days.
+    Thirty360,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -87,6 +193,7 @@ pub enum LedgerOperation {
 pub struct LedgerOperationData {
     pub account: Arc<str>,
     pub amount: Option<Expression>,
+    pub functional_amount: Option<Expression>,
     pub unit_spec: Option<UnitSpec>,
 }
 
@@ -109,6 +216,19 @@ pub struct SellCommand {
     pub dimensions: Vec<(Arc<str>, Expression)>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReverseCommand {
+    pub journal_id: Expression,
+    pub date: Expression,
+    pub description: Option<Expression>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LetCommand {
+    pub name: Arc<str>,
+    pub value: Expression,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct SplitCommand {
     pub account: Arc<str>,
@@ -124,6 +244,49 @@ pub struct JournalExpression {
     pub amount: Expression,
     pub operations: Vec<LedgerOperation>,
     pub dimensions: BTreeMap<Arc<str>, Expression>,
+    pub auto_reverse: Option<Expression>,
+}
+
+/// CREATE ASSET id IN SERVICE date COST amount LIFE years YEARS [SALVAGE amount] [METHOD ...]
+/// ACCOUNT @asset ACCUMULATED @contra EXPENSE @expense
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateAssetExpression {
+    pub id: Arc<str>,
+    pub in_service: Expression,
+    pub cost: Expression,
+    pub life_years: Expression,
+    pub salvage: Option<Expression>,
+    pub method: DepreciationMethodExpression,
+    pub asset_account: Arc<str>,
+    pub accumulated_account: Arc<str>,
+    pub expense_account: Arc<str>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DepreciationMethodExpression {
+    StraightLine,
+    /// Rate factor; defaults to 2 (double-declining).
+    DecliningBalance(Option<Expression>),
+    SumOfYears,
+}
+
+/// DEPRECIATE [asset] THROUGH date
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepreciateCommand {
+    /// Every asset in the register when not given.
+    pub asset: Option<Arc<str>>,
+    pub through: Expression,
+}
+
+/// DISPOSE ASSET id AT proceeds ON date PROCEEDS @account GAIN_LOSS @account DESCRIPTION text
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisposeCommand {
+    pub asset: Arc<str>,
+    pub proceeds: Expression,
+    pub date: Expression,
+    pub proceeds_account: Arc<str>,
+    pub gain_loss_account: Arc<str>,
+    pub description: Expression,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -136,6 +299,8 @@ pub struct SetRateExpression {
     pub id: Arc<str>,
     pub date: Expression,
     pub rate: Expression,
+    /// `FOR key=value`: the value applies only to entries tagged with it.
+    pub dimension: Option<(Arc<str>, Expression)>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
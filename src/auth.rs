@@ -7,13 +7,129 @@ use axum::{
 use serde::Serialize;
 use subtle::ConstantTimeEq;
 
+use crate::ast::{CreateCommand, SetCommand, Statement};
 use crate::config::AuthConfig;
 
+/// Caller role, ordered from least to most privileged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Reader,
+    Writer,
+    Admin,
+}
+
+impl Role {
+    /// Parse a configured role name. Unknown names fall back to `Reader`.
+    pub fn parse(s: &str) -> Self {
+        match s.to_ascii_lowercase().as_str() {
+            "admin" => Role::Admin,
+            "writer" => Role::Writer,
+            _ => Role::Reader,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Reader => "reader",
+            Role::Writer => "writer",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// True if this role has at least the privileges of `required`.
+    pub fn permits(self, required: Role) -> bool {
+        self >= required
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Minimum role needed to execute a statement.
+///
+/// Readers only run GET, and AMORTIZE ... PREVIEW, which posts nothing. Scripts
+/// already run in their own transaction and requests carry their entity, so LET,
+/// USE ENTITY and explicit transactions are left to writers.
+pub fn required_role(statement: &Statement) -> Role {
+    match statement {
+        Statement::Amortize(a) if a.preview => Role::Reader,
+        Statement::Get(_) => Role::Reader,
+        Statement::Let(_)
+        | Statement::UseEntity(_)
+        | Statement::Begin
+        | Statement::Commit
+        | Statement::Rollback
+        | Statement::Create(CreateCommand::Journal(_))
+        | Statement::Create(CreateCommand::Rate(_))
+        | Statement::Set(SetCommand::Rate(_))
+        | Statement::Accrue(_)
+        | Statement::Distribute(_)
+        | Statement::Allocate(_)
+        | Statement::Amortize(_)
+        | Statement::Create(CreateCommand::Asset(_))
+        | Statement::Depreciate(_)
+        | Statement::Dispose(_)
+        | Statement::Sell(_)
+        | Statement::Split(_)
+        | Statement::Reverse(_) => Role::Writer,
+        Statement::Create(CreateCommand::Entity(_))
+        | Statement::Create(CreateCommand::Account(_))
+        | Statement::LockPeriod(_)
+        | Statement::Unlock
+        | Statement::CloseYear(_) => Role::Admin,
+    }
+}
+
+/// Short human-readable name of a statement, used in permission errors.
+pub fn statement_name(statement: &Statement) -> &'static str {
+    match statement {
+        Statement::Get(_) => "GET",
+        Statement::Let(_) => "LET",
+        Statement::UseEntity(_) => "USE ENTITY",
+        Statement::Begin => "BEGIN",
+        Statement::Commit => "COMMIT",
+        Statement::Rollback => "ROLLBACK",
+        Statement::Create(CreateCommand::Journal(_)) => "CREATE JOURNAL",
+        Statement::Create(CreateCommand::Rate(_)) => "CREATE RATE",
+        Statement::Create(CreateCommand::Entity(_)) => "CREATE ENTITY",
+        Statement::Create(CreateCommand::Account(_)) => "CREATE ACCOUNT",
+        Statement::Set(SetCommand::Rate(_)) => "SET RATE",
+        Statement::Accrue(_) => "ACCRUE",
+        Statement::Distribute(_) => "DISTRIBUTE",
+        Statement::Allocate(_) => "ALLOCATE",
+        Statement::Amortize(_) => "AMORTIZE",
+        Statement::Create(CreateCommand::Asset(_)) => "CREATE ASSET",
+        Statement::Depreciate(_) => "DEPRECIATE",
+        Statement::Dispose(_) => "DISPOSE ASSET",
+        Statement::Sell(_) => "SELL",
+        Statement::Split(_) => "SPLIT",
+        Statement::Reverse(_) => "REVERSE JOURNAL",
+        Statement::LockPeriod(_) => "LOCK PERIOD",
+        Statement::Unlock => "UNLOCK",
+        Statement::CloseYear(_) => "CLOSE YEAR",
+    }
+}
+
 /// Authenticated caller identity, available to handlers via request extensions.
 #[derive(Debug, Clone)]
 pub struct CallerIdentity {
     pub name: String,
-    pub role: String,
+    pub role: Role,
+}
+
+/// Look up the caller for an API key. Returns `None` if the key is unknown.
+pub fn authenticate(config: &AuthConfig, key: &str) -> Option<CallerIdentity> {
+    config
+        .api_keys
+        .iter()
+        .find(|entry| entry.key.as_bytes().ct_eq(key.as_bytes()).into())
+        .map(|entry| CallerIdentity {
+            name: entry.name.clone(),
+            role: Role::parse(&entry.role),
+        })
 }
 
 #[derive(Serialize)]
@@ -30,7 +146,7 @@ pub async fn auth_middleware<B>(
     if !config.enabled {
         req.extensions_mut().insert(CallerIdentity {
             name: "anonymous".to_string(),
-            role: "admin".to_string(),
+            role: Role::Admin,
         });
         return next.run(req).await;
     }
@@ -43,15 +159,10 @@ pub async fn auth_middleware<B>(
 
     match api_key {
         Some(key) => {
-            match config.api_keys.iter().find(|entry| {
-                    entry.key.as_bytes().ct_eq(key.as_bytes()).into()
-                }) {
-                Some(entry) => {
-                    tracing::debug!(caller = %entry.name, role = %entry.role, "Authenticated request");
-                    req.extensions_mut().insert(CallerIdentity {
-                        name: entry.name.clone(),
-                        role: entry.role.clone(),
-                    });
+            match authenticate(&config, key) {
+                Some(caller) => {
+                    tracing::debug!(caller = %caller.name, role = %caller.role, "Authenticated request");
+                    req.extensions_mut().insert(caller);
                     next.run(req).await
                 }
                 None => {
@@ -21,6 +21,10 @@ pub struct CliArgs {
     /// Run as MCP server over stdio (for AI agent integration)
     #[arg(long)]
     pub mcp: bool,
+
+    /// Role granted to the MCP client: "reader", "writer" or "admin"
+    #[arg(long, default_value = "reader")]
+    pub mcp_role: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -1,6 +1,7 @@
-use dblentry_core::{AccountType, DataValue, LotItem, StatementTxn, TrialBalanceItem};
+use dblentry_core::{AccountClass, AccountType, AmortizationItem, BalanceSeriesItem, BalanceSheet, CashFlowCategory, CashFlowStatement, DataValue, DepreciationItem, DimensionBalanceItem, JournalEntry, LotItem, StatementTxn, TrialBalanceItem};
 use prettytable::{row, Table};
 
+use crate::functions::format_journal_id;
 use crate::statement_executor::ExecutionResult;
 
 pub fn format_data_value(value: &DataValue) -> String {
@@ -19,6 +20,13 @@ pub fn format_data_value(value: &DataValue) -> String {
         DataValue::Statement(txns) => format_statement(txns),
         DataValue::TrialBalance(items) => format_trial_balance(items),
         DataValue::Lots(lots) => format_lots(lots),
+        DataValue::Journals(journals) => format_journals(journals),
+        DataValue::DimensionBalances(items) => format_dimension_balances(items),
+        DataValue::BalanceSheet(sheet) => format_balance_sheet(sheet),
+        DataValue::CashFlowStatement(statement) => format_cash_flow_statement(statement),
+        DataValue::BalanceSeries(items) => format_balance_series(items),
+        DataValue::Amortization(items) => format_amortization(items),
+        DataValue::DepreciationSchedule(items) => format_depreciation_schedule(items),
     }
 }
 
@@ -49,21 +57,134 @@ fn format_statement(txns: &[StatementTxn]) -> String {
     format!("\n{}\n", table)
 }
 
+fn format_balance_series(items: &[BalanceSeriesItem]) -> String {
+    let mut table = Table::new();
+    table.add_row(row!["Period End", "Balance", "Change"]);
+    table.add_empty_row();
+
+    for item in items {
+        table.add_row(row![item.period_end, item.balance, item.change]);
+    }
+
+    format!("\n{}\n", table)
+}
+
+fn format_amortization(items: &[AmortizationItem]) -> String {
+    let mut table = Table::new();
+    table.add_row(row!["#", "Date", "Payment", "Interest", "Principal", "Balance"]);
+    table.add_empty_row();
+
+    for item in items {
+        table.add_row(row![item.number, item.date, item.payment, item.interest, item.principal, item.balance]);
+    }
+
+    format!("\n{}\n", table)
+}
+
+fn format_depreciation_schedule(items: &[DepreciationItem]) -> String {
+    let mut table = Table::new();
+    table.add_row(row!["Period End", "Depreciation", "Accumulated", "Book Value"]);
+    table.add_empty_row();
+
+    for item in items {
+        table.add_row(row![item.period_end, item.depreciation, item.accumulated, item.book_value]);
+    }
+
+    format!("\n{}\n", table)
+}
+
 fn format_trial_balance(items: &[TrialBalanceItem]) -> String {
     let mut table = Table::new();
     table.add_row(row!["Account", "Debit", "Credit"]);
     table.add_empty_row();
 
     for item in items {
-        match item.account_type {
-            AccountType::Asset | AccountType::Expense => {
-                table.add_row(row![item.account_id, item.balance, ""]);
-            }
-            AccountType::Liability | AccountType::Equity | AccountType::Income => {
-                table.add_row(row![item.account_id, "", item.balance]);
+        let account = format!("{}{}", "  ".repeat(item.depth), item.account_id);
+        let (debit, credit) = item.debit_credit();
+        let column = |amount: Option<rust_decimal::Decimal>| amount.map(|a| a.to_string()).unwrap_or_default();
+        table.add_row(row![account, column(debit), column(credit)]);
+    }
+
+    format!("\n{}\n", table)
+}
+
+fn format_balance_sheet(sheet: &BalanceSheet) -> String {
+    let mut table = Table::new();
+    table.add_row(row![format!("Balance sheet at {}", sheet.date), "Amount"]);
+
+    let mut current_type = None;
+    for section in &sheet.sections {
+        let plural = match section.account_type {
+            AccountType::Asset => "assets",
+            AccountType::Liability => "liabilities",
+            _ => "equity",
+        };
+        if current_type.as_ref() != Some(&section.account_type) {
+            if let Some(previous) = current_type.replace(section.account_type.clone()) {
+                add_balance_sheet_total(&mut table, sheet, &previous);
             }
+            table.add_empty_row();
+        }
+        let heading = match section.class {
+            Some(AccountClass::Current) => format!("Current {}", plural),
+            Some(AccountClass::NonCurrent) => format!("Non-current {}", plural),
+            None => format!("{}{}", plural[..1].to_uppercase(), &plural[1..]),
+        };
+        table.add_row(row![heading, ""]);
+        for line in &section.lines {
+            table.add_row(row![format!("  {}", line.account_id), line.balance]);
+        }
+        if section.class.is_some() {
+            table.add_row(row![format!("Total {}", heading.to_lowercase()), section.subtotal]);
         }
     }
+    if let Some(last) = current_type {
+        add_balance_sheet_total(&mut table, sheet, &last);
+    }
+
+    table.add_empty_row();
+    table.add_row(row!["Total liabilities and equity", sheet.total_liabilities + sheet.total_equity]);
+    if !sheet.balanced {
+        table.add_row(row!["Out of balance", sheet.total_assets - sheet.total_liabilities - sheet.total_equity]);
+    }
+
+    format!("\n{}\n", table)
+}
+
+fn add_balance_sheet_total(table: &mut Table, sheet: &BalanceSheet, account_type: &AccountType) {
+    let (label, total) = match account_type {
+        AccountType::Asset => ("Total assets", sheet.total_assets),
+        AccountType::Liability => ("Total liabilities", sheet.total_liabilities),
+        _ => ("Total equity", sheet.total_equity),
+    };
+    table.add_row(row![label, total]);
+}
+
+fn format_cash_flow_statement(statement: &CashFlowStatement) -> String {
+    let mut table = Table::new();
+    table.add_row(row![format!("Cash flows {} to {}", statement.from, statement.to), "Amount"]);
+
+    for section in &statement.sections {
+        let activity = match section.category {
+            CashFlowCategory::Investing => "investing",
+            CashFlowCategory::Financing => "financing",
+            _ => "operating",
+        };
+        table.add_empty_row();
+        table.add_row(row![format!("Cash flows from {} activities", activity), ""]);
+        for line in &section.lines {
+            table.add_row(row![format!("  {}", line.account_id), line.amount]);
+        }
+        table.add_row(row![format!("Net cash from {} activities", activity), section.subtotal]);
+    }
+
+    table.add_empty_row();
+    table.add_row(row!["Net change in cash", statement.net_change]);
+    table.add_row(row!["Cash at beginning of period", statement.opening_cash]);
+    table.add_row(row!["Cash at end of period", statement.closing_cash]);
+    if !statement.reconciled {
+        table.add_row(row!["Unreconciled difference", statement.closing_cash - statement.opening_cash - statement.net_change]);
+    }
 
     format!("\n{}\n", table)
 }
@@ -79,3 +200,36 @@ fn format_lots(lots: &[LotItem]) -> String {
 
     format!("\n{}\n", table)
 }
+
+fn format_dimension_balances(items: &[DimensionBalanceItem]) -> String {
+    let mut table = Table::new();
+    let header = items.first().map(|item| item.dimension.to_string()).unwrap_or_else(|| "Value".to_string());
+    table.add_row(row![header, "Amount"]);
+    table.add_empty_row();
+
+    for item in items {
+        table.add_row(row![format_data_value(&item.value), item.amount]);
+    }
+
+    format!("\n{}\n", table)
+}
+
+fn format_journals(journals: &[JournalEntry]) -> String {
+    let mut table = Table::new();
+    table.add_row(row!["Journal", "Date", "Description", "Account", "Debit", "Credit"]);
+    table.add_empty_row();
+
+    for journal in journals {
+        for (i, line) in journal.lines.iter().enumerate() {
+            let debit = if line.debit.is_zero() { String::new() } else { line.debit.to_string() };
+            let credit = if line.credit.is_zero() { String::new() } else { line.credit.to_string() };
+            if i == 0 {
+                table.add_row(row![format_journal_id(journal.id), journal.date, journal.description, line.account_id, debit, credit]);
+            } else {
+                table.add_row(row!["", "", "", line.account_id, debit, credit]);
+            }
+        }
+    }
+
+    format!("\n{}\n", table)
+}
@@ -25,6 +25,8 @@ pub enum EvaluationError {
     StorageError(#[from] StorageError),
     #[error("no rate found for the given date")]
     NoRateFound,
+    #[error("permission denied: {0}")]
+    PermissionDenied(String),
     #[error("{0}")]
     General(String),
 }
@@ -179,7 +181,7 @@ impl ExpressionEvaluator {
                 ast::Literal::Real(r) => DataValue::Money(Decimal::from_str(r).unwrap_or(Decimal::ZERO)),
                 ast::Literal::Date(d) => DataValue::Date(*d),
                 ast::Literal::Account(a) => DataValue::AccountId(a.clone()),
-                ast::Literal::Percentage(p) => DataValue::Percentage(Decimal::from_str(p).unwrap_or(Decimal::ZERO)),
+                ast::Literal::Percentage(p) => DataValue::Percentage(Decimal::from_str(p).unwrap_or(Decimal::ZERO) / Decimal::ONE_HUNDRED),
             },
             ast::UnaryExpression::Property { name, key } => match context.get_variable(name) {
                 Some(DataValue::Map(o)) => match o.get(key) {
@@ -293,6 +295,8 @@ impl ExpressionEvaluator {
                     (DataValue::Money(n1), DataValue::Money(n2)) => DataValue::Money(n1 + n2),
                     (DataValue::Int(n1), DataValue::Money(n2)) => DataValue::Money(Decimal::from(n1) + n2),
                     (DataValue::Money(n1), DataValue::Int(n2)) => DataValue::Money(n1 + Decimal::from(n2)),
+                    (DataValue::Percentage(n1), DataValue::Percentage(n2) | DataValue::Money(n2)) => DataValue::Percentage(n1 + n2),
+                    (DataValue::Money(n1), DataValue::Percentage(n2)) => DataValue::Percentage(n1 + n2),
                     //(QueryValue::Date(d1), QueryValue::Date(d2)) => QueryValue::Date(d1.add(d2)),
 
                     (DataValue::Int(n1), DataValue::String(s2)) => DataValue::String(Arc::from(n1.to_string() + &s2)),
@@ -310,6 +314,8 @@ impl ExpressionEvaluator {
                     (DataValue::Money(n1), DataValue::Money(n2)) => DataValue::Money(n1 - n2),
                     (DataValue::Int(n1), DataValue::Money(n2)) => DataValue::Money(Decimal::from(n1) - n2),
                     (DataValue::Money(n1), DataValue::Int(n2)) => DataValue::Money(n1 - Decimal::from(n2)),
+                    (DataValue::Percentage(n1), DataValue::Percentage(n2) | DataValue::Money(n2)) => DataValue::Percentage(n1 - n2),
+                    (DataValue::Money(n1), DataValue::Percentage(n2)) => DataValue::Percentage(n1 - n2),
                     _ => DataValue::Null,
                 }
             }
@@ -321,6 +327,9 @@ impl ExpressionEvaluator {
                     (DataValue::Money(n1), DataValue::Money(n2)) => DataValue::Money(n1 * n2),
                     (DataValue::Int(n1), DataValue::Money(n2)) => DataValue::Money(Decimal::from(n1) * n2),
                     (DataValue::Money(n1), DataValue::Int(n2)) => DataValue::Money(n1 * Decimal::from(n2)),
+                    (DataValue::Percentage(n1), DataValue::Percentage(n2)) => DataValue::Percentage(n1 * n2),
+                    (DataValue::Percentage(n1), DataValue::Money(n2)) | (DataValue::Money(n1), DataValue::Percentage(n2)) => DataValue::Money(n1 * n2),
+                    (DataValue::Percentage(n1), DataValue::Int(n2)) | (DataValue::Int(n2), DataValue::Percentage(n1)) => DataValue::Money(n1 * Decimal::from(n2)),
                     _ => DataValue::Null,
                 }
             }
@@ -419,15 +428,8 @@ impl ExpressionEvaluator {
         context: &ExpressionEvaluationContext,
         expression: &ast::FunctionExpression,
     ) -> Result<DataValue, EvaluationError> {
-        let mut values = Vec::new();
-        for arg in &expression.args {
-            values.push(self.evaluate_expression(context, arg)?);
-        }
-        
-        let result = match self.function_registry.get_function(&expression.name) {
-            Some(function) => match function.as_ref() {
-                Function::Scalar(scalar) => scalar.call(context, values)?,
-            },
+        let function = match self.function_registry.get_function(&expression.name) {
+            Some(function) => function,
             None => {
                 return Err(EvaluationError::UnknownFunction(
                     expression.name.to_string(),
@@ -435,6 +437,22 @@ impl ExpressionEvaluator {
             }
         };
 
+        let result = match function.as_ref() {
+            Function::Scalar(scalar) => {
+                let name_args = scalar.name_args();
+                let mut values = Vec::new();
+                for (i, arg) in expression.args.iter().enumerate() {
+                    match arg {
+                        ast::Expression::UnaryExpression(ast::UnaryExpression::Identifier(name)) if name_args.contains(&i) => {
+                            values.push(DataValue::String(name.clone()));
+                        }
+                        _ => values.push(self.evaluate_expression(context, arg)?),
+                    }
+                }
+                scalar.call(context, values)?
+            }
+        };
+
         Ok(result)
     }
 
@@ -9,6 +9,12 @@ pub enum Function {
 
 pub trait ScalarFunction: Send + Sync {
   fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError>;
+
+  /// Argument positions where a bare identifier is a name (e.g. a dimension key) passed as a string,
+  /// rather than a variable reference.
+  fn name_args(&self) -> &'static [usize] {
+    &[]
+  }
 }
 
 pub struct FunctionRegistry {
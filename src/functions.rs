@@ -1,18 +1,87 @@
-use std::{sync::Arc, ops::Bound};
+use std::{collections::HashMap, sync::Arc, ops::Bound};
 
 use rust_decimal::Decimal;
+use time::Date;
 
-use crate::{ast::AccountType, function_registry::ScalarFunction, models::{DataValue, TrialBalanceItem}, evaluator::{ExpressionEvaluationContext, EvaluationError}, storage::StorageBackend};
+use crate::{ast::{AccountClass, AccountType, CashFlowCategory, Period}, function_registry::ScalarFunction, models::{self, BalanceSeriesItem, BalanceSheetLine, BalanceSheetSection, CashFlowLine, CashFlowSection, DataValue, DimensionBalanceItem, DimensionFilter, TrialBalanceItem}, evaluator::{ExpressionEvaluationContext, EvaluationError}, storage::StorageBackend, statement_executor::{depreciation_schedule, generate_periods}};
 
-/// Extract an optional dimension argument from function args at the given index.
-fn extract_dimension_arg(args: &[DataValue], index: usize) -> Option<(Arc<str>, Arc<DataValue>)> {
+/// Journal ids are shown as UUIDs, the form the storage backends and their errors use.
+pub fn format_journal_id(id: u128) -> String {
+    uuid::Uuid::from_u128(id).to_string()
+}
+
+/// Journal ids are accepted either as a UUID or in their decimal form.
+pub fn parse_journal_id(s: &str) -> Option<u128> {
+    let s = s.trim();
+    s.parse::<u128>().ok()
+        .or_else(|| uuid::Uuid::parse_str(s).ok().map(|u| u.as_u128()))
+}
+
+/// Collect the trailing `key=value` arguments from the given index into a conjunctive filter.
+fn extract_dimension_filter(args: &[DataValue], index: usize) -> Result<DimensionFilter, EvaluationError> {
+    args.iter()
+        .skip(index)
+        .map(|arg| match arg {
+            DataValue::Dimension(dimension) => Ok(dimension.clone()),
+            _ => Err(EvaluationError::InvalidArgument("dimension".to_string())),
+        })
+        .collect()
+}
+
+/// Read the optional `transaction`/`functional` argument at `index`: whether amounts are wanted
+/// in the account's transaction currency, and the index the dimension arguments start at.
+fn extract_currency_mode(args: &[DataValue], index: usize) -> Result<(bool, usize), EvaluationError> {
     match args.get(index) {
-        Some(DataValue::Dimension((key, val))) => Some((key.clone(), val.clone())),
-        _ => None,
+        Some(DataValue::String(mode)) if mode.eq_ignore_ascii_case("transaction") => Ok((true, index + 1)),
+        Some(DataValue::String(mode)) if mode.eq_ignore_ascii_case("functional") => Ok((false, index + 1)),
+        Some(DataValue::String(_)) => Err(EvaluationError::InvalidArgument("currency".to_string())),
+        _ => Ok((false, index)),
     }
 }
 
+/// An entity's accounts in depth-first order (siblings sorted by id), each with its depth
+/// in the hierarchy and whether it has children. Parent balances already roll up their
+/// children, so totals must only count accounts without children.
+fn account_tree(storage: &dyn StorageBackend, entity_id: &str) -> Vec<(Arc<str>, AccountType, usize, bool)> {
+    let mut accounts = storage.list_accounts(entity_id);
+    accounts.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut children: HashMap<Arc<str>, Vec<(Arc<str>, AccountType)>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (account_id, account_type) in accounts {
+        match storage.get_parent_account(entity_id, &account_id) {
+            Some(parent) => children.entry(parent).or_default().push((account_id, account_type)),
+            None => roots.push((account_id, account_type)),
+        }
+    }
 
+    let mut result = Vec::new();
+    let mut stack: Vec<_> = roots.into_iter().rev().map(|(id, t)| (id, t, 0)).collect();
+    while let Some((account_id, account_type, depth)) = stack.pop() {
+        let descendants = children.remove(&account_id).unwrap_or_default();
+        result.push((account_id, account_type, depth, !descendants.is_empty()));
+        stack.extend(descendants.into_iter().rev().map(|(id, t)| (id, t, depth + 1)));
+    }
+    result
+}
+
+
+/// Net debit posted to each account by year-end closing journals dated within `(from, to]`,
+/// so period reports can show the activity that the close moved to equity.
+fn closing_debits(storage: &dyn StorageBackend, entity_id: &str, from: Date, to: Date) -> Result<HashMap<Arc<str>, Decimal>, EvaluationError> {
+    let mut debits: HashMap<Arc<str>, Decimal> = HashMap::new();
+    for (year_end, journal_ids) in storage.list_year_closes(entity_id)? {
+        if year_end <= from || year_end > to {
+            continue;
+        }
+        for journal_id in journal_ids {
+            for line in storage.get_journal(entity_id, journal_id)?.lines {
+                *debits.entry(line.account_id).or_default() += line.debit - line.credit;
+            }
+        }
+    }
+    Ok(debits)
+}
 
 pub struct Balance {
     storage: Arc<dyn StorageBackend>,
@@ -39,16 +108,22 @@ impl ScalarFunction for Balance {
             _ => return Err(EvaluationError::InvalidArgument("effective_date".to_string())),
         };
 
-        let dimension = match args.get(2) {
-            Some(DataValue::Dimension(dimension)) => Some(dimension),
-            None => None,
-            _ => return Err(EvaluationError::InvalidArgument("dimension".to_string())),
-        };
+        let (transaction, index) = extract_currency_mode(&args, 2)?;
+        let dimensions = extract_dimension_filter(&args, index)?;
 
-        let result = self.storage.get_balance(context.get_entity_id(), account_id, *effective_date, dimension)?;
+        // Accounts held in the functional currency read the same either way
+        let result = if transaction && self.storage.get_account_currency(context.get_entity_id(), account_id).is_some() {
+            self.storage.get_currency_balance(context.get_entity_id(), account_id, *effective_date, &dimensions)?
+        } else {
+            self.storage.get_balance(context.get_entity_id(), account_id, *effective_date, &dimensions)?
+        };
 
         Ok(DataValue::Money(result))
     }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[2]
+    }
 }
 
 
@@ -82,16 +157,288 @@ impl ScalarFunction for Statement {
             _ => return Err(EvaluationError::InvalidArgument("to".to_string())),
         };
 
-        let dimension = match args.get(3) {
-            Some(DataValue::Dimension(dimension)) => Some(dimension),
-            None => None,
-            _ => return Err(EvaluationError::InvalidArgument("dimension".to_string())),
-        };
+        let (transaction, index) = extract_currency_mode(&args, 3)?;
+        let dimensions = extract_dimension_filter(&args, index)?;
 
-        let result = self.storage.get_statement(context.get_entity_id(), account_id, Bound::Included(*from), Bound::Included(*to), dimension)?;
+        let result = if transaction && self.storage.get_account_currency(context.get_entity_id(), account_id).is_some() {
+            self.storage.get_currency_statement(context.get_entity_id(), account_id, Bound::Included(*from), Bound::Included(*to), &dimensions)?
+        } else {
+            self.storage.get_statement(context.get_entity_id(), account_id, Bound::Included(*from), Bound::Included(*to), &dimensions)?
+        };
 
         Ok(result)
     }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[3]
+    }
+}
+
+/// Read the `(@account, from, to [, dimension ...])` arguments shared by the period activity functions.
+fn period_args(args: &[DataValue]) -> Result<(Arc<str>, Date, Date, DimensionFilter), EvaluationError> {
+    let account_id = match args.first() {
+        Some(DataValue::AccountId(id)) => id.clone(),
+        _ => return Err(EvaluationError::InvalidArgument("account_id".to_string())),
+    };
+
+    let from = match args.get(1) {
+        Some(DataValue::Date(d)) => *d,
+        _ => return Err(EvaluationError::InvalidArgument("from".to_string())),
+    };
+
+    let to = match args.get(2) {
+        Some(DataValue::Date(d)) => *d,
+        _ => return Err(EvaluationError::InvalidArgument("to".to_string())),
+    };
+
+    Ok((account_id, from, to, extract_dimension_filter(args, 3)?))
+}
+
+/// Balance at the end of every day from `from` through `to`, read in one pass.
+pub(crate) fn daily_balances(storage: &dyn StorageBackend, entity_id: &str, account_id: &str, from: Date, to: Date, dimensions: &DimensionFilter) -> Result<Vec<Decimal>, EvaluationError> {
+    let mut days = Vec::new();
+    let mut day = from;
+    while day <= to {
+        days.push(day);
+        day = match day.next_day() {
+            Some(d) => d,
+            None => break,
+        };
+    }
+    Ok(storage.get_balance_series(entity_id, account_id, &days, dimensions)?)
+}
+
+/// average_balance(account, from, to [, dimension]) — Average of the end-of-day balances over the period.
+pub struct AverageBalance {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl AverageBalance {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for AverageBalance {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let (account_id, from, to, dimensions) = period_args(&args)?;
+        if to < from {
+            return Err(EvaluationError::InvalidArgument("to".to_string()));
+        }
+        let balances = daily_balances(self.storage.as_ref(), context.get_entity_id(), &account_id, from, to, &dimensions)?;
+        let total: Decimal = balances.iter().sum();
+        Ok(DataValue::Money(total / Decimal::from(balances.len())))
+    }
+}
+
+/// debits(account, from, to [, dimension]) — Total debits posted to the account over the period.
+pub struct Debits {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl Debits {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for Debits {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let (account_id, from, to, dimensions) = period_args(&args)?;
+        let (debits, _) = self.storage.get_turnover(context.get_entity_id(), &account_id, Bound::Included(from), to, &dimensions)?;
+        Ok(DataValue::Money(debits))
+    }
+}
+
+/// credits(account, from, to [, dimension]) — Total credits posted to the account over the period.
+pub struct Credits {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl Credits {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for Credits {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let (account_id, from, to, dimensions) = period_args(&args)?;
+        let (_, credits) = self.storage.get_turnover(context.get_entity_id(), &account_id, Bound::Included(from), to, &dimensions)?;
+        Ok(DataValue::Money(credits))
+    }
+}
+
+/// activity(account, from, to [, dimension]) — Net movement over the period in the account's
+/// normal sign, i.e. the change in its balance.
+pub struct Activity {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl Activity {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for Activity {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let (account_id, from, to, dimensions) = period_args(&args)?;
+        let opening = match from.previous_day() {
+            Some(day) => self.storage.get_balance(context.get_entity_id(), &account_id, day, &dimensions)?,
+            None => Decimal::ZERO,
+        };
+        let closing = self.storage.get_balance(context.get_entity_id(), &account_id, to, &dimensions)?;
+        Ok(DataValue::Money(closing - opening))
+    }
+}
+
+/// balance_series(account, from, to, period [, dimension]) — Balance at the end of each
+/// period between `from` and `to`, with the change since the previous period end.
+pub struct BalanceSeries {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl BalanceSeries {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for BalanceSeries {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let account_id = match args.first() {
+            Some(DataValue::AccountId(id)) => id,
+            _ => return Err(EvaluationError::InvalidArgument("account_id".to_string())),
+        };
+
+        let from = match args.get(1) {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("from".to_string())),
+        };
+
+        let to = match args.get(2) {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("to".to_string())),
+        };
+
+        let period = match args.get(3) {
+            Some(DataValue::String(p)) => match p.to_ascii_uppercase().as_str() {
+                "DAILY" => Period::Daily,
+                "MONTHLY" => Period::Monthly,
+                "QUARTERLY" => Period::Quarterly,
+                "YEARLY" => Period::Yearly,
+                _ => return Err(EvaluationError::InvalidArgument("period".to_string())),
+            },
+            _ => return Err(EvaluationError::InvalidArgument("period".to_string())),
+        };
+
+        let dimensions = extract_dimension_filter(&args, 4)?;
+
+        // The day before `from` gives the opening balance for the first period's change
+        let period_ends: Vec<Date> = generate_periods(from, to, &period).into_iter().map(|(_, end)| end).collect();
+        let mut dates = vec![from.previous_day().unwrap_or(from)];
+        dates.extend(&period_ends);
+        let balances = self.storage.get_balance_series(context.get_entity_id(), account_id, &dates, &dimensions)?;
+
+        let items = period_ends.into_iter()
+            .zip(balances.windows(2))
+            .map(|(period_end, pair)| BalanceSeriesItem { period_end, balance: pair[1], change: pair[1] - pair[0] })
+            .collect();
+
+        Ok(DataValue::BalanceSeries(items))
+    }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[3]
+    }
+}
+
+/// Read the dimension key argument, given either bare (`Customer`) or as a string.
+fn extract_dimension_key(args: &[DataValue], index: usize) -> Result<Arc<str>, EvaluationError> {
+    match args.get(index) {
+        Some(DataValue::String(key)) if !key.is_empty() => Ok(key.clone()),
+        _ => Err(EvaluationError::InvalidArgument("dimension_key".to_string())),
+    }
+}
+
+fn dimension_balances(key: Arc<str>, totals: Vec<(Arc<DataValue>, Decimal)>) -> DataValue {
+    DataValue::DimensionBalances(
+        totals.into_iter()
+            .map(|(value, amount)| DimensionBalanceItem { dimension: key.clone(), value, amount })
+            .collect(),
+    )
+}
+
+/// balance_by(account, date, key) — Returns the balance at date per value of a dimension.
+pub struct BalanceBy {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl BalanceBy {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for BalanceBy {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let account_id = match args.first() {
+            Some(DataValue::AccountId(id)) => id,
+            _ => return Err(EvaluationError::InvalidArgument("account_id".to_string())),
+        };
+
+        let date = match args.get(1) {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
+        };
+
+        let key = extract_dimension_key(&args, 2)?;
+        let totals = self.storage.get_balances_by_dimension(context.get_entity_id(), account_id, &key, Bound::Unbounded, date)?;
+        Ok(dimension_balances(key, totals))
+    }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[2]
+    }
+}
+
+/// activity_by(account, from, to, key) — Returns the net movement over a period per value of a dimension.
+pub struct ActivityBy {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl ActivityBy {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for ActivityBy {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let account_id = match args.first() {
+            Some(DataValue::AccountId(id)) => id,
+            _ => return Err(EvaluationError::InvalidArgument("account_id".to_string())),
+        };
+
+        let from = match args.get(1) {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("from".to_string())),
+        };
+
+        let to = match args.get(2) {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("to".to_string())),
+        };
+
+        let key = extract_dimension_key(&args, 3)?;
+        let totals = self.storage.get_balances_by_dimension(context.get_entity_id(), account_id, &key, Bound::Included(from), to)?;
+        Ok(dimension_balances(key, totals))
+    }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[3]
+    }
 }
 
 pub struct TrialBalance {
@@ -113,19 +460,35 @@ impl ScalarFunction for TrialBalance {
             _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
         };
 
-        let accounts = self.storage.list_accounts(context.get_entity_id());
+        // `tree` lists parent accounts as indented subtotal rows; by default only
+        // postable accounts are listed so that the column still sums to zero.
+        let tree = match args.get(1) {
+            None => false,
+            Some(DataValue::String(mode)) if mode.eq_ignore_ascii_case("tree") => true,
+            _ => return Err(EvaluationError::InvalidArgument("mode".to_string())),
+        };
+
         let mut result = Vec::new();
-        for (account_id, account_type) in accounts {
-            let balance = self.storage.get_balance(context.get_entity_id(), &account_id, *effective_date, None)?;
+        for (account_id, account_type, depth, subtotal) in account_tree(self.storage.as_ref(), context.get_entity_id()) {
+            if (subtotal && !tree) || account_type == AccountType::Statistical {
+                continue;
+            }
+            let balance = self.storage.get_balance(context.get_entity_id(), &account_id, *effective_date, &DimensionFilter::new())?;
             result.push(TrialBalanceItem {
                 account_id,
                 account_type,
                 balance,
+                depth: if tree { depth } else { 0 },
+                subtotal,
             });
         }
 
         Ok(DataValue::TrialBalance(result))
     }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[1]
+    }
 }
 
 /// income_statement(from_date, to_date) — Returns net income/expense for the period.
@@ -152,22 +515,33 @@ impl ScalarFunction for IncomeStatement {
             _ => return Err(EvaluationError::InvalidArgument("to_date".to_string())),
         };
 
-        let accounts = self.storage.list_accounts(context.get_entity_id());
+        let accounts = account_tree(self.storage.as_ref(), context.get_entity_id());
+        let closing = closing_debits(self.storage.as_ref(), context.get_entity_id(), from, to)?;
         let mut total_income = Decimal::ZERO;
         let mut total_expenses = Decimal::ZERO;
         let mut items = Vec::new();
 
-        for (account_id, account_type) in &accounts {
+        for (account_id, account_type, _, subtotal) in &accounts {
+            if *subtotal {
+                continue;
+            }
             match account_type {
                 AccountType::Income | AccountType::Expense => {
-                    let bal_from = self.storage.get_balance(context.get_entity_id(), account_id, from, None)?;
-                    let bal_to = self.storage.get_balance(context.get_entity_id(), account_id, to, None)?;
-                    let change = bal_to - bal_from;
+                    let bal_from = self.storage.get_balance(context.get_entity_id(), account_id, from, &DimensionFilter::new())?;
+                    let bal_to = self.storage.get_balance(context.get_entity_id(), account_id, to, &DimensionFilter::new())?;
+                    // Add back what a year-end close inside the period moved to equity
+                    let closed = closing.get(account_id).copied().unwrap_or_default();
+                    let change = match account_type {
+                        AccountType::Income => bal_to - bal_from + closed,
+                        _ => bal_to - bal_from - closed,
+                    };
                     if change != Decimal::ZERO {
                         items.push(TrialBalanceItem {
                             account_id: account_id.clone(),
                             account_type: account_type.clone(),
                             balance: change,
+                            depth: 0,
+                            subtotal: false,
                         });
                         match account_type {
                             AccountType::Income => total_income += change,
@@ -185,12 +559,207 @@ impl ScalarFunction for IncomeStatement {
             account_id: "NET_INCOME".into(),
             account_type: AccountType::Income,
             balance: total_income - total_expenses,
+            depth: 0,
+            subtotal: false,
         });
 
         Ok(DataValue::TrialBalance(items))
     }
 }
 
+/// balance_sheet(date [, dimension]) — Asset, liability and equity balances at a date.
+/// Asset and liability accounts classified as current or non-current get their own
+/// sections. Income and expenses not yet closed appear as a NET_INCOME equity line.
+pub struct BalanceSheet {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl BalanceSheet {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for BalanceSheet {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let date = match args.first() {
+            Some(DataValue::Date(dt)) => *dt,
+            _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
+        };
+        let filter = extract_dimension_filter(&args, 1)?;
+        let entity_id = context.get_entity_id();
+
+        let mut sections: Vec<BalanceSheetSection> = Vec::new();
+        let mut net_income = Decimal::ZERO;
+        for (account_id, account_type, _, subtotal) in account_tree(self.storage.as_ref(), entity_id) {
+            if subtotal {
+                continue;
+            }
+            let balance = self.storage.get_balance(entity_id, &account_id, date, &filter)?;
+            let class = match account_type {
+                AccountType::Income => {
+                    net_income += balance;
+                    continue;
+                }
+                AccountType::Expense => {
+                    net_income -= balance;
+                    continue;
+                }
+                AccountType::Equity => None,
+                AccountType::Statistical => continue,
+                _ => self.storage.get_account_class(entity_id, &account_id),
+            };
+            let section = match sections.iter_mut().find(|s| s.account_type == account_type && s.class == class) {
+                Some(section) => section,
+                None => {
+                    sections.push(BalanceSheetSection { account_type, class, lines: Vec::new(), subtotal: Decimal::ZERO });
+                    sections.last_mut().unwrap()
+                }
+            };
+            section.subtotal += balance;
+            section.lines.push(BalanceSheetLine { account_id, balance });
+        }
+
+        if net_income != Decimal::ZERO {
+            let equity = match sections.iter_mut().find(|s| s.account_type == AccountType::Equity) {
+                Some(section) => section,
+                None => {
+                    sections.push(BalanceSheetSection { account_type: AccountType::Equity, class: None, lines: Vec::new(), subtotal: Decimal::ZERO });
+                    sections.last_mut().unwrap()
+                }
+            };
+            equity.subtotal += net_income;
+            equity.lines.push(BalanceSheetLine { account_id: "NET_INCOME".into(), balance: net_income });
+        }
+
+        // Current before non-current before unclassified, within assets, liabilities, equity
+        sections.sort_by_key(|s| {
+            let type_rank = match s.account_type {
+                AccountType::Asset => 0,
+                AccountType::Liability => 1,
+                _ => 2,
+            };
+            let class_rank = match s.class {
+                Some(AccountClass::Current) => 0,
+                Some(AccountClass::NonCurrent) => 1,
+                None => 2,
+            };
+            (type_rank, class_rank)
+        });
+
+        let total = |t: AccountType| -> Decimal {
+            sections.iter().filter(|s| s.account_type == t).map(|s| s.subtotal).sum()
+        };
+        let total_assets = total(AccountType::Asset);
+        let total_liabilities = total(AccountType::Liability);
+        let total_equity = total(AccountType::Equity);
+
+        Ok(DataValue::BalanceSheet(models::BalanceSheet {
+            date,
+            balanced: total_assets == total_liabilities + total_equity,
+            sections,
+            total_assets,
+            total_liabilities,
+            total_equity,
+        }))
+    }
+}
+
+/// cash_flow_statement(from_date, to_date) — Indirect-method statement of cash flows.
+/// Starts from net income and adds the cash effect of the change in every other
+/// balance sheet account, grouped by its cash flow category. Untagged asset and
+/// liability accounts are operating, untagged equity accounts are financing.
+pub struct CashFlowStatement {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl CashFlowStatement {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for CashFlowStatement {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let from = match args.first() {
+            Some(DataValue::Date(dt)) => *dt,
+            _ => return Err(EvaluationError::InvalidArgument("from_date".to_string())),
+        };
+
+        let to = match args.get(1) {
+            Some(DataValue::Date(dt)) => *dt,
+            _ => return Err(EvaluationError::InvalidArgument("to_date".to_string())),
+        };
+
+        let entity_id = context.get_entity_id();
+        let closing = closing_debits(self.storage.as_ref(), entity_id, from, to)?;
+        let mut sections: Vec<CashFlowSection> = [CashFlowCategory::Operating, CashFlowCategory::Investing, CashFlowCategory::Financing]
+            .into_iter()
+            .map(|category| CashFlowSection { category, lines: Vec::new(), subtotal: Decimal::ZERO })
+            .collect();
+        let mut net_income = Decimal::ZERO;
+        let mut opening_cash = Decimal::ZERO;
+        let mut closing_cash = Decimal::ZERO;
+
+        for (account_id, account_type, _, subtotal) in account_tree(self.storage.as_ref(), entity_id) {
+            if subtotal {
+                continue;
+            }
+            let bal_from = self.storage.get_balance(entity_id, &account_id, from, &DimensionFilter::new())?;
+            let bal_to = self.storage.get_balance(entity_id, &account_id, to, &DimensionFilter::new())?;
+            // Year-end closing journals only move balances between income, expenses and
+            // equity, so they are taken out of the change and net income is reported in full
+            let closed = closing.get(&account_id).copied().unwrap_or_default();
+            let category = match account_type {
+                AccountType::Income => {
+                    net_income += bal_to - bal_from + closed;
+                    continue;
+                }
+                AccountType::Expense => {
+                    net_income -= bal_to - bal_from - closed;
+                    continue;
+                }
+                AccountType::Statistical => continue,
+                _ => self.storage.get_cash_flow_category(entity_id, &account_id).unwrap_or(match account_type {
+                    AccountType::Equity => CashFlowCategory::Financing,
+                    _ => CashFlowCategory::Operating,
+                }),
+            };
+            if category == CashFlowCategory::Cash {
+                opening_cash += bal_from;
+                closing_cash += bal_to;
+                continue;
+            }
+            // An increase in an asset uses cash; an increase in a liability or equity provides it
+            let amount = match account_type {
+                AccountType::Asset => bal_from - bal_to,
+                _ => bal_to - bal_from + closed,
+            };
+            if amount != Decimal::ZERO {
+                let section = sections.iter_mut().find(|s| s.category == category).unwrap();
+                section.subtotal += amount;
+                section.lines.push(CashFlowLine { account_id, amount });
+            }
+        }
+
+        let operating = &mut sections[0];
+        operating.lines.insert(0, CashFlowLine { account_id: "NET_INCOME".into(), amount: net_income });
+        operating.subtotal += net_income;
+
+        let net_change = sections.iter().map(|s| s.subtotal).sum::<Decimal>();
+        Ok(DataValue::CashFlowStatement(models::CashFlowStatement {
+            from,
+            to,
+            net_income,
+            sections,
+            net_change,
+            opening_cash,
+            closing_cash,
+            reconciled: net_change == closing_cash - opening_cash,
+        }))
+    }
+}
+
 /// account_count() — Returns the number of accounts.
 pub struct AccountCount {
     storage: Arc<dyn StorageBackend>,
@@ -369,8 +938,8 @@ impl ScalarFunction for Units {
             _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
         };
 
-        let dim = extract_dimension_arg(&args, 2);
-        let total = self.storage.get_total_units(context.get_entity_id(), account_id, dim.as_ref())?;
+        let dimensions = extract_dimension_filter(&args, 2)?;
+        let total = self.storage.get_total_units(context.get_entity_id(), account_id, &dimensions)?;
         Ok(DataValue::Money(total))
     }
 }
@@ -398,8 +967,8 @@ impl ScalarFunction for MarketValue {
             _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
         };
 
-        let dim = extract_dimension_arg(&args, 2);
-        let units = self.storage.get_total_units(context.get_entity_id(), account_id, dim.as_ref())?;
+        let dimensions = extract_dimension_filter(&args, 2)?;
+        let units = self.storage.get_total_units(context.get_entity_id(), account_id, &dimensions)?;
         let rate_id = self.storage.get_unit_rate_id(context.get_entity_id(), account_id)
             .ok_or_else(|| EvaluationError::InvalidArgument(format!("Account @{} has no linked rate", account_id)))?;
         let rate = self.storage.get_rate(context.get_entity_id(), &rate_id, date)?;
@@ -431,13 +1000,13 @@ impl ScalarFunction for UnrealizedGain {
             _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
         };
 
-        let dim = extract_dimension_arg(&args, 2);
-        let units = self.storage.get_total_units(context.get_entity_id(), account_id, dim.as_ref())?;
+        let dimensions = extract_dimension_filter(&args, 2)?;
+        let units = self.storage.get_total_units(context.get_entity_id(), account_id, &dimensions)?;
         let rate_id = self.storage.get_unit_rate_id(context.get_entity_id(), account_id)
             .ok_or_else(|| EvaluationError::InvalidArgument(format!("Account @{} has no linked rate", account_id)))?;
         let rate = self.storage.get_rate(context.get_entity_id(), &rate_id, date)?;
         let market_value = units * rate;
-        let cost_basis = self.storage.get_balance(context.get_entity_id(), account_id, date, dim.as_ref())?;
+        let cost_basis = self.storage.get_balance(context.get_entity_id(), account_id, date, &dimensions)?;
 
         Ok(DataValue::Money(market_value - cost_basis))
     }
@@ -466,13 +1035,13 @@ impl ScalarFunction for CostBasis {
             _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
         };
 
-        let dim = extract_dimension_arg(&args, 2);
-        let units = self.storage.get_total_units(context.get_entity_id(), account_id, dim.as_ref())?;
+        let dimensions = extract_dimension_filter(&args, 2)?;
+        let units = self.storage.get_total_units(context.get_entity_id(), account_id, &dimensions)?;
         if units == Decimal::ZERO {
             return Ok(DataValue::Money(Decimal::ZERO));
         }
 
-        let lots = self.storage.get_lots(context.get_entity_id(), account_id, dim.as_ref())?;
+        let lots = self.storage.get_lots(context.get_entity_id(), account_id, &dimensions)?;
         let total_cost: Decimal = lots.iter().map(|l| l.units * l.cost_per_unit).sum();
 
         Ok(DataValue::Money(total_cost / units))
@@ -502,8 +1071,93 @@ impl ScalarFunction for Lots {
             _ => return Err(EvaluationError::InvalidArgument("date".to_string())),
         };
 
-        let dim = extract_dimension_arg(&args, 2);
-        let lots = self.storage.get_lots(context.get_entity_id(), account_id, dim.as_ref())?;
+        let dimensions = extract_dimension_filter(&args, 2)?;
+        let lots = self.storage.get_lots(context.get_entity_id(), account_id, &dimensions)?;
         Ok(DataValue::Lots(lots))
     }
-}
\ No newline at end of file
+}
+
+/// depreciation_schedule(asset) — Monthly depreciation, accumulated depreciation and book
+/// value over the asset's life, up to its disposal.
+pub struct DepreciationSchedule {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl DepreciationSchedule {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for DepreciationSchedule {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let asset_id = match args.first() {
+            Some(DataValue::String(s)) => s,
+            _ => return Err(EvaluationError::InvalidArgument("asset".to_string())),
+        };
+
+        let asset = self.storage.get_fixed_asset(context.get_entity_id(), asset_id)?;
+        let items = depreciation_schedule(&asset)?.into_iter()
+            .filter(|item| asset.disposed_on.is_none_or(|disposed_on| item.period_end <= disposed_on))
+            .collect();
+        Ok(DataValue::DepreciationSchedule(items))
+    }
+
+    fn name_args(&self) -> &'static [usize] {
+        &[0]
+    }
+}
+
+/// journal(id) — Returns a single journal with its legs.
+pub struct Journal {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl Journal {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for Journal {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let journal_id = match args.first() {
+            Some(DataValue::String(s)) => parse_journal_id(s)
+                .ok_or_else(|| EvaluationError::InvalidArgument("journal_id".to_string()))?,
+            _ => return Err(EvaluationError::InvalidArgument("journal_id".to_string())),
+        };
+
+        let journal = self.storage.get_journal(context.get_entity_id(), journal_id)?;
+        Ok(DataValue::Journals(vec![journal]))
+    }
+}
+
+/// journals(from, to, [dimension]) — Returns all journals in a date range with their legs.
+pub struct Journals {
+    storage: Arc<dyn StorageBackend>,
+}
+
+impl Journals {
+    pub fn new(storage: Arc<dyn StorageBackend>) -> Self {
+        Self { storage }
+    }
+}
+
+impl ScalarFunction for Journals {
+    fn call(&self, context: &ExpressionEvaluationContext, args: Vec<DataValue>) -> Result<DataValue, EvaluationError> {
+        let from = match args.first() {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("from".to_string())),
+        };
+
+        let to = match args.get(1) {
+            Some(DataValue::Date(d)) => *d,
+            _ => return Err(EvaluationError::InvalidArgument("to".to_string())),
+        };
+
+        let dimensions = extract_dimension_filter(&args, 2)?;
+
+        let journals = self.storage.list_journals(context.get_entity_id(), from, to, &dimensions)?;
+        Ok(DataValue::Journals(journals))
+    }
+}
@@ -1,13 +1,18 @@
 use std::sync::Arc;
 
+use dblentry_core::params::{bind_params, FqlParam, FqlParams};
+use dblentry_core::JournalEntry;
 use tonic::{Request, Response, Status};
 
 use crate::{
+    auth::{self, Role},
+    config::AuthConfig,
     display::{format_data_value, format_execution_result},
-    evaluator::QueryVariables,
+    evaluator::{EvaluationError, QueryVariables},
+    functions::format_journal_id,
     lexer,
     models::DataValue,
-    storage::{StorageBackend, DEFAULT_ENTITY},
+    storage::{StorageBackend, StorageError, DEFAULT_ENTITY},
     statement_executor::{ExecutionContext, StatementExecutor},
 };
 
@@ -17,12 +22,8 @@ pub mod pb {
 
 use pb::dbl_entry_server::DblEntry;
 
-/// Escape a string value for safe interpolation into FQL single-quoted literals.
-fn escape_fql(s: &str) -> String {
-    s.replace('\'', "''")
-}
-
 /// Validate that a value contains only safe identifier characters.
+/// Identifiers are part of the statement structure; values are always bound as parameters.
 fn is_safe_identifier(s: &str) -> bool {
     !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
 }
@@ -35,33 +36,105 @@ fn validate_identifier(s: &str, field: &str) -> Result<(), Status> {
     Ok(())
 }
 
+/// Build the optional `, Key=$dimension` argument for balance/statement queries.
+#[allow(clippy::result_large_err)]
+fn dimension_arg(key: &Option<String>, value: &Option<String>, params: &mut FqlParams) -> Result<String, Status> {
+    match (key, value) {
+        (Some(k), Some(v)) => {
+            validate_identifier(k, "dimension key")?;
+            params.insert("dimension".to_string(), FqlParam::String(v.clone()));
+            Ok(format!(", {}=$dimension", k))
+        }
+        _ => Ok(String::new()),
+    }
+}
+
 /// Returns the entity_id to use, defaulting to DEFAULT_ENTITY if empty.
 fn resolve_entity_id(entity_id: &str) -> &str {
     if entity_id.is_empty() { DEFAULT_ENTITY } else { entity_id }
 }
 
+/// Convert gRPC parameters into query variables.
+fn bind_grpc_params(params: &std::collections::HashMap<String, pb::FqlParam>) -> Result<QueryVariables, String> {
+    let mut typed = FqlParams::new();
+    for (name, param) in params {
+        let value = FqlParam::from_parts(&param.r#type, param.value.clone())
+            .map_err(|e| format!("parameter ${}: {}", name, e))?;
+        typed.insert(name.clone(), value);
+    }
+    bind_params(&typed)
+}
+
+fn map_journal(journal: &JournalEntry) -> pb::Journal {
+    pb::Journal {
+        id: format_journal_id(journal.id),
+        sequence: journal.sequence,
+        date: journal.date.to_string(),
+        description: journal.description.to_string(),
+        amount: journal.amount.to_string(),
+        dimensions: journal.dimensions.iter()
+            .map(|(k, v)| (k.to_string(), format_data_value(v)))
+            .collect(),
+        lines: journal.lines.iter()
+            .map(|line| pb::JournalLine {
+                account_id: line.account_id.to_string(),
+                debit: line.debit.to_string(),
+                credit: line.credit.to_string(),
+            })
+            .collect(),
+        reverses: journal.reverses.map(format_journal_id),
+        reversed_by: journal.reversed_by.map(format_journal_id),
+    }
+}
+
 pub struct DblEntryService {
     executor: Arc<StatementExecutor>,
     storage: Arc<dyn StorageBackend>,
+    auth: Arc<AuthConfig>,
 }
 
 impl DblEntryService {
-    pub fn new(executor: Arc<StatementExecutor>, storage: Arc<dyn StorageBackend>) -> Self {
-        Self { executor, storage }
+    pub fn new(executor: Arc<StatementExecutor>, storage: Arc<dyn StorageBackend>, auth: Arc<AuthConfig>) -> Self {
+        Self { executor, storage, auth }
     }
 
+    /// Resolve the caller's role from `x-api-key` or `authorization` metadata.
     #[allow(clippy::result_large_err)]
-    fn execute_fql_with_entity(&self, fql: &str, entity_id: &str) -> Result<Vec<crate::statement_executor::ExecutionResult>, Status> {
+    fn caller_role<T>(&self, request: &Request<T>) -> Result<Role, Status> {
+        if !self.auth.enabled {
+            return Ok(Role::Admin);
+        }
+        let metadata = request.metadata();
+        let key = metadata
+            .get("x-api-key")
+            .or_else(|| metadata.get("authorization"))
+            .and_then(|v| v.to_str().ok())
+            .map(|s| s.strip_prefix("Bearer ").unwrap_or(s))
+            .ok_or_else(|| Status::unauthenticated("Missing API key. Provide x-api-key or authorization metadata"))?;
+        auth::authenticate(&self.auth, key)
+            .map(|caller| caller.role)
+            .ok_or_else(|| Status::unauthenticated("Invalid API key"))
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn execute_fql_with_entity(&self, fql: &str, params: FqlParams, entity_id: &str, role: Role) -> Result<Vec<crate::statement_executor::ExecutionResult>, Status> {
+        let variables = bind_params(&params).map_err(Status::invalid_argument)?;
         let statements = lexer::parse(fql)
             .map_err(|e| Status::invalid_argument(format!("Parse error: {}", e)))?;
 
         let eff_date = time::OffsetDateTime::now_utc().date();
-        let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+        let mut context = ExecutionContext::new(eff_date, variables);
         context.entity_id = Arc::from(resolve_entity_id(entity_id));
+        context.role = role;
 
         self.executor
             .execute_script(&mut context, &statements)
-            .map_err(|e| Status::internal(format!("{}", e)))
+            .map_err(|e| match e {
+                EvaluationError::PermissionDenied(_) => Status::permission_denied(format!("{}", e)),
+                EvaluationError::StorageError(StorageError::JournalNotFound(_)) => Status::not_found(format!("{}", e)),
+                EvaluationError::StorageError(StorageError::PeriodLocked(_)) => Status::failed_precondition(format!("{}", e)),
+                _ => Status::internal(format!("{}", e)),
+            })
     }
 }
 
@@ -71,6 +144,7 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::ExecuteFqlRequest>,
     ) -> Result<Response<pb::ExecuteFqlResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
         let entity_id = resolve_entity_id(&req.entity_id);
 
@@ -87,9 +161,23 @@ impl DblEntry for DblEntryService {
             }
         };
 
+        let variables = match bind_grpc_params(&req.params) {
+            Ok(v) => v,
+            Err(e) => {
+                return Ok(Response::new(pb::ExecuteFqlResponse {
+                    success: false,
+                    results: vec![],
+                    error: e,
+                    statements_executed: 0,
+                    journals_created: 0,
+                }));
+            }
+        };
+
         let eff_date = time::OffsetDateTime::now_utc().date();
-        let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+        let mut context = ExecutionContext::new(eff_date, variables);
         context.entity_id = Arc::from(entity_id);
+        context.role = role;
 
         match self.executor.execute_script(&mut context, &statements) {
             Ok(script_results) => {
@@ -124,6 +212,13 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::CreateEntityRequest>,
     ) -> Result<Response<pb::CreateEntityResponse>, Status> {
+        let role = self.caller_role(&request)?;
+        if !role.permits(Role::Admin) {
+            return Err(Status::permission_denied(format!(
+                "permission denied: role '{}' cannot run CREATE ENTITY (requires 'admin')",
+                role
+            )));
+        }
         let req = request.into_inner();
         self.storage.create_entity(&req.name)
             .map_err(|e| Status::internal(format!("{}", e)))?;
@@ -132,8 +227,9 @@ impl DblEntry for DblEntryService {
 
     async fn list_entities(
         &self,
-        _request: Request<pb::ListEntitiesRequest>,
+        request: Request<pb::ListEntitiesRequest>,
     ) -> Result<Response<pb::ListEntitiesResponse>, Status> {
+        self.caller_role(&request)?;
         let entities: Vec<String> = self.storage.list_entities().iter().map(|e| e.to_string()).collect();
         Ok(Response::new(pb::ListEntitiesResponse { entities }))
     }
@@ -142,11 +238,16 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::CreateAccountRequest>,
     ) -> Result<Response<pb::CreateAccountResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
         validate_identifier(&req.id, "account ID")?;
         validate_identifier(&req.account_type, "account type")?;
-        let fql = format!("CREATE ACCOUNT @{} {}", req.id, req.account_type.to_uppercase());
-        self.execute_fql_with_entity(&fql, &req.entity_id)?;
+        let mut fql = format!("CREATE ACCOUNT @{} {}", req.id, req.account_type.to_uppercase());
+        if !req.parent.is_empty() {
+            validate_identifier(&req.parent, "parent account ID")?;
+            fql.push_str(&format!(" UNDER @{}", req.parent));
+        }
+        self.execute_fql_with_entity(&fql, FqlParams::new(), &req.entity_id, role)?;
         Ok(Response::new(pb::CreateAccountResponse { success: true }))
     }
 
@@ -154,9 +255,10 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::ListAccountsRequest>,
     ) -> Result<Response<pb::ListAccountsResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
-        let fql = "GET trial_balance(2099-12-31) AS accounts";
-        let results = self.execute_fql_with_entity(fql, &req.entity_id)?;
+        let fql = "GET trial_balance(2099-12-31, tree) AS accounts";
+        let results = self.execute_fql_with_entity(fql, FqlParams::new(), &req.entity_id, role)?;
 
         let mut accounts = Vec::new();
         if let Some(result) = results.last() {
@@ -177,17 +279,15 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::GetBalanceRequest>,
     ) -> Result<Response<pb::GetBalanceResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
-        validate_identifier(&req.account_id, "account ID")?;
-        let dim = match (&req.dimension_key, &req.dimension_value) {
-            (Some(k), Some(v)) => {
-                validate_identifier(k, "dimension key")?;
-                format!(", {}='{}'", k, escape_fql(v))
-            }
-            _ => String::new(),
-        };
-        let fql = format!("GET balance(@{}, {}{}) AS result", req.account_id, req.date, dim);
-        let results = self.execute_fql_with_entity(&fql, &req.entity_id)?;
+        let mut params = FqlParams::from([
+            ("account".to_string(), FqlParam::Account(req.account_id)),
+            ("date".to_string(), FqlParam::Date(req.date)),
+        ]);
+        let dim = dimension_arg(&req.dimension_key, &req.dimension_value, &mut params)?;
+        let fql = format!("GET balance($account, $date{}) AS result", dim);
+        let results = self.execute_fql_with_entity(&fql, params, &req.entity_id, role)?;
 
         let balance = results
             .last()
@@ -205,26 +305,23 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::GetStatementRequest>,
     ) -> Result<Response<pb::GetStatementResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
-        validate_identifier(&req.account_id, "account ID")?;
-        let dim = match (&req.dimension_key, &req.dimension_value) {
-            (Some(k), Some(v)) => {
-                validate_identifier(k, "dimension key")?;
-                format!(", {}='{}'", k, escape_fql(v))
-            }
-            _ => String::new(),
-        };
-        let fql = format!(
-            "GET statement(@{}, {}, {}{}) AS result",
-            req.account_id, req.from_date, req.to_date, dim
-        );
-        let results = self.execute_fql_with_entity(&fql, &req.entity_id)?;
+        let mut params = FqlParams::from([
+            ("account".to_string(), FqlParam::Account(req.account_id)),
+            ("from".to_string(), FqlParam::Date(req.from_date)),
+            ("to".to_string(), FqlParam::Date(req.to_date)),
+        ]);
+        let dim = dimension_arg(&req.dimension_key, &req.dimension_value, &mut params)?;
+        let fql = format!("GET statement($account, $from, $to{}) AS result", dim);
+        let results = self.execute_fql_with_entity(&fql, params, &req.entity_id, role)?;
 
         let mut transactions = Vec::new();
         if let Some(result) = results.last() {
             if let Some(DataValue::Statement(txns)) = result.variables.get("result") {
                 for txn in txns {
                     transactions.push(pb::StatementTransaction {
+                        journal_id: format_journal_id(txn.journal_id),
                         date: format!("{}", txn.date),
                         description: txn.description.to_string(),
                         amount: txn.amount.to_string(),
@@ -237,22 +334,65 @@ impl DblEntry for DblEntryService {
         Ok(Response::new(pb::GetStatementResponse { transactions }))
     }
 
+    async fn get_journal(
+        &self,
+        request: Request<pb::GetJournalRequest>,
+    ) -> Result<Response<pb::GetJournalResponse>, Status> {
+        let role = self.caller_role(&request)?;
+        let req = request.into_inner();
+        let params = FqlParams::from([("id".to_string(), FqlParam::String(req.journal_id))]);
+        let results = self.execute_fql_with_entity("GET journal($id) AS result", params, &req.entity_id, role)?;
+
+        let journal = match results.last().and_then(|r| r.variables.get("result")) {
+            Some(DataValue::Journals(journals)) => journals.first().map(map_journal),
+            _ => None,
+        };
+
+        Ok(Response::new(pb::GetJournalResponse { journal }))
+    }
+
+    async fn list_journals(
+        &self,
+        request: Request<pb::ListJournalsRequest>,
+    ) -> Result<Response<pb::ListJournalsResponse>, Status> {
+        let role = self.caller_role(&request)?;
+        let req = request.into_inner();
+        let mut params = FqlParams::from([
+            ("from".to_string(), FqlParam::Date(req.from_date)),
+            ("to".to_string(), FqlParam::Date(req.to_date)),
+        ]);
+        let dim = dimension_arg(&req.dimension_key, &req.dimension_value, &mut params)?;
+        let fql = format!("GET journals($from, $to{}) AS result", dim);
+        let results = self.execute_fql_with_entity(&fql, params, &req.entity_id, role)?;
+
+        let journals = match results.last().and_then(|r| r.variables.get("result")) {
+            Some(DataValue::Journals(journals)) => journals.iter().map(map_journal).collect(),
+            _ => Vec::new(),
+        };
+
+        Ok(Response::new(pb::ListJournalsResponse { journals }))
+    }
+
     async fn get_trial_balance(
         &self,
         request: Request<pb::GetTrialBalanceRequest>,
     ) -> Result<Response<pb::GetTrialBalanceResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
-        let fql = format!("GET trial_balance({}) AS result", req.date);
-        let results = self.execute_fql_with_entity(fql.as_str(), &req.entity_id)?;
+        let params = FqlParams::from([("date".to_string(), FqlParam::Date(req.date))]);
+        let results = self.execute_fql_with_entity("GET trial_balance($date) AS result", params, &req.entity_id, role)?;
 
         let mut items = Vec::new();
         if let Some(result) = results.last() {
             if let Some(DataValue::TrialBalance(tb_items)) = result.variables.get("result") {
                 for item in tb_items {
+                    let (debit, credit) = item.debit_credit();
                     items.push(pb::TrialBalanceItem {
                         account_id: item.account_id.to_string(),
                         account_type: format!("{:?}", item.account_type),
                         balance: item.balance.to_string(),
+                        debit: debit.map(|d| d.to_string()).unwrap_or_default(),
+                        credit: credit.map(|c| c.to_string()).unwrap_or_default(),
                     });
                 }
             }
@@ -261,14 +401,51 @@ impl DblEntry for DblEntryService {
         Ok(Response::new(pb::GetTrialBalanceResponse { items }))
     }
 
+    async fn get_cash_flow_statement(
+        &self,
+        request: Request<pb::GetCashFlowStatementRequest>,
+    ) -> Result<Response<pb::GetCashFlowStatementResponse>, Status> {
+        let role = self.caller_role(&request)?;
+        let req = request.into_inner();
+        let params = FqlParams::from([
+            ("from".to_string(), FqlParam::Date(req.from_date)),
+            ("to".to_string(), FqlParam::Date(req.to_date)),
+        ]);
+        let results = self.execute_fql_with_entity("GET cash_flow_statement($from, $to) AS result", params, &req.entity_id, role)?;
+
+        match results.last().and_then(|r| r.variables.get("result")) {
+            Some(DataValue::CashFlowStatement(statement)) => Ok(Response::new(pb::GetCashFlowStatementResponse {
+                net_income: statement.net_income.to_string(),
+                sections: statement.sections.iter()
+                    .map(|section| pb::CashFlowSection {
+                        category: format!("{:?}", section.category).to_lowercase(),
+                        lines: section.lines.iter()
+                            .map(|line| pb::CashFlowLine {
+                                account_id: line.account_id.to_string(),
+                                amount: line.amount.to_string(),
+                            })
+                            .collect(),
+                        subtotal: section.subtotal.to_string(),
+                    })
+                    .collect(),
+                net_change: statement.net_change.to_string(),
+                opening_cash: statement.opening_cash.to_string(),
+                closing_cash: statement.closing_cash.to_string(),
+                reconciled: statement.reconciled,
+            })),
+            _ => Err(Status::internal("cash_flow_statement returned no result")),
+        }
+    }
+
     async fn create_rate(
         &self,
         request: Request<pb::CreateRateRequest>,
     ) -> Result<Response<pb::CreateRateResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
         validate_identifier(&req.id, "rate ID")?;
         let fql = format!("CREATE RATE {}", req.id);
-        self.execute_fql_with_entity(&fql, &req.entity_id)?;
+        self.execute_fql_with_entity(&fql, FqlParams::new(), &req.entity_id, role)?;
         Ok(Response::new(pb::CreateRateResponse { success: true }))
     }
 
@@ -276,10 +453,15 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::SetRateRequest>,
     ) -> Result<Response<pb::SetRateResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
         validate_identifier(&req.rate_id, "rate ID")?;
-        let fql = format!("SET RATE {} {} {}", req.rate_id, req.value, req.date);
-        self.execute_fql_with_entity(&fql, &req.entity_id)?;
+        let params = FqlParams::from([
+            ("rate".to_string(), FqlParam::Money(req.value)),
+            ("date".to_string(), FqlParam::Date(req.date)),
+        ]);
+        let fql = format!("SET RATE {} $rate $date", req.rate_id);
+        self.execute_fql_with_entity(&fql, params, &req.entity_id, role)?;
         Ok(Response::new(pb::SetRateResponse { success: true }))
     }
 
@@ -287,42 +469,46 @@ impl DblEntry for DblEntryService {
         &self,
         request: Request<pb::CreateJournalRequest>,
     ) -> Result<Response<pb::CreateJournalResponse>, Status> {
+        let role = self.caller_role(&request)?;
         let req = request.into_inner();
         for op in &req.operations {
             validate_identifier(&op.account, "account ID")?;
-            validate_identifier(&op.op_type, "operation type")?;
+            if !op.op_type.eq_ignore_ascii_case("debit") && !op.op_type.eq_ignore_ascii_case("credit") {
+                return Err(Status::invalid_argument(format!("Invalid operation type: {}", op.op_type)));
+            }
         }
         for k in req.dimensions.keys() {
             validate_identifier(k, "dimension key")?;
         }
-        let mut fql = format!(
-            "CREATE JOURNAL {}, {}, '{}'",
-            req.date, req.amount, escape_fql(&req.description)
-        );
+
+        let mut params = FqlParams::from([
+            ("date".to_string(), FqlParam::Date(req.date)),
+            ("amount".to_string(), FqlParam::Money(req.amount)),
+            ("description".to_string(), FqlParam::String(req.description)),
+        ]);
+        let mut fql = "CREATE JOURNAL $date, $amount, $description".to_string();
 
         if !req.dimensions.is_empty() {
-            let dims: Vec<String> = req
-                .dimensions
-                .iter()
-                .map(|(k, v)| format!("{}='{}'", k, escape_fql(v)))
-                .collect();
+            let mut dims = Vec::new();
+            for (i, (k, v)) in req.dimensions.into_iter().enumerate() {
+                dims.push(format!("{}=$dim{}", k, i));
+                params.insert(format!("dim{}", i), FqlParam::String(v));
+            }
             fql.push_str(&format!(" FOR {}", dims.join(", ")));
         }
 
-        let ops: Vec<String> = req
-            .operations
-            .iter()
-            .map(|op| {
-                let mut s = format!("{} @{}", op.op_type.to_uppercase(), op.account);
-                if let Some(ref amt) = op.amount {
-                    s.push_str(&format!(" {}", amt));
-                }
-                s
-            })
-            .collect();
+        let mut ops = Vec::new();
+        for (i, op) in req.operations.into_iter().enumerate() {
+            let mut s = format!("{} @{}", op.op_type.to_uppercase(), op.account);
+            if let Some(amt) = op.amount {
+                s.push_str(&format!(" $op{}", i));
+                params.insert(format!("op{}", i), FqlParam::Money(amt));
+            }
+            ops.push(s);
+        }
         fql.push_str(&format!(" {}", ops.join(", ")));
 
-        self.execute_fql_with_entity(&fql, &req.entity_id)?;
+        self.execute_fql_with_entity(&fql, params, &req.entity_id, role)?;
         Ok(Response::new(pb::CreateJournalResponse { success: true }))
     }
 
@@ -24,6 +24,9 @@ peg::parser! {
         rule kw_compound()  = ("COMPOUND" / "compound")
         rule kw_daily()     = ("DAILY" / "daily")
         rule kw_continuous() = ("CONTINUOUS" / "continuous")
+        rule kw_annually()  = ("ANNUALLY" / "annually")
+        rule kw_simple()    = ("SIMPLE" / "simple")
+        rule kw_daycount()  = ("DAYCOUNT" / "daycount")
 
         rule kw_debit()     = ("DEBIT" / "debit")
         rule kw_credit()    = ("CREDIT" / "credit")
@@ -33,6 +36,7 @@ peg::parser! {
         rule kw_income()    = ("INCOME" / "income")
         rule kw_expense()   = ("EXPENSE" / "expense")
         rule kw_equity()    = ("EQUITY" / "equity")
+        rule kw_statistical() = ("STATISTICAL" / "statistical")
 
         rule kw_for()       = ("FOR" / "for")
         rule kw_from()      = ("FROM" / "from")
@@ -68,10 +72,30 @@ peg::parser! {
         rule kw_use()       = ("USE" / "use")
         rule kw_distribute() = ("DISTRIBUTE" / "distribute")
         rule kw_period()    = ("PERIOD" / "period")
+        rule kw_weekly()    = ("WEEKLY" / "weekly")
         rule kw_monthly()   = ("MONTHLY" / "monthly")
         rule kw_quarterly() = ("QUARTERLY" / "quarterly")
+        rule kw_semiannually() = ("SEMIANNUALLY" / "semiannually")
         rule kw_yearly()    = ("YEARLY" / "yearly")
+        rule kw_anchored()  = ("ANCHORED" / "anchored")
         rule kw_prorate()   = ("PRORATE" / "prorate")
+        rule kw_weights()   = ("WEIGHTS" / "weights")
+        rule kw_allocate()  = ("ALLOCATE" / "allocate")
+        rule kw_across()    = ("ACROSS" / "across")
+        rule kw_amortize()  = ("AMORTIZE" / "amortize")
+        rule kw_over()      = ("OVER" / "over")
+        rule kw_preview()   = ("PREVIEW" / "preview")
+        rule kw_service()   = ("SERVICE" / "service")
+        rule kw_cost()      = ("COST" / "cost")
+        rule kw_life()      = ("LIFE" / "life")
+        rule kw_years()     = ("YEARS" / "years")
+        rule kw_salvage()   = ("SALVAGE" / "salvage")
+        rule kw_accumulated() = ("ACCUMULATED" / "accumulated")
+        rule kw_straight_line() = ("STRAIGHT_LINE" / "straight_line")
+        rule kw_declining_balance() = ("DECLINING_BALANCE" / "declining_balance")
+        rule kw_sum_of_years() = ("SUM_OF_YEARS" / "sum_of_years")
+        rule kw_depreciate() = ("DEPRECIATE" / "depreciate")
+        rule kw_dispose()   = ("DISPOSE" / "dispose")
         rule kw_description() = ("DESCRIPTION" / "description")
         rule kw_units()     = ("UNITS" / "units")
         rule kw_sell()      = ("SELL" / "sell")
@@ -85,6 +109,25 @@ peg::parser! {
         rule kw_proceeds()  = ("PROCEEDS" / "proceeds")
         rule kw_gain_loss() = ("GAIN_LOSS" / "gain_loss")
         rule kw_split()     = ("SPLIT" / "split")
+        rule kw_let()       = ("LET" / "let")
+        rule kw_reverse()   = ("REVERSE" / "reverse")
+        rule kw_auto()      = ("AUTO" / "auto")
+        rule kw_under()     = ("UNDER" / "under")
+        rule kw_lock()      = ("LOCK" / "lock")
+        rule kw_unlock()    = ("UNLOCK" / "unlock")
+        rule kw_through()   = ("THROUGH" / "through")
+        rule kw_close()     = ("CLOSE" / "close")
+        rule kw_year()      = ("YEAR" / "year")
+        rule kw_ending()    = ("ENDING" / "ending")
+        rule kw_current()   = ("CURRENT" / "current")
+        rule kw_noncurrent() = ("NONCURRENT" / "noncurrent")
+        rule kw_cashflow()  = ("CASHFLOW" / "cashflow")
+        rule kw_currency()  = ("CURRENCY" / "currency")
+        rule kw_functional() = ("FUNCTIONAL" / "functional")
+        rule kw_cash()      = ("CASH" / "cash")
+        rule kw_operating() = ("OPERATING" / "operating")
+        rule kw_investing() = ("INVESTING" / "investing")
+        rule kw_financing() = ("FINANCING" / "financing")
 
         rule _()
             = [' ']
@@ -140,25 +183,32 @@ peg::parser! {
 
         // e.g. 'TRUE', '42', 'hello world'
         rule literal() -> Literal
-            = r:real() { Literal::Real(r) }
+            = pr:real() "%" !percent_operand() { Literal::Percentage(pr) }
+            / pi:$("-"? num()+) "%" !percent_operand() { Literal::Percentage(Arc::from(pi)) }
+            / r:real() { Literal::Real(r) }
             / d:date() { Literal::Date(d) }
             / i:integer() { Literal::Integer(i) }
             / b:boolean() { Literal::Boolean(b) }
             / t:text() { Literal::Text(t) }
             / a:account_id() { Literal::Account(a) }            
-            / pr:real() "%" { Literal::Percentage(pr) }            
-            / pi:$("-"? num()+) "%" { Literal::Percentage(Arc::from(pi)) }            
             / kw_null() { Literal::Null }
 
+        // `10%3` and `a%b` are modulos, not a percentage followed by an operand
+        rule percent_operand() = alpha_num() / ['(' | '@' | '$']
+
 
         rule unit_spec() -> UnitSpec
             = units:expression() __+ kw_units() __+ kw_at() __+ price:expression() { UnitSpec { units, price } }
 
+        // A fixed amount on a currency account may give its functional value: `100 FUNCTIONAL 86`
+        rule leg_amount() -> (Expression, Option<Expression>)
+            = amount:expression() functional:(__+ kw_functional() __+ f:expression() { f })? { (amount, functional) }
+
         rule ledger_operation() -> LedgerOperation
-            = kw_debit() __+ account:account_id() __+ us:unit_spec() { LedgerOperation::Debit(LedgerOperationData { account, amount: None, unit_spec: Some(us) }) }
-            / kw_debit() __+ account:account_id() __* amount:expression()? { LedgerOperation::Debit(LedgerOperationData { account, amount, unit_spec: None }) }
-            / kw_credit() __+ account:account_id() __+ us:unit_spec() { LedgerOperation::Credit(LedgerOperationData { account, amount: None, unit_spec: Some(us) }) }
-            / kw_credit() __+ account:account_id() __* amount:expression()? { LedgerOperation::Credit(LedgerOperationData { account, amount, unit_spec: None }) }
+            = kw_debit() __+ account:account_id() __+ us:unit_spec() { LedgerOperation::Debit(LedgerOperationData { account, amount: None, functional_amount: None, unit_spec: Some(us) }) }
+            / kw_debit() __+ account:account_id() __* amount:leg_amount()? { let (amount, functional_amount) = amount.unzip(); LedgerOperation::Debit(LedgerOperationData { account, amount, functional_amount: functional_amount.flatten(), unit_spec: None }) }
+            / kw_credit() __+ account:account_id() __+ us:unit_spec() { LedgerOperation::Credit(LedgerOperationData { account, amount: None, functional_amount: None, unit_spec: Some(us) }) }
+            / kw_credit() __+ account:account_id() __* amount:leg_amount()? { let (amount, functional_amount) = amount.unzip(); LedgerOperation::Credit(LedgerOperationData { account, amount, functional_amount: functional_amount.flatten(), unit_spec: None }) }
 
         rule ledger_operations() -> Vec<LedgerOperation>
             = ledger_operations:(ledger_operation() ** (__* "," __*)) { ledger_operations }
@@ -187,7 +237,7 @@ peg::parser! {
                 a:(@) __* "<=" __* b:@ { BinaryExpression::le(a, b) }
                 a:(@) __* ">"  __* b:@ { BinaryExpression::gt(a, b) }
                 a:(@) __* ">=" __* b:@ { BinaryExpression::ge(a, b) }
-                a:(@) __* kw_in() __* b:@ { BinaryExpression::in_(a, b) }
+                a:(@) __* kw_in() !alpha_num() __* b:@ { BinaryExpression::in_(a, b) }
                 --
                 a:(@) __* "+" __* b:@ { BinaryExpression::add(a, b) }
                 a:(@) __* "-" __* b:@ { BinaryExpression::subtract(a, b) }
@@ -231,15 +281,19 @@ peg::parser! {
             = x:(name:ident() __* "=" __* value:expression() __* { (name, value) })
 
         rule journal() -> JournalExpression
-            = kw_journal() __* date:expression() __* "," __* amount:expression() __* "," __* description:expression() __* dims:(kw_for() __+ dims:dimensions() {dims})? __* ops:ledger_operations() { JournalExpression {
+            = kw_journal() __* date:expression() __* "," __* amount:expression() __* "," __* description:expression() __* dims:(kw_for() __+ dims:dimensions() {dims})? __* auto_reverse:auto_reverse()? __* ops:ledger_operations() { JournalExpression {
                     date,
                     amount,
                     description,
                     operations: ops,
                     dimensions: dims.unwrap_or_default(),
+                    auto_reverse,
                 } 
             }
 
+        rule auto_reverse() -> Expression
+            = kw_auto() __+ kw_reverse() __+ kw_on() __+ date:expression() { date }
+
         rule into_journal() -> IntoJournalExpression
             = kw_into() __+ kw_journal() __+ date:expression() __* "," __* description:expression() __* ops:ledger_operations() { IntoJournalExpression {
                     date,
@@ -254,16 +308,31 @@ peg::parser! {
             / kw_income() { AccountType::Income }
             / kw_expense() { AccountType::Expense }
             / kw_equity() { AccountType::Equity }
+            / kw_statistical() { AccountType::Statistical }
         
         rule account() -> AccountExpression
-            = kw_account() __* id:account_id() __+ account_type:account_type() rate_id:(__+ u:units_clause() { u })? { 
+            = kw_account() __* id:account_id() __+ account_type:account_type() class:(__+ c:account_class() { c })? cash_flow:(__+ kw_cashflow() __+ c:cash_flow_category() { c })? currency:(__+ kw_currency() __+ c:text() { c })? rate_id:(__+ u:units_clause() { u })? parent:(__+ kw_under() __+ p:account_id() { p })? { 
                 AccountExpression { 
                     id, 
                     account_type,
                     unit_rate_id: rate_id,
+                    parent,
+                    class,
+                    cash_flow,
+                    currency,
                 } 
             }
 
+        rule account_class() -> AccountClass
+            = kw_noncurrent() { AccountClass::NonCurrent }
+            / kw_current() { AccountClass::Current }
+
+        rule cash_flow_category() -> CashFlowCategory
+            = kw_cash() { CashFlowCategory::Cash }
+            / kw_operating() { CashFlowCategory::Operating }
+            / kw_investing() { CashFlowCategory::Investing }
+            / kw_financing() { CashFlowCategory::Financing }
+
         rule units_clause() -> Arc<str>
             = kw_units() __+ rate_id:text() { rate_id }
 
@@ -277,46 +346,154 @@ peg::parser! {
         rule compound() -> Compounding
             = kw_compound() __+ kw_daily() { Compounding::Daily }
             / kw_compound() __+ kw_continuous() { Compounding::Continuous }
+            / kw_compound() __+ kw_monthly() { Compounding::Monthly }
+            / kw_compound() __+ kw_quarterly() { Compounding::Quarterly }
+            / kw_compound() __+ kw_annually() { Compounding::Annually }
+            / kw_compound() __+ kw_simple() { Compounding::Simple }
+
+        rule day_count() -> DayCount
+            = kw_daycount() __+ d:(
+                ("ACT/365F" / "act/365f") { DayCount::Act365Fixed }
+                / ("ACT/360" / "act/360") { DayCount::Act360 }
+                / ("ACT/ACT" / "act/act") { DayCount::ActAct }
+                / "30/360" { DayCount::Thirty360 }
+            ) { d }
 
 
         rule accrue_command() -> AccrueCommand
-            = kw_accrue() __+ account_id:account_id() __+ kw_from() __+ start_date:expression() __+ kw_to() __+ end_date:expression() __+ kw_with() __+ kw_rate() __+ rate_id:ident() __* compounding:compound()? __* kw_by() __+ by_dimension:ident() __+ into_journal:into_journal() { 
+            = kw_accrue() __+ account_id:account_id() __+ kw_from() __+ start_date:expression() __+ kw_to() __+ end_date:expression() __+ kw_with() __+ kw_rate() __+ rate:expression() __* basis:accrual_basis()? __* day_count:day_count()? __* by_dimensions:accrue_by()? __* into_journal:into_journal() { 
+                let (compounding, average_balance) = basis.unwrap_or((None, false));
                 AccrueCommand {
                     account_id,
-                    rate_id,
+                    rate,
                     compounding,
+                    average_balance,
+                    day_count,
                     start_date,
                     end_date,
-                    by_dimension,
+                    by_dimensions: by_dimensions.unwrap_or_default(),
                     into_journal,
                 } 
             }
 
+        rule accrue_by() -> Vec<Arc<str>>
+            = kw_by() __+ d:ident() ++ (__* "," __*) { d }
+
+        rule accrual_basis() -> (Option<Compounding>, bool)
+            = c:compound() { (Some(c), false) }
+            / kw_on() __+ kw_average() __+ kw_balance() { (None, true) }
+
         rule period() -> Period
-            = kw_monthly() { Period::Monthly }
+            = kw_daily() { Period::Daily }
+            / kw_weekly() { Period::Weekly }
+            / kw_monthly() { Period::Monthly }
             / kw_quarterly() { Period::Quarterly }
+            / kw_semiannually() { Period::SemiAnnually }
             / kw_yearly() { Period::Yearly }
 
         rule distribute_command() -> DistributeCommand
-            = kw_distribute() __+ amount:expression() __+ kw_from() __+ start_date:expression() __+ kw_to() __+ end_date:expression() __+ kw_period() __+ period:period() __* prorate:(kw_prorate() { true })? __* dims:(kw_for() __+ dims:dimensions() {dims})? __* kw_description() __+ description:expression() __* ops:ledger_operations() {
+            = kw_distribute() __+ amount:expression() __+ schedule:distribute_schedule() __* dims:(kw_for() __+ dims:dimensions() {dims})? __* kw_description() __+ description:expression() __* ops:ledger_operations() {
                 DistributeCommand {
                     amount,
+                    schedule,
+                    dimensions: dims.unwrap_or_default(),
+                    operations: ops,
+                    description,
+                }
+            }
+            
+
+        rule distribute_schedule() -> DistributeSchedule
+            = kw_from() __+ start_date:expression() __+ kw_to() __+ end_date:expression() __+ kw_period() __+ period:period() __* anchored:(kw_anchored() { true })? __* prorate:(kw_prorate() { true })? {
+                DistributeSchedule::Periods {
                     start_date,
                     end_date,
                     period,
+                    anchored: anchored.unwrap_or(false),
                     prorate: prorate.unwrap_or(false),
+                }
+            }
+            / kw_on() __* dates:expression() __* weights:(kw_weights() __* w:expression() { w })? {
+                DistributeSchedule::Dates { dates, weights }
+            }
+
+        rule allocate_command() -> AllocateCommand
+            = kw_allocate() __+ amount:expression() __+ kw_on() __+ date:expression() __+ kw_across() __+ dimension:ident() __+ kw_by() __+ driver:allocation_driver() __* dims:(kw_for() __+ dims:dimensions() {dims})? __* kw_description() __+ description:expression() __* ops:ledger_operations() {
+                AllocateCommand {
+                    amount,
+                    date,
+                    dimension,
+                    driver,
                     dimensions: dims.unwrap_or_default(),
                     operations: ops,
                     description,
                 }
             }
-            
+
+        rule allocation_driver() -> AllocationDriver
+            = kw_weights() __* "{" __* weights:(value:expression() __* ":" __* weight:expression() { (value, weight) }) ** (__* "," __*) __* "}" { AllocationDriver::Weights(weights) }
+            / driver:expression() { AllocationDriver::Expression(driver) }
+
+        rule amortize_command() -> AmortizeCommand
+            = kw_amortize() __+ principal:expression() __+ kw_at() __+ kw_rate() __+ rate:expression() __+ kw_over() __+ payments:expression() __+ period:period() __+ kw_from() __+ start_date:expression() __* dims:(kw_for() __+ dims:dimensions() {dims})? __* preview:(kw_preview() { true })? __* journal:(kw_description() __+ description:expression() __* ops:ledger_operations() { (description, ops) })? {
+                AmortizeCommand {
+                    principal,
+                    rate,
+                    payments,
+                    period,
+                    start_date,
+                    dimensions: dims.unwrap_or_default(),
+                    preview: preview.unwrap_or(false),
+                    journal,
+                }
+            }
+
+        rule asset() -> CreateAssetExpression
+            = kw_asset() __+ id:ident() __+ kw_in() __+ kw_service() __+ in_service:expression() __+ kw_cost() __+ cost:expression() __+ kw_life() __+ life_years:expression() __+ kw_years() salvage:(__+ kw_salvage() __+ s:expression() { s })? method:(__+ kw_method() __+ m:depreciation_method() { m })? __+ kw_account() __+ asset_account:account_id() __+ kw_accumulated() __+ accumulated_account:account_id() __+ kw_expense() __+ expense_account:account_id() {
+                CreateAssetExpression {
+                    id,
+                    in_service,
+                    cost,
+                    life_years,
+                    salvage,
+                    method: method.unwrap_or(DepreciationMethodExpression::StraightLine),
+                    asset_account,
+                    accumulated_account,
+                    expense_account,
+                }
+            }
+
+        rule depreciation_method() -> DepreciationMethodExpression
+            = kw_straight_line() { DepreciationMethodExpression::StraightLine }
+            / kw_declining_balance() factor:(__+ !kw_account() f:expression() { f })? { DepreciationMethodExpression::DecliningBalance(factor) }
+            / kw_sum_of_years() { DepreciationMethodExpression::SumOfYears }
+
+        rule depreciate_command() -> DepreciateCommand
+            = kw_depreciate() __+ asset:(!kw_through() a:ident() __+ { a })? kw_through() __+ through:expression() {
+                DepreciateCommand {
+                    asset,
+                    through,
+                }
+            }
+
+        rule dispose_command() -> DisposeCommand
+            = kw_dispose() __+ kw_asset() __+ asset:ident() __+ kw_at() __+ proceeds:expression() __+ kw_on() __+ date:expression() __+ kw_proceeds() __+ proceeds_account:account_id() __+ kw_gain_loss() __+ gain_loss_account:account_id() __+ kw_description() __+ description:expression() {
+                DisposeCommand {
+                    asset,
+                    proceeds,
+                    date,
+                    proceeds_account,
+                    gain_loss_account,
+                    description,
+                }
+            }
 
         rule set_command() -> SetCommand
-            = kw_set() __+ kw_rate() __+ id:ident() __+ rate:expression() __+ date:expression() { SetCommand::Rate(SetRateExpression { 
+            = kw_set() __+ kw_rate() __+ id:ident() __+ rate:expression() __+ date:expression() dimension:(__+ kw_for() __+ d:dimension() { d })? { SetCommand::Rate(SetRateExpression { 
                 id, 
                 date, 
-                rate
+                rate,
+                dimension,
             })}
 
         rule cost_method() -> CostMethod
@@ -349,11 +526,38 @@ peg::parser! {
                 }
             }
 
+        rule close_year_command() -> CloseYearCommand
+            = kw_close() __+ kw_year() __+ kw_ending() __+ year_end:expression() __+ kw_into() __+ retained_earnings:account_id() by_dimension:(__+ kw_by() __+ d:ident() { d })? {
+                CloseYearCommand {
+                    year_end,
+                    retained_earnings,
+                    by_dimension,
+                }
+            }
+
+        rule reverse_command() -> ReverseCommand
+            = kw_reverse() __+ kw_journal() __+ journal_id:expression() __+ kw_on() __+ date:expression() description:(__+ kw_description() __+ d:expression() { d })? {
+                ReverseCommand {
+                    journal_id,
+                    date,
+                    description,
+                }
+            }
+
+        rule let_command() -> LetCommand
+            = kw_let() __+ name:ident() __* "=" __* value:expression() {
+                LetCommand {
+                    name,
+                    value,
+                }
+            }
+
         rule create_command() -> CreateCommand
             = kw_create() __+ kw_entity() __+ name:text()  { CreateCommand::Entity(name) }
             / kw_create() __* journal:journal()  { CreateCommand::Journal(journal) }
             / kw_create() __* account:account()  { CreateCommand::Account(account) }
             / kw_create() __* rate:rate()  { CreateCommand::Rate(rate) }
+            / kw_create() __+ asset:asset()  { CreateCommand::Asset(asset) }
         
         pub rule statement() -> Statement
             = c:create_command() { Statement::Create(c) }
@@ -362,8 +566,17 @@ peg::parser! {
             / s:set_command() { Statement::Set(s) }
             / a:accrue_command() { Statement::Accrue(a) }
             / d:distribute_command() { Statement::Distribute(d) }
+            / a:allocate_command() { Statement::Allocate(a) }
+            / a:amortize_command() { Statement::Amortize(a) }
+            / d:depreciate_command() { Statement::Depreciate(d) }
+            / d:dispose_command() { Statement::Dispose(d) }
             / sl:sell_command() { Statement::Sell(sl) }
             / sp:split_command() { Statement::Split(sp) }
+            / r:reverse_command() { Statement::Reverse(r) }
+            / l:let_command() { Statement::Let(l) }
+            / kw_lock() __+ kw_period() __+ kw_through() __+ date:expression() { Statement::LockPeriod(date) }
+            / kw_unlock() (__+ kw_period())? { Statement::Unlock }
+            / c:close_year_command() { Statement::CloseYear(c) }
             / kw_begin() { Statement::Begin }
             / kw_commit() { Statement::Commit }
             / kw_rollback() { Statement::Rollback }
@@ -3,8 +3,10 @@ use std::sync::Arc;
 use axum::{Router, routing::{post, get}, extract::{State, Path, Query, DefaultBodyLimit}, response::IntoResponse, http::StatusCode, Json, middleware, Extension};
 use clap::Parser;
 use dblentry::grpc::{pb::dbl_entry_server::DblEntryServer, DblEntryService};
-use dblentry::auth::auth_middleware;
+use dblentry::auth::{auth_middleware, CallerIdentity, Role};
 use dblentry::config::{CliArgs, Config};
+use dblentry::ast::{CreateCommand, Statement as AstStatement};
+use dblentry::dblentry_core::params::{bind_params, FqlParam, FqlParams};
 use dblentry::functions::{Statement, TrialBalance};
 use dblentry::api::v1::handlers::fql_handler_v1;
 use dblentry::api::v1::schema::{SchemaState, schema_overview, schema_entity};
@@ -12,7 +14,7 @@ use dblentry::api::v1::handlers::batch_fql_handler;
 use dblentry::api::v1::spec::fql_spec_handler;
 use dblentry::api::v1::nl::{nl_handler, NlState};
 use dblentry::idempotency::IdempotencyStore;
-use dblentry::{display::format_execution_result, statement_executor::{StatementExecutor, ExecutionContext}, storage::StorageBackend, evaluator::{ExpressionEvaluator, QueryVariables}, function_registry::{FunctionRegistry, Function}, functions::{Balance, IncomeStatement, AccountCount, Convert, FxRate, Round, Abs, Min, Max, Units, MarketValue, UnrealizedGain, CostBasis, Lots}, lexer};
+use dblentry::{display::format_execution_result, statement_executor::{StatementExecutor, ExecutionContext}, storage::StorageBackend, evaluator::{ExpressionEvaluator, QueryVariables}, function_registry::{FunctionRegistry, Function}, functions::{Balance, IncomeStatement, BalanceSheet, CashFlowStatement, AccountCount, Convert, FxRate, Round, Abs, Min, Max, Units, MarketValue, UnrealizedGain, CostBasis, Lots, Journal, Journals, BalanceBy, ActivityBy, Debits, Credits, Activity, BalanceSeries, AverageBalance, DepreciationSchedule}, lexer};
 use dblentry_memory::InMemoryStorage;
 use dblentry_sqlite::SqliteStorage;
 use dblentry_postgres::PostgresStorage;
@@ -26,17 +28,20 @@ struct CreateEntityRestRequest {
     name: String,
 }
 
-use dblentry::api::{TextFqlResponse, TextFqlMetadata};
+use dblentry::api::{execution_error_status, TextFqlResponse, TextFqlMetadata};
 
 struct DblEntryFqlEngine {
     executor: Arc<StatementExecutor>,
+    role: Role,
 }
 
 impl dblentry_mcp::FqlEngine for DblEntryFqlEngine {
-    fn execute_fql(&self, fql: &str, entity: Option<&str>) -> Result<Vec<dblentry_mcp::FqlResult>, String> {
+    fn execute_fql(&self, fql: &str, entity: Option<&str>, params: &FqlParams) -> Result<Vec<dblentry_mcp::FqlResult>, String> {
+        let variables = bind_params(params)?;
         let statements = lexer::parse(fql).map_err(|e| format!("Parse error: {}", e))?;
         let eff_date = time::OffsetDateTime::now_utc().date();
-        let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+        let mut context = ExecutionContext::new(eff_date, variables);
+        context.role = self.role;
         if let Some(entity) = entity {
             context.entity_id = Arc::from(entity);
         }
@@ -101,6 +106,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     function_registry.register_function("statement", Function::Scalar(Arc::new(Statement::new(storage.clone()))));
     function_registry.register_function("trial_balance", Function::Scalar(Arc::new(TrialBalance::new(storage.clone()))));
     function_registry.register_function("income_statement", Function::Scalar(Arc::new(IncomeStatement::new(storage.clone()))));
+    function_registry.register_function("balance_sheet", Function::Scalar(Arc::new(BalanceSheet::new(storage.clone()))));
+    function_registry.register_function("cash_flow_statement", Function::Scalar(Arc::new(CashFlowStatement::new(storage.clone()))));
     function_registry.register_function("account_count", Function::Scalar(Arc::new(AccountCount::new(storage.clone()))));
     function_registry.register_function("convert", Function::Scalar(Arc::new(Convert::new(storage.clone()))));
     function_registry.register_function("fx_rate", Function::Scalar(Arc::new(FxRate::new(storage.clone()))));
@@ -113,14 +120,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     function_registry.register_function("unrealized_gain", Function::Scalar(Arc::new(UnrealizedGain::new(storage.clone()))));
     function_registry.register_function("cost_basis", Function::Scalar(Arc::new(CostBasis::new(storage.clone()))));
     function_registry.register_function("lots", Function::Scalar(Arc::new(Lots::new(storage.clone()))));
+    function_registry.register_function("journal", Function::Scalar(Arc::new(Journal::new(storage.clone()))));
+    function_registry.register_function("journals", Function::Scalar(Arc::new(Journals::new(storage.clone()))));
+    function_registry.register_function("balance_by", Function::Scalar(Arc::new(BalanceBy::new(storage.clone()))));
+    function_registry.register_function("activity_by", Function::Scalar(Arc::new(ActivityBy::new(storage.clone()))));
+    function_registry.register_function("debits", Function::Scalar(Arc::new(Debits::new(storage.clone()))));
+    function_registry.register_function("credits", Function::Scalar(Arc::new(Credits::new(storage.clone()))));
+    function_registry.register_function("activity", Function::Scalar(Arc::new(Activity::new(storage.clone()))));
+    function_registry.register_function("balance_series", Function::Scalar(Arc::new(BalanceSeries::new(storage.clone()))));
+    function_registry.register_function("average_balance", Function::Scalar(Arc::new(AverageBalance::new(storage.clone()))));
+    function_registry.register_function("depreciation_schedule", Function::Scalar(Arc::new(DepreciationSchedule::new(storage.clone()))));
     let function_registry = Arc::new(function_registry);
     let expression_evaluator = Arc::new(ExpressionEvaluator::new(function_registry.clone(), storage.clone()));
     let exec = StatementExecutor::new(expression_evaluator, storage.clone());
     let state = Arc::new(exec);
     
     if cli.mcp {
-        tracing::info!("Starting DblEntry MCP server over stdio");
-        let engine = Arc::new(DblEntryFqlEngine { executor: state.clone() });
+        let role = Role::parse(&cli.mcp_role);
+        tracing::info!(role = %role, "Starting DblEntry MCP server over stdio");
+        let engine = Arc::new(DblEntryFqlEngine { executor: state.clone(), role });
         dblentry_mcp::run_mcp_stdio(engine, storage.clone()).await?;
         return Ok(());
     }
@@ -149,7 +167,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/accounts/:id/statement", get(rest_get_statement))
         .route("/api/rates", post(rest_create_rate))
         .route("/api/rates/:id", post(rest_set_rate))
-        .route("/api/journals", post(rest_create_journal))
+        .route("/api/journals", post(rest_create_journal).get(rest_list_journals))
+        .route("/api/journals/:id", get(rest_get_journal))
         .route("/api/trial-balance", get(rest_trial_balance))
         .route("/api/entities", get(rest_list_entities).post(rest_create_entity))
         .route("/", post(fql_handler))
@@ -157,7 +176,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .route("/api/v1/schema/entities/:entity_id", get(schema_entity))
         .with_state(state.clone())
         .layer(middleware::from_fn(auth_middleware))
-        .layer(Extension(auth_config))
+        .layer(Extension(auth_config.clone()))
         .layer(Extension(idempotency_store))
         .layer(Extension(SchemaState {
             storage: storage.clone(),
@@ -186,7 +205,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let grpc_addr = format!("{}:{}", config.server.host, config.grpc.port)
             .parse()
             .expect("Invalid gRPC listen address");
-        let grpc_service = DblEntryService::new(state.clone(), storage.clone());
+        let grpc_service = DblEntryService::new(state.clone(), storage.clone(), auth_config.clone());
         tracing::info!("DblEntry gRPC listening on {}", grpc_addr);
 
         let grpc_server = tonic::transport::Server::builder()
@@ -227,6 +246,7 @@ async fn health_handler() -> Json<HealthResponse> {
 
 async fn fql_handler(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     query: String
 ) -> impl IntoResponse {
     counter!("fql_requests_total", 1);
@@ -249,6 +269,7 @@ async fn fql_handler(
 
     let eff_date = time::OffsetDateTime::now_utc().date();
     let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+    context.role = caller.role;
     
     match exec.execute_script(&mut context, &statements) {
         Ok(script_results) => {
@@ -294,38 +315,25 @@ async fn fql_handler(
                 error: Some(format!("{}", e)),
                 metadata: TextFqlMetadata { statements_executed: 0, journals_created: 0 },
             };
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(resp))
+            (execution_error_status(&e), Json(resp))
         }
     }
 }
 
-/// Escape a string value for safe interpolation into FQL single-quoted literals.
-/// Doubles any single quotes to prevent FQL injection.
-fn escape_fql(s: &str) -> String {
-    s.replace('\'', "''")
-}
-
 /// Validate that a value contains only safe identifier characters (alphanumeric, underscore, hyphen).
+/// Identifiers are part of the statement structure; values are always bound as parameters.
 fn is_safe_identifier(s: &str) -> bool {
     !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
 }
 
-/// Validate that a string is a safe date literal (YYYY-MM-DD format only).
-fn is_safe_date(s: &str) -> bool {
-    s.len() == 10
-        && s.as_bytes()[4] == b'-'
-        && s.as_bytes()[7] == b'-'
-        && s.chars().enumerate().all(|(i, c)| {
-            if i == 4 || i == 7 { c == '-' } else { c.is_ascii_digit() }
-        })
-}
-
 // --- REST API types ---
 
 #[derive(Deserialize)]
 struct CreateAccountRequest {
     id: String,
     account_type: String,
+    #[serde(default)]
+    parent: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -377,6 +385,16 @@ struct StatementQuery {
     dimension_value: Option<String>,
 }
 
+#[derive(Deserialize)]
+struct JournalsQuery {
+    from: String,
+    to: String,
+    #[serde(default)]
+    dimension_key: Option<String>,
+    #[serde(default)]
+    dimension_value: Option<String>,
+}
+
 #[derive(Deserialize)]
 struct TrialBalanceQuery {
     date: String,
@@ -407,6 +425,7 @@ fn rest_err<T: Serialize>(status: StatusCode, msg: String) -> (StatusCode, Json<
 
 async fn rest_create_account(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Json(req): Json<CreateAccountRequest>,
 ) -> impl IntoResponse {
     if !is_safe_identifier(&req.id) {
@@ -415,16 +434,23 @@ async fn rest_create_account(
     if !is_safe_identifier(&req.account_type) {
         return rest_err(StatusCode::BAD_REQUEST, "Invalid account type".to_string());
     }
-    let fql = format!("CREATE ACCOUNT @{} {}", req.id, req.account_type.to_uppercase());
-    execute_fql_rest(&exec, &fql).await
+    let mut fql = format!("CREATE ACCOUNT @{} {}", req.id, req.account_type.to_uppercase());
+    if let Some(parent) = req.parent.as_deref() {
+        if !is_safe_identifier(parent) {
+            return rest_err(StatusCode::BAD_REQUEST, "Invalid parent account ID: must be alphanumeric".to_string());
+        }
+        fql.push_str(&format!(" UNDER @{}", parent));
+    }
+    execute_fql_rest(&exec, &caller, &fql, FqlParams::new()).await
 }
 
 async fn rest_list_accounts(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
 ) -> impl IntoResponse {
-    let fql = "GET trial_balance(2099-12-31) AS accounts";
+    let fql = "GET trial_balance(2099-12-31, tree) AS accounts";
     // Use a simple FQL to list - returns the accounts via trial balance
-    execute_fql_rest(&exec, fql).await
+    execute_fql_rest(&exec, &caller, fql, FqlParams::new()).await
 }
 
 async fn rest_list_entities(
@@ -440,56 +466,53 @@ async fn rest_list_entities(
 
 async fn rest_create_entity(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Json(req): Json<CreateEntityRestRequest>,
 ) -> impl IntoResponse {
-    let fql = format!("CREATE ENTITY '{}'", escape_fql(&req.name));
-    execute_fql_rest(&exec, &fql).await
+    // CREATE ENTITY only takes a literal name, so build the statement directly.
+    let statement = AstStatement::Create(CreateCommand::Entity(Arc::from(req.name.as_str())));
+    execute_statements_rest(&exec, &caller, &[statement], QueryVariables::new())
 }
 
 async fn rest_create_rate(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Json(req): Json<CreateRateRequest>,
 ) -> impl IntoResponse {
     if !is_safe_identifier(&req.id) {
         return rest_err(StatusCode::BAD_REQUEST, "Invalid rate ID: must be alphanumeric".to_string());
     }
     let fql = format!("CREATE RATE {}", req.id);
-    execute_fql_rest(&exec, &fql).await
+    execute_fql_rest(&exec, &caller, &fql, FqlParams::new()).await
 }
 
 async fn rest_set_rate(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Path(id): Path<String>,
     Json(req): Json<SetRateRequest>,
 ) -> impl IntoResponse {
     if !is_safe_identifier(&id) {
         return rest_err(StatusCode::BAD_REQUEST, "Invalid rate ID".to_string());
     }
-    if !is_safe_identifier(&req.rate) && req.rate.parse::<f64>().is_err() {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid rate value".to_string());
-    }
-    if !is_safe_date(&req.date) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid date format: expected YYYY-MM-DD".to_string());
-    }
-    let fql = format!("SET RATE {} {} {}", id, req.rate, req.date);
-    execute_fql_rest(&exec, &fql).await
+    let params = FqlParams::from([
+        ("rate".to_string(), FqlParam::Money(req.rate)),
+        ("date".to_string(), FqlParam::Date(req.date)),
+    ]);
+    let fql = format!("SET RATE {} $rate $date", id);
+    execute_fql_rest(&exec, &caller, &fql, params).await
 }
 
 async fn rest_create_journal(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Json(req): Json<CreateJournalRequest>,
 ) -> impl IntoResponse {
-    if !is_safe_date(&req.date) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid date format: expected YYYY-MM-DD".to_string());
-    }
-    if req.amount.parse::<rust_decimal::Decimal>().is_err() {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid amount".to_string());
-    }
     for op in &req.operations {
         if !is_safe_identifier(&op.account) {
             return rest_err(StatusCode::BAD_REQUEST, format!("Invalid account ID: {}", op.account));
         }
-        if !is_safe_identifier(&op.op_type) {
+        if !op.op_type.eq_ignore_ascii_case("debit") && !op.op_type.eq_ignore_ascii_case("credit") {
             return rest_err(StatusCode::BAD_REQUEST, format!("Invalid operation type: {}", op.op_type));
         }
     }
@@ -499,100 +522,155 @@ async fn rest_create_journal(
         }
     }
 
-    let mut fql = format!("CREATE JOURNAL {}, {}, '{}'", req.date, req.amount, escape_fql(&req.description));
-    
+    let mut params = FqlParams::from([
+        ("date".to_string(), FqlParam::Date(req.date)),
+        ("amount".to_string(), FqlParam::Money(req.amount)),
+        ("description".to_string(), FqlParam::String(req.description)),
+    ]);
+    let mut fql = "CREATE JOURNAL $date, $amount, $description".to_string();
+
     if !req.dimensions.is_empty() {
-        let dims: Vec<String> = req.dimensions.iter()
-            .map(|(k, v)| format!("{}='{}'", k, escape_fql(v)))
-            .collect();
+        let mut dims = Vec::new();
+        for (i, (k, v)) in req.dimensions.into_iter().enumerate() {
+            dims.push(format!("{}=$dim{}", k, i));
+            params.insert(format!("dim{}", i), FqlParam::String(v));
+        }
         fql.push_str(&format!(" FOR {}", dims.join(", ")));
     }
-    
-    let ops: Vec<String> = req.operations.iter().map(|op| {
+
+    let mut ops = Vec::new();
+    for (i, op) in req.operations.into_iter().enumerate() {
         let mut s = format!("{} @{}", op.op_type.to_uppercase(), op.account);
-        if let Some(ref amt) = op.amount {
-            s.push_str(&format!(" {}", amt));
+        if let Some(amt) = op.amount {
+            s.push_str(&format!(" $op{}", i));
+            params.insert(format!("op{}", i), FqlParam::Money(amt));
         }
-        s
-    }).collect();
+        ops.push(s);
+    }
     fql.push_str(&format!(" {}", ops.join(", ")));
-    
-    execute_fql_rest(&exec, &fql).await
+
+    execute_fql_rest(&exec, &caller, &fql, params).await
 }
 
-async fn rest_get_balance(
-    State(exec): State<Arc<StatementExecutor>>,
-    Path(id): Path<String>,
-    Query(params): Query<BalanceQuery>,
-) -> impl IntoResponse {
-    if !is_safe_identifier(&id) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid account ID".to_string());
-    }
-    if !is_safe_date(&params.date) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid date format: expected YYYY-MM-DD".to_string());
-    }
-    let dim = match (&params.dimension_key, &params.dimension_value) {
+/// Build the optional `, Key=$dimension` argument for balance/statement queries.
+fn rest_dimension_arg(
+    key: &Option<String>,
+    value: &Option<String>,
+    params: &mut FqlParams,
+) -> Result<String, String> {
+    match (key, value) {
         (Some(k), Some(v)) => {
             if !is_safe_identifier(k) {
-                return rest_err(StatusCode::BAD_REQUEST, "Invalid dimension key".to_string());
+                return Err("Invalid dimension key".to_string());
             }
-            format!(", {}='{}'", k, escape_fql(v))
+            params.insert("dimension".to_string(), FqlParam::String(v.clone()));
+            Ok(format!(", {}=$dimension", k))
         }
-        _ => String::new(),
+        _ => Ok(String::new()),
+    }
+}
+
+async fn rest_get_balance(
+    State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
+    Path(id): Path<String>,
+    Query(query): Query<BalanceQuery>,
+) -> impl IntoResponse {
+    let mut params = FqlParams::from([
+        ("account".to_string(), FqlParam::Account(id)),
+        ("date".to_string(), FqlParam::Date(query.date)),
+    ]);
+    let dim = match rest_dimension_arg(&query.dimension_key, &query.dimension_value, &mut params) {
+        Ok(d) => d,
+        Err(e) => return rest_err(StatusCode::BAD_REQUEST, e),
     };
-    let fql = format!("GET balance(@{}, {}{}) AS result", id, params.date, dim);
-    execute_fql_rest(&exec, &fql).await
+    let fql = format!("GET balance($account, $date{}) AS result", dim);
+    execute_fql_rest(&exec, &caller, &fql, params).await
 }
 
 async fn rest_get_statement(
     State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
     Path(id): Path<String>,
-    Query(params): Query<StatementQuery>,
+    Query(query): Query<StatementQuery>,
 ) -> impl IntoResponse {
-    if !is_safe_identifier(&id) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid account ID".to_string());
-    }
-    if !is_safe_date(&params.from) || !is_safe_date(&params.to) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid date format: expected YYYY-MM-DD".to_string());
-    }
-    let dim = match (&params.dimension_key, &params.dimension_value) {
-        (Some(k), Some(v)) => {
-            if !is_safe_identifier(k) {
-                return rest_err(StatusCode::BAD_REQUEST, "Invalid dimension key".to_string());
-            }
-            format!(", {}='{}'", k, escape_fql(v))
-        }
-        _ => String::new(),
+    let mut params = FqlParams::from([
+        ("account".to_string(), FqlParam::Account(id)),
+        ("from".to_string(), FqlParam::Date(query.from)),
+        ("to".to_string(), FqlParam::Date(query.to)),
+    ]);
+    let dim = match rest_dimension_arg(&query.dimension_key, &query.dimension_value, &mut params) {
+        Ok(d) => d,
+        Err(e) => return rest_err(StatusCode::BAD_REQUEST, e),
     };
-    let fql = format!("GET statement(@{}, {}, {}{}) AS result", id, params.from, params.to, dim);
-    execute_fql_rest(&exec, &fql).await
+    let fql = format!("GET statement($account, $from, $to{}) AS result", dim);
+    execute_fql_rest(&exec, &caller, &fql, params).await
+}
+
+async fn rest_get_journal(
+    State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let params = FqlParams::from([("id".to_string(), FqlParam::String(id))]);
+    execute_fql_rest(&exec, &caller, "GET journal($id) AS result", params).await
+}
+
+async fn rest_list_journals(
+    State(exec): State<Arc<StatementExecutor>>,
+    Extension(caller): Extension<CallerIdentity>,
+    Query(query): Query<JournalsQuery>,
+) -> impl IntoResponse {
+    let mut params = FqlParams::from([
+        ("from".to_string(), FqlParam::Date(query.from)),
+        ("to".to_string(), FqlParam::Date(query.to)),
+    ]);
+    let dim = match rest_dimension_arg(&query.dimension_key, &query.dimension_value, &mut params) {
+        Ok(d) => d,
+        Err(e) => return rest_err(StatusCode::BAD_REQUEST, e),
+    };
+    let fql = format!("GET journals($from, $to{}) AS result", dim);
+    execute_fql_rest(&exec, &caller, &fql, params).await
 }
 
 async fn rest_trial_balance(
     State(exec): State<Arc<StatementExecutor>>,
-    Query(params): Query<TrialBalanceQuery>,
+    Extension(caller): Extension<CallerIdentity>,
+    Query(query): Query<TrialBalanceQuery>,
 ) -> impl IntoResponse {
-    if !is_safe_date(&params.date) {
-        return rest_err(StatusCode::BAD_REQUEST, "Invalid date format: expected YYYY-MM-DD".to_string());
-    }
-    let fql = format!("GET trial_balance({}) AS result", params.date);
-    execute_fql_rest(&exec, &fql).await
+    let params = FqlParams::from([("date".to_string(), FqlParam::Date(query.date))]);
+    execute_fql_rest(&exec, &caller, "GET trial_balance($date) AS result", params).await
 }
 
 async fn execute_fql_rest(
     exec: &StatementExecutor,
+    caller: &CallerIdentity,
     fql: &str,
+    params: FqlParams,
 ) -> (StatusCode, Json<RestResponse<String>>) {
+    let variables = match bind_params(&params) {
+        Ok(v) => v,
+        Err(e) => return rest_err(StatusCode::BAD_REQUEST, e),
+    };
     let statements = match lexer::parse(fql) {
         Ok(s) => s,
         Err(e) => return rest_err(StatusCode::BAD_REQUEST, format!("Internal FQL error: {}", e)),
     };
+    execute_statements_rest(exec, caller, &statements, variables)
+}
 
+fn execute_statements_rest(
+    exec: &StatementExecutor,
+    caller: &CallerIdentity,
+    statements: &[AstStatement],
+    variables: QueryVariables,
+) -> (StatusCode, Json<RestResponse<String>>) {
     let eff_date = time::OffsetDateTime::now_utc().date();
-    let mut context = ExecutionContext::new(eff_date, QueryVariables::new());
+    let mut context = ExecutionContext::new(eff_date, variables);
+    context.role = caller.role;
     let mut output = String::new();
 
-    for statement in &statements {
+    for statement in statements {
         match exec.execute(&mut context, statement) {
             Ok(result) => {
                 let s = format_execution_result(&result);
@@ -600,7 +678,7 @@ async fn execute_fql_rest(
                     output.push_str(&s);
                 }
             },
-            Err(e) => return rest_err(StatusCode::INTERNAL_SERVER_ERROR, format!("{}", e)),
+            Err(e) => return rest_err(execution_error_status(&e), format!("{}", e)),
         }
     }
 
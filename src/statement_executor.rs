@@ -1,10 +1,10 @@
-use std::{sync::Arc, collections::{BTreeMap, HashMap}, fmt::Display};
+use std::{sync::Arc, collections::{BTreeMap, HashMap, HashSet}, fmt::Display, ops::Bound};
 
-use rust_decimal::Decimal;
+use rust_decimal::{prelude::ToPrimitive, Decimal, MathematicalOps};
 use rust_decimal_macros::dec;
 use time::Date;
 
-use crate::{evaluator::{ExpressionEvaluator, QueryVariables, EvaluationError, ExpressionEvaluationContext}, ast::{Statement, JournalExpression, CreateCommand, self, AccountExpression, GetExpression, CreateRateExpression, SetCommand, SetRateExpression, AccrueCommand, Compounding, LedgerOperation, DistributeCommand, Period, SellCommand, SplitCommand}, storage::{StorageBackend, TransactionId, DEFAULT_ENTITY}, models::{write::{CreateJournalCommand, LedgerEntryCommand, CreateRateCommand, SetRateCommand}, DataValue}};
+use crate::{auth::{self, Role}, functions, evaluator::{ExpressionEvaluator, QueryVariables, EvaluationError, ExpressionEvaluationContext}, ast::{Statement, JournalExpression, CreateCommand, self, AccountExpression, GetExpression, CreateRateExpression, SetCommand, SetRateExpression, AccrueCommand, Compounding, DayCount, LedgerOperation, DistributeCommand, DistributeSchedule, AllocateCommand, AllocationDriver, AmortizeCommand, CreateAssetExpression, DepreciationMethodExpression, DepreciateCommand, DisposeCommand, Period, SellCommand, SplitCommand, LetCommand, ReverseCommand, CloseYearCommand, AccountType, CashFlowCategory}, storage::{ensure_period_open, DimensionMovement, StorageBackend, StorageError, TransactionId, DEFAULT_ENTITY}, models::{write::{CreateJournalCommand, LedgerEntryCommand, CreateRateCommand, SetRateCommand}, AmortizationItem, DataValue, DepreciationItem, DepreciationMethod, DimensionFilter, FixedAsset}};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExecutionContext {
@@ -12,6 +12,9 @@ pub struct ExecutionContext {
     pub variables: QueryVariables,
     pub transaction_id: Option<TransactionId>,
     pub entity_id: Arc<str>,
+    /// Role of the caller; statements above this role are rejected. A new context is a
+    /// reader, so entry points grant anything more explicitly.
+    pub role: Role,
 }
 
 impl ExecutionContext {
@@ -21,6 +24,7 @@ impl ExecutionContext {
             variables,
             transaction_id: None,
             entity_id: Arc::from(DEFAULT_ENTITY),
+            role: Role::Reader,
         }
     }
 }
@@ -76,11 +80,20 @@ impl StatementExecutor {
     }
 
     pub fn execute(&self, context: &mut ExecutionContext, statement: &Statement) -> Result<ExecutionResult, EvaluationError> {
+        let required = auth::required_role(statement);
+        if !context.role.permits(required) {
+            return Err(EvaluationError::PermissionDenied(format!(
+                "role '{}' cannot run {} (requires '{}')",
+                context.role, auth::statement_name(statement), required
+            )));
+        }
+
         Ok(match statement {
             Statement::Create(c) => match c {
                 CreateCommand::Account(a) => self.create_account(context, a)?,
                 CreateCommand::Journal(j) => self.create_journal(context, j)?,
                 CreateCommand::Rate(r) => self.create_rate(context, r)?,
+                CreateCommand::Asset(a) => self.create_asset(context, a)?,
                 CreateCommand::Entity(name) => {
                     self.storage.create_entity(name)?;
                     tracing::debug!("Created entity: {}", name);
@@ -90,8 +103,21 @@ impl StatementExecutor {
             Statement::Get(get) => self.get(context, get)?,
             Statement::Accrue(accrue) => self.accrue(context, accrue)?,
             Statement::Distribute(distribute) => self.distribute(context, distribute)?,
+            Statement::Allocate(allocate) => self.allocate(context, allocate)?,
+            Statement::Amortize(amortize) => self.amortize(context, amortize)?,
+            Statement::Depreciate(depreciate) => self.depreciate(context, depreciate)?,
+            Statement::Dispose(dispose) => self.dispose(context, dispose)?,
             Statement::Sell(sell) => self.sell(context, sell)?,
             Statement::Split(split) => self.split(context, split)?,
+            Statement::Reverse(reverse) => self.reverse(context, reverse)?,
+            Statement::Let(l) => self.let_value(context, l)?,
+            Statement::LockPeriod(date) => self.lock_period(context, date)?,
+            Statement::CloseYear(close) => self.close_year(context, close)?,
+            Statement::Unlock => {
+                self.storage.unlock_period(&context.entity_id)?;
+                tracing::debug!("Unlocked periods of entity: {}", context.entity_id);
+                ExecutionResult::new()
+            },
             Statement::Set(s) => match s {
                 SetCommand::Rate(r) => self.set_rate(context, r)?,
             },
@@ -190,11 +216,24 @@ impl StatementExecutor {
             ledger_entries: {
                 let entries = self.build_ledger_entries(&eval_ctx, &journal.operations, journal_amount)?;
 
+                // Statistical legs are single-sided quantities and take no part in balancing
+                let statistical: HashSet<Arc<str>> = journal.operations.iter()
+                    .map(|op| match op {
+                        ast::LedgerOperation::Debit(d) => &d.account,
+                        ast::LedgerOperation::Credit(c) => &c.account,
+                    })
+                    .collect::<HashSet<_>>()
+                    .into_iter()
+                    .filter(|account_id| self.storage.get_account_type(&context.entity_id, account_id) == Some(AccountType::Statistical))
+                    .cloned()
+                    .collect();
+                let monetary = |account_id: &Arc<str>| !statistical.contains(account_id);
+
                 // Validate that total debits == total credits when all operations have explicit amounts
                 let all_explicit = journal.operations.iter().all(|op| {
                     match op {
-                        ast::LedgerOperation::Debit(d) => d.amount.is_some() || d.unit_spec.is_some(),
-                        ast::LedgerOperation::Credit(c) => c.amount.is_some() || c.unit_spec.is_some(),
+                        ast::LedgerOperation::Debit(d) => d.amount.is_some() || d.unit_spec.is_some() || !monetary(&d.account),
+                        ast::LedgerOperation::Credit(c) => c.amount.is_some() || c.unit_spec.is_some() || !monetary(&c.account),
                     }
                 });
 
@@ -203,8 +242,9 @@ impl StatementExecutor {
                     let mut total_credits = Decimal::ZERO;
                     for entry in &entries {
                         match entry {
-                            LedgerEntryCommand::Debit { amount, .. } => total_debits += amount,
-                            LedgerEntryCommand::Credit { amount, .. } => total_credits += amount,
+                            LedgerEntryCommand::Debit { account_id, amount, .. } if monetary(account_id) => total_debits += amount,
+                            LedgerEntryCommand::Credit { account_id, amount, .. } if monetary(account_id) => total_credits += amount,
+                            _ => {}
                         }
                     }
                     if total_debits != total_credits {
@@ -216,16 +256,125 @@ impl StatementExecutor {
 
                 entries
             },
+            leg_dimensions: BTreeMap::new(),
+            reverses: None,
         };
 
-        self.storage.create_journal(&context.entity_id, &command)?;
+        let auto_reverse = match &journal.auto_reverse {
+            Some(expr) => match self.expression_evaluator.evaluate_expression(&eval_ctx, expr)? {
+                DataValue::Date(d) => Some(self.reversal_command(context, &command, d, None)?),
+                _ => return Err(EvaluationError::InvalidType),
+            },
+            None => None,
+        };
+
+        let journal_id = self.post_journal(context, &command)?;
         tracing::debug!("Created journal: {:?}", command);
 
         let mut result = ExecutionResult::new();        
         result.journals_created += 1;
+
+        if let Some(mut reversal) = auto_reverse {
+            reversal.reverses = Some(journal_id);
+            self.post_journal(context, &reversal)?;
+            result.journals_created += 1;
+        }
         Ok(result)
     }
 
+    /// Post the mirror image of an existing journal, linked back to it.
+    fn reverse(&self, context: &ExecutionContext, reverse: &ReverseCommand) -> Result<ExecutionResult, EvaluationError> {
+        let mut eval_ctx: ExpressionEvaluationContext = context.into();
+
+        let journal_id = match self.expression_evaluator.evaluate_expression(&eval_ctx, &reverse.journal_id)? {
+            DataValue::String(s) => functions::parse_journal_id(&s)
+                .ok_or_else(|| EvaluationError::General(format!("invalid journal id '{}'", s)))?,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        let date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &reverse.date)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+        eval_ctx.set_effective_date(date);
+
+        let description = match &reverse.description {
+            Some(expr) => match self.expression_evaluator.evaluate_expression(&eval_ctx, expr)? {
+                DataValue::String(s) => Some(s),
+                _ => return Err(EvaluationError::InvalidType),
+            },
+            None => None,
+        };
+
+        let original = self.storage.get_journal(&context.entity_id, journal_id)?;
+        if original.reversed_by.is_some() {
+            return Err(EvaluationError::General(format!("journal {} has already been reversed", functions::format_journal_id(journal_id))));
+        }
+
+        let posted = CreateJournalCommand {
+            date: original.date,
+            description: original.description.clone(),
+            amount: original.amount,
+            dimensions: original.dimensions.clone(),
+            ledger_entries: original.lines.iter().map(|line| {
+                if line.credit.is_zero() {
+                    LedgerEntryCommand::Debit { account_id: line.account_id.clone(), amount: line.debit, units: None, currency_amount: line.currency_amount }
+                } else {
+                    LedgerEntryCommand::Credit { account_id: line.account_id.clone(), amount: line.credit, units: None, currency_amount: line.currency_amount }
+                }
+            }).collect(),
+            leg_dimensions: original.lines.iter().enumerate()
+                .filter_map(|(index, line)| line.dimensions.clone().map(|dimensions| (index, dimensions)))
+                .collect(),
+            reverses: None,
+        };
+
+        let mut reversal = self.reversal_command(context, &posted, date, description)?;
+        reversal.reverses = Some(journal_id);
+        self.post_journal(context, &reversal)?;
+        tracing::debug!("Reversed journal {}: {:?}", functions::format_journal_id(journal_id), reversal);
+
+        let mut result = ExecutionResult::new();
+        result.journals_created += 1;
+        Ok(result)
+    }
+
+    /// Build the mirror of `original` dated `date`: every debit becomes a credit and vice versa.
+    fn reversal_command(&self, context: &ExecutionContext, original: &CreateJournalCommand, date: Date, description: Option<Arc<str>>) -> Result<CreateJournalCommand, EvaluationError> {
+        if date < original.date {
+            return Err(EvaluationError::General(
+                format!("reversal date {} is before the journal date {}", date, original.date)
+            ));
+        }
+
+        let mut ledger_entries = Vec::with_capacity(original.ledger_entries.len());
+        for entry in &original.ledger_entries {
+            let account_id = match entry {
+                LedgerEntryCommand::Debit { account_id, .. } | LedgerEntryCommand::Credit { account_id, .. } => account_id,
+            };
+            // Lots cannot be restored by a mirror posting, so unit accounts are excluded
+            if self.storage.is_unit_account(&context.entity_id, account_id) {
+                return Err(EvaluationError::General(
+                    format!("cannot reverse a journal that posts to unit account @{}", account_id)
+                ));
+            }
+            ledger_entries.push(match entry {
+                LedgerEntryCommand::Debit { account_id, amount, currency_amount, .. } => LedgerEntryCommand::Credit { account_id: account_id.clone(), amount: *amount, units: None, currency_amount: *currency_amount },
+                LedgerEntryCommand::Credit { account_id, amount, currency_amount, .. } => LedgerEntryCommand::Debit { account_id: account_id.clone(), amount: *amount, units: None, currency_amount: *currency_amount },
+            });
+        }
+
+        Ok(CreateJournalCommand {
+            date,
+            description: description.unwrap_or_else(|| Arc::from(format!("Reversal of {}", original.description))),
+            amount: original.amount,
+            dimensions: original.dimensions.clone(),
+            ledger_entries,
+            leg_dimensions: original.leg_dimensions.clone(),
+            reverses: None,
+        })
+    }
+
     fn build_ledger_entries(&self, eval_ctx: &ExpressionEvaluationContext, operations: &Vec<LedgerOperation>, journal_amount: Decimal) -> Result<Vec<LedgerEntryCommand>, EvaluationError> {
         let mut entries = Vec::new();
         for op in operations {
@@ -246,20 +395,15 @@ impl StatementExecutor {
                             account_id: op.account.clone(),
                             amount: units * price,
                             units: Some(units),
+                            currency_amount: None,
                         }
                     } else {
+                        let (amount, currency_amount) = self.leg_amounts(eval_ctx, op, journal_amount)?;
                         LedgerEntryCommand::Debit {
                             account_id: op.account.clone(),
-                            amount: match &op.amount {
-                                Some(amount) => match self.expression_evaluator.evaluate_expression(eval_ctx, amount)? {
-                                    DataValue::Money(d) => d,
-                                    DataValue::Int(i) => Decimal::from(i),
-                                    DataValue::Percentage(p) => journal_amount * p,
-                                    _ => return Err(EvaluationError::InvalidType),
-                                },
-                                None => journal_amount,
-                            },
+                            amount,
                             units: None,
+                            currency_amount,
                         }
                     }
                 },
@@ -279,20 +423,15 @@ impl StatementExecutor {
                             account_id: op.account.clone(),
                             amount: units * price,
                             units: Some(units),
+                            currency_amount: None,
                         }
                     } else {
+                        let (amount, currency_amount) = self.leg_amounts(eval_ctx, op, journal_amount)?;
                         LedgerEntryCommand::Credit {
                             account_id: op.account.clone(),
-                            amount: match &op.amount {
-                                Some(amount) => match self.expression_evaluator.evaluate_expression(eval_ctx, amount)? {
-                                    DataValue::Money(d) => d,
-                                    DataValue::Int(i) => Decimal::from(i),
-                                    DataValue::Percentage(p) => journal_amount * p,
-                                    _ => return Err(EvaluationError::InvalidType),
-                                },
-                                None => journal_amount,
-                            },
+                            amount,
                             units: None,
+                            currency_amount,
                         }
                     }
                 }
@@ -303,9 +442,134 @@ impl StatementExecutor {
         Ok(entries)
     }
 
+    /// Functional and transaction-currency amounts of a leg. An explicit amount on a currency
+    /// account is in that currency and is converted at the rate in effect on the effective date,
+    /// unless the leg gives its functional amount with `FUNCTIONAL`; percentages and the journal
+    /// amount are functional already.
+    fn leg_amounts(&self, eval_ctx: &ExpressionEvaluationContext, op: &ast::LedgerOperationData, journal_amount: Decimal) -> Result<(Decimal, Option<Decimal>), EvaluationError> {
+        let amount = match &op.amount {
+            Some(amount) => match self.expression_evaluator.evaluate_expression(eval_ctx, amount)? {
+                DataValue::Money(d) => d,
+                DataValue::Int(i) => Decimal::from(i),
+                DataValue::Percentage(p) if op.functional_amount.is_none() => return Ok((journal_amount * p, None)),
+                _ => return Err(EvaluationError::InvalidType),
+            },
+            None => return Ok((journal_amount, None)),
+        };
+        let rate = self.currency_rate(eval_ctx.get_entity_id(), &op.account, eval_ctx.get_effective_date())?;
+        match (&op.functional_amount, rate) {
+            (Some(functional), Some(_)) => {
+                let functional = match self.expression_evaluator.evaluate_expression(eval_ctx, functional)? {
+                    DataValue::Money(d) => d,
+                    DataValue::Int(i) => Decimal::from(i),
+                    _ => return Err(EvaluationError::InvalidType),
+                };
+                if functional.is_sign_negative() != amount.is_sign_negative() {
+                    return Err(EvaluationError::General(format!("@{}: functional amount {} and amount {} differ in sign", op.account, functional, amount)));
+                }
+                Ok((functional, Some(amount)))
+            }
+            (Some(_), None) => Err(EvaluationError::General(format!("@{}: FUNCTIONAL only applies to legs on currency accounts", op.account))),
+            (None, Some(rate)) => Ok(((amount * rate).round_dp(2), Some(amount))),
+            (None, None) => Ok((amount, None)),
+        }
+    }
+
+    /// Value of one unit of the account's currency in the functional currency on `date`, or
+    /// `None` when the account is held in the functional currency.
+    fn currency_rate(&self, entity_id: &str, account_id: &str, date: Date) -> Result<Option<Decimal>, EvaluationError> {
+        match self.storage.get_account_currency(entity_id, account_id) {
+            Some(currency) => {
+                let rate = self.storage.get_rate(entity_id, &currency, date)?;
+                if rate <= Decimal::ZERO {
+                    return Err(EvaluationError::General(format!("@{}: {} rate on {} must be positive", account_id, currency, date)));
+                }
+                Ok(Some(rate))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Post a journal, first giving legs on currency accounts that only carry a functional
+    /// amount their transaction-currency amount at the rate on the journal date.
+    fn post_journal(&self, context: &ExecutionContext, journal: &CreateJournalCommand) -> Result<u128, EvaluationError> {
+        let mut journal = journal.clone();
+        let date = journal.date;
+        for entry in journal.ledger_entries.iter_mut() {
+            let (LedgerEntryCommand::Debit { account_id, amount, currency_amount, .. } | LedgerEntryCommand::Credit { account_id, amount, currency_amount, .. }) = entry;
+            if currency_amount.is_none() {
+                if let Some(rate) = self.currency_rate(&context.entity_id, account_id, date)? {
+                    *currency_amount = Some((*amount / rate).round_dp(2));
+                }
+            }
+        }
+        Ok(self.storage.create_journal(&context.entity_id, &journal)?)
+    }
+
     fn create_account(&self, context: &ExecutionContext, account: &AccountExpression) -> Result<ExecutionResult, EvaluationError> {
         //let mut eval_ctx : ExpressionEvaluationContext = context.into();
 
+        if account.class.is_some() && !matches!(account.account_type, AccountType::Asset | AccountType::Liability) {
+            return Err(EvaluationError::General(format!(
+                "@{}: only asset and liability accounts can be classified as current or non-current",
+                account.id
+            )));
+        }
+        if account.account_type == AccountType::Statistical && (account.cash_flow.is_some() || account.unit_rate_id.is_some()) {
+            return Err(EvaluationError::General(format!(
+                "@{}: statistical accounts hold plain quantities and take no cash flow category or units",
+                account.id
+            )));
+        }
+        match (&account.account_type, account.cash_flow) {
+            (AccountType::Income | AccountType::Expense, Some(_)) => {
+                return Err(EvaluationError::General(format!(
+                    "@{}: income and expense accounts are reported through net income and take no cash flow category",
+                    account.id
+                )));
+            }
+            (AccountType::Liability | AccountType::Equity, Some(CashFlowCategory::Cash)) => {
+                return Err(EvaluationError::General(format!("@{}: only asset accounts can hold cash", account.id)));
+            }
+            _ => {}
+        }
+
+        if account.currency.is_some() && !matches!(account.account_type, AccountType::Asset | AccountType::Liability) {
+            return Err(EvaluationError::General(format!(
+                "@{}: only asset and liability accounts can be held in a foreign currency",
+                account.id
+            )));
+        }
+        if account.currency.is_some() && account.unit_rate_id.is_some() {
+            return Err(EvaluationError::General(format!(
+                "@{}: unit accounts are carried at cost and take no currency",
+                account.id
+            )));
+        }
+        if let Some(currency) = &account.currency {
+            if !self.storage.list_rates(&context.entity_id).contains(currency) {
+                return Err(StorageError::RateNotFound(currency.to_string()).into());
+            }
+        }
+
+        // Children of a currency account share its currency so its transaction balance stays in one currency
+        let parent_currency = account.parent.as_ref()
+            .and_then(|parent| self.storage.get_account_currency(&context.entity_id, parent));
+        let inherited;
+        let account = match (&parent_currency, &account.currency) {
+            (Some(parent), Some(currency)) if parent != currency => {
+                return Err(EvaluationError::General(format!(
+                    "@{}: currency {} differs from {} of its parent",
+                    account.id, currency, parent
+                )));
+            }
+            (Some(_), None) => {
+                inherited = AccountExpression { currency: parent_currency.clone(), ..account.clone() };
+                &inherited
+            }
+            _ => account,
+        };
+
         self.storage.create_account(&context.entity_id, account)?;
 
         tracing::debug!("Created account: {:?}", account);
@@ -323,6 +587,72 @@ impl StatementExecutor {
         Ok(ExecutionResult::new())
     }
 
+    fn create_asset(&self, context: &ExecutionContext, asset: &CreateAssetExpression) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let amount = |expression: &ast::Expression| -> Result<Decimal, EvaluationError> {
+            match self.expression_evaluator.evaluate_expression(&eval_ctx, expression)? {
+                DataValue::Money(d) => Ok(d),
+                DataValue::Int(i) => Ok(Decimal::from(i)),
+                _ => Err(EvaluationError::InvalidType),
+            }
+        };
+
+        let in_service = match self.expression_evaluator.evaluate_expression(&eval_ctx, &asset.in_service)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+        let cost = amount(&asset.cost)?;
+        let salvage = match &asset.salvage {
+            Some(salvage) => amount(salvage)?,
+            None => Decimal::ZERO,
+        };
+        if cost <= Decimal::ZERO || salvage < Decimal::ZERO || salvage > cost {
+            return Err(EvaluationError::General("CREATE ASSET: cost must be positive and salvage between zero and the cost".into()));
+        }
+        let life_years = match self.expression_evaluator.evaluate_expression(&eval_ctx, &asset.life_years)? {
+            DataValue::Int(n) if (1..=MAX_ASSET_LIFE_YEARS as i64).contains(&n) => n as u32,
+            _ => return Err(EvaluationError::General(format!("CREATE ASSET: life must be between 1 and {} years", MAX_ASSET_LIFE_YEARS))),
+        };
+        let method = match &asset.method {
+            DepreciationMethodExpression::StraightLine => DepreciationMethod::StraightLine,
+            DepreciationMethodExpression::SumOfYears => DepreciationMethod::SumOfYears,
+            DepreciationMethodExpression::DecliningBalance(factor) => {
+                let factor = match factor {
+                    Some(factor) => amount(factor)?,
+                    None => Decimal::TWO,
+                };
+                if factor <= Decimal::ZERO {
+                    return Err(EvaluationError::General("CREATE ASSET: declining balance factor must be positive".into()));
+                }
+                DepreciationMethod::DecliningBalance(factor)
+            }
+        };
+
+        let accounts = self.storage.list_accounts(&context.entity_id);
+        for account in [&asset.asset_account, &asset.accumulated_account, &asset.expense_account] {
+            if !accounts.iter().any(|(id, _)| id == account) {
+                return Err(StorageError::AccountNotFound(account.to_string()).into());
+            }
+        }
+
+        self.storage.create_fixed_asset(&context.entity_id, &FixedAsset {
+            id: asset.id.clone(),
+            cost,
+            in_service,
+            life_years,
+            salvage,
+            method,
+            asset_account: asset.asset_account.clone(),
+            accumulated_account: asset.accumulated_account.clone(),
+            expense_account: asset.expense_account.clone(),
+            depreciated_through: None,
+            disposed_on: None,
+        })?;
+        tracing::debug!("Created fixed asset: {}", asset.id);
+
+        Ok(ExecutionResult::new())
+    }
+
     fn set_rate(&self, context: &ExecutionContext, rate: &SetRateExpression) -> Result<ExecutionResult, EvaluationError> {
         let mut eval_ctx : ExpressionEvaluationContext = context.into();
 
@@ -342,6 +672,10 @@ impl StatementExecutor {
                 DataValue::Percentage(p) => p,
                 _ => return Err(EvaluationError::InvalidType),
             },
+            dimension: match &rate.dimension {
+                Some((key, value)) => Some((key.clone(), Arc::new(self.expression_evaluator.evaluate_expression(&eval_ctx, value)?))),
+                None => None,
+            },
         };
         self.storage.set_rate(&context.entity_id, &cmd)?;
         tracing::debug!("Set rate: {:?}", rate);
@@ -361,6 +695,156 @@ impl StatementExecutor {
         Ok(result)
     }
 
+    /// Evaluate a LET and store the result so later statements can read it.
+    fn let_value(&self, context: &mut ExecutionContext, command: &LetCommand) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx : ExpressionEvaluationContext = (&*context).into();
+        let value = self.expression_evaluator.evaluate_expression(&eval_ctx, &command.value)?;
+        tracing::debug!("Let {} = {:?}", command.name, value);
+        context.variables.insert(command.name.clone(), value);
+        Ok(ExecutionResult::new())
+    }
+
+    fn lock_period(&self, context: &ExecutionContext, date: &ast::Expression) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let through = match self.expression_evaluator.evaluate_expression(&eval_ctx, date)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+        self.storage.lock_period(&context.entity_id, through)?;
+        tracing::debug!("Locked entity {} through {}", context.entity_id, through);
+        Ok(ExecutionResult::new())
+    }
+
+    /// Fail before anything is posted when `date` falls in a locked period, so commands
+    /// that generate several journals never stop halfway.
+    fn ensure_open(&self, context: &ExecutionContext, date: Date) -> Result<(), EvaluationError> {
+        let locked_through = self.storage.get_period_lock(&context.entity_id)?;
+        Ok(ensure_period_open(locked_through, date)?)
+    }
+
+    /// Zero every income and expense account into the equity account as of the year end,
+    /// then record the close and lock the period. Closing a year twice is a no-op.
+    fn close_year(&self, context: &ExecutionContext, close: &CloseYearCommand) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let mut result = ExecutionResult::new();
+
+        let year_end = match self.expression_evaluator.evaluate_expression(&eval_ctx, &close.year_end)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        let closes = self.storage.list_year_closes(&context.entity_id)?;
+        if closes.iter().any(|(date, _)| *date == year_end) {
+            return Ok(result);
+        }
+        self.ensure_open(context, year_end)?;
+
+        let accounts = self.storage.list_accounts(&context.entity_id);
+        match accounts.iter().find(|(id, _)| *id == close.retained_earnings) {
+            Some((_, AccountType::Equity)) => {},
+            Some(_) => return Err(EvaluationError::General(format!("@{} is not an equity account", close.retained_earnings))),
+            None => return Err(StorageError::AccountNotFound(close.retained_earnings.to_string()).into()),
+        }
+
+        // Parent balances roll up their children, so only leaf accounts are closed
+        let parents: HashSet<Arc<str>> = accounts.iter()
+            .filter_map(|(id, _)| self.storage.get_parent_account(&context.entity_id, id))
+            .collect();
+        let since = closes.iter().map(|(date, _)| *date).filter(|date| *date < year_end).max()
+            .map_or(Bound::Unbounded, Bound::Excluded);
+
+        // Balances to close, grouped by dimension value (None for the untagged remainder)
+        type Balances = Vec<(Arc<str>, AccountType, Decimal)>;
+        let mut groups: Vec<(Option<Arc<DataValue>>, Balances)> = Vec::new();
+        let mut add = |value: Option<Arc<DataValue>>, account_id: &Arc<str>, account_type: &AccountType, amount: Decimal| {
+            if amount.is_zero() {
+                return;
+            }
+            let index = match groups.iter().position(|(v, _)| *v == value) {
+                Some(index) => index,
+                None => {
+                    groups.push((value, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            groups[index].1.push((account_id.clone(), account_type.clone(), amount));
+        };
+
+        for (account_id, account_type) in &accounts {
+            if !matches!(account_type, AccountType::Income | AccountType::Expense) || parents.contains(account_id) {
+                continue;
+            }
+            let total = self.storage.get_balance(&context.entity_id, account_id, year_end, &DimensionFilter::new())?;
+            let mut tagged = Decimal::ZERO;
+            if let Some(key) = &close.by_dimension {
+                for (value, amount) in self.storage.get_balances_by_dimension(&context.entity_id, account_id, key, since, year_end)? {
+                    tagged += amount;
+                    add(Some(value), account_id, account_type, amount);
+                }
+            }
+            add(None, account_id, account_type, total - tagged);
+        }
+
+        let description: Arc<str> = Arc::from(format!("Year-end close {}", year_end));
+        let mut journal_ids = Vec::new();
+        for (value, balances) in groups {
+            let mut ledger_entries = Vec::new();
+            let mut net_income = Decimal::ZERO;
+            for (account_id, account_type, amount) in balances {
+                // Income carries a credit balance and expenses a debit balance
+                let debit = match account_type {
+                    AccountType::Income => {
+                        net_income += amount;
+                        amount
+                    }
+                    _ => {
+                        net_income -= amount;
+                        -amount
+                    }
+                };
+                ledger_entries.push(if debit > Decimal::ZERO {
+                    LedgerEntryCommand::Debit { account_id, amount: debit, units: None, currency_amount: None }
+                } else {
+                    LedgerEntryCommand::Credit { account_id, amount: -debit, units: None, currency_amount: None }
+                });
+            }
+            let account_id = close.retained_earnings.clone();
+            if net_income > Decimal::ZERO {
+                ledger_entries.push(LedgerEntryCommand::Credit { account_id, amount: net_income, units: None, currency_amount: None });
+            } else if net_income < Decimal::ZERO {
+                ledger_entries.push(LedgerEntryCommand::Debit { account_id, amount: -net_income, units: None, currency_amount: None });
+            }
+
+            let amount = ledger_entries.iter()
+                .map(|entry| match entry {
+                    LedgerEntryCommand::Debit { amount, .. } => *amount,
+                    LedgerEntryCommand::Credit { .. } => Decimal::ZERO,
+                })
+                .sum();
+            let mut dimensions = BTreeMap::new();
+            if let (Some(key), Some(value)) = (&close.by_dimension, value) {
+                dimensions.insert(key.clone(), value);
+            }
+            let journal = CreateJournalCommand {
+                date: year_end,
+                description: description.clone(),
+                amount,
+                dimensions,
+                ledger_entries,
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
+            };
+            journal_ids.push(self.post_journal(context, &journal)?);
+            result.journals_created += 1;
+        }
+
+        self.storage.record_year_close(&context.entity_id, year_end, &journal_ids)?;
+        self.storage.lock_period(&context.entity_id, year_end)?;
+        tracing::debug!("Closed year ending {} into @{}", year_end, close.retained_earnings);
+
+        Ok(result)
+    }
+
     fn accrue(&self, context: &ExecutionContext, accrue: &AccrueCommand) -> Result<ExecutionResult, EvaluationError> {
         let mut eval_ctx : ExpressionEvaluationContext = context.into();
         let mut result = ExecutionResult::new();
@@ -387,73 +871,142 @@ impl StatementExecutor {
         
 
         eval_ctx.set_effective_date(effective_date);
+        self.ensure_open(context, effective_date)?;
 
-        let dimension_values = self.storage.get_dimension_values(&context.entity_id, &accrue.account_id, accrue.by_dimension.clone(), start_date, end_date)?;
-        let mut amounts = HashMap::new();
-        
-        let mut dt = start_date;
-        while dt <= end_date {
-            
-            let rate = self.storage.get_rate(&context.entity_id, &accrue.rate_id, dt)?;
-            
-            for dimension_value in &dimension_values {
-                let dim = (accrue.by_dimension.clone() ,dimension_value.clone());
-                let open = self.storage.get_balance(&context.entity_id, &accrue.account_id, dt, Some(&dim))?;
-                
-                let accural = match amounts.get(dimension_value) {
-                    Some(pv) => *pv,
-                    None => Decimal::ZERO,
-                };
-                let delta = calc_daily_accural_amount(rate, open + accural, &accrue.compounding);
-                
-                amounts.insert(dimension_value.clone(), accural + delta);
-            }
-            
-            dt = match dt.next_day() {
-                Some(d) => d,
-                None => break,
-            };
+        // Without COMPOUND the rate is already a daily rate, so there is no year to divide
+        if accrue.compounding.is_none() && accrue.day_count.is_some() && !accrue.average_balance {
+            return Err(EvaluationError::General("ACCRUE: DAYCOUNT needs a COMPOUND method or ON AVERAGE BALANCE".into()));
+        }
+
+        if end_date < start_date {
+            return Ok(result);
         }
 
+        // Everything the accrual needs comes from a few queries, walked once in date order
+        let combinations: Vec<_> = self.storage.get_dimension_combinations(&context.entity_id, &accrue.account_id, &accrue.by_dimensions, start_date, end_date)?
+            .into_iter()
+            .collect();
+        let movements = self.storage.get_dimension_movements(&context.entity_id, &accrue.account_id, &accrue.by_dimensions, start_date, end_date)?;
+        let schedules = self.rate_schedules(&eval_ctx, accrue, &combinations, start_date, end_date)?;
+        let amounts = accrue_amounts(accrue, combinations, &schedules, &movements, start_date, end_date)?;
+
         for (dimension_value, amount) in amounts {
 
             let amount = amount.round_dp(2);
-            let dimensions = {
-                let mut dimensions = BTreeMap::new();
-                dimensions.insert(accrue.by_dimension.clone(), dimension_value);
-                dimensions
-            };
-            
+            // An undimensioned accrual runs even when nothing was posted in the period
+            if accrue.by_dimensions.is_empty() && amount.is_zero() {
+                continue;
+            }
+            let dimensions: BTreeMap<_, _> = accrue.by_dimensions.iter().cloned().zip(dimension_value).collect();
+
             let journal = CreateJournalCommand { 
                 date: effective_date, 
                 description: description.clone(), 
                 amount, 
                 ledger_entries: self.build_ledger_entries(&eval_ctx, &accrue.into_journal.operations, amount)?, 
-                dimensions 
+                dimensions,
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
             };
-            self.storage.create_journal(&context.entity_id, &journal)?;
+            self.post_journal(context, &journal)?;
             result.journals_created += 1;
         }
 
         Ok(result)
     }
-    fn distribute(&self, context: &ExecutionContext, cmd: &DistributeCommand) -> Result<ExecutionResult, EvaluationError> {
-        let eval_ctx: ExpressionEvaluationContext = context.into();
-        let mut result = ExecutionResult::new();
 
-        let start_date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.start_date)? {
-            DataValue::Date(d) => d,
-            _ => return Err(EvaluationError::InvalidType),
-        };
+    /// The value of the ACCRUE rate expression over the period for each dimension value
+    /// combination. Identifiers in the expression that are not query variables name rates; a
+    /// rate set `FOR` one of the combination's values overrides the undimensioned rate, the
+    /// earlier `BY` dimension winning when several apply.
+    fn rate_schedules(&self, eval_ctx: &ExpressionEvaluationContext, accrue: &AccrueCommand, combinations: &[Vec<Arc<DataValue>>], start: Date, end: Date) -> Result<Vec<Arc<RateSchedule>>, EvaluationError> {
+        // Evaluate with placeholders until every rate the expression refers to is known
+        let mut rate_ids: Vec<Arc<str>> = Vec::new();
+        loop {
+            let mut ctx = eval_ctx.clone();
+            let mut variables = ctx.clone_variables();
+            variables.extend(rate_ids.iter().map(|id| (id.clone(), DataValue::Percentage(Decimal::ZERO))));
+            ctx.replace_variables(variables);
+            match self.expression_evaluator.evaluate_expression(&ctx, &accrue.rate) {
+                Ok(_) => break,
+                Err(EvaluationError::UnknownIdentifier(id)) if !rate_ids.iter().any(|r| r.as_ref() == id) => rate_ids.push(Arc::from(id)),
+                Err(e) => return Err(e),
+            }
+        }
 
-        let end_date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.end_date)? {
-            DataValue::Date(d) => d,
-            _ => return Err(EvaluationError::InvalidType),
-        };
+        let mut base: Vec<Vec<(Date, Decimal)>> = vec![Vec::new(); rate_ids.len()];
+        let mut keyed: Vec<HashMap<DimensionValue, Vec<(Date, Decimal)>>> = vec![HashMap::new(); rate_ids.len()];
+        for (i, id) in rate_ids.iter().enumerate() {
+            for change in self.storage.get_rate_changes(eval_ctx.get_entity_id(), id, start, end)? {
+                match change.dimension {
+                    Some(dimension) => keyed[i].entry(dimension).or_default().push((change.date, change.rate)),
+                    None => base[i].push((change.date, change.rate)),
+                }
+            }
+        }
 
-        if end_date < start_date {
-            return Err(EvaluationError::General("DISTRIBUTE: end date must be on or after start date".into()));
+        // Combinations with the same applicable rate series share one schedule
+        let mut shared: HashMap<Vec<Vec<DimensionValue>>, Arc<RateSchedule>> = HashMap::new();
+        let mut evaluated: HashMap<Vec<Decimal>, Decimal> = HashMap::new();
+        let mut schedules = Vec::with_capacity(combinations.len());
+        for combination in combinations {
+            let applicable: Vec<Vec<DimensionValue>> = keyed.iter()
+                .map(|series| accrue.by_dimensions.iter().cloned()
+                    .zip(combination.iter().cloned())
+                    .filter(|dimension| series.contains_key(dimension))
+                    .collect())
+                .collect();
+            if let Some(schedule) = shared.get(&applicable) {
+                schedules.push(schedule.clone());
+                continue;
+            }
+
+            let mut dates: Vec<Date> = base.iter().flatten().map(|(date, _)| *date)
+                .chain(keyed.iter().zip(&applicable)
+                    .flat_map(|(series, dimensions)| dimensions.iter().flat_map(move |dimension| series[dimension].iter().map(|(date, _)| *date))))
+                .collect();
+            dates.sort();
+            dates.dedup();
+
+            let mut schedule = Vec::with_capacity(dates.len());
+            for date in dates {
+                let in_effect = |series: &[(Date, Decimal)]| series.iter().rev().find(|(d, _)| *d <= date).map(|(_, rate)| *rate);
+                let inputs: Option<Vec<Decimal>> = (0..rate_ids.len())
+                    .map(|i| applicable[i].iter()
+                        .find_map(|dimension| in_effect(&keyed[i][dimension]))
+                        .or_else(|| in_effect(&base[i])))
+                    .collect();
+                let rate = match inputs {
+                    Some(inputs) => Some(match evaluated.get(&inputs) {
+                        Some(rate) => *rate,
+                        None => {
+                            let mut ctx = eval_ctx.clone();
+                            let mut variables = ctx.clone_variables();
+                            variables.extend(rate_ids.iter().cloned().zip(inputs.iter().map(|r| DataValue::Percentage(*r))));
+                            ctx.replace_variables(variables);
+                            let rate = match self.expression_evaluator.evaluate_expression(&ctx, &accrue.rate)? {
+                                DataValue::Percentage(r) | DataValue::Money(r) => r,
+                                DataValue::Int(i) => Decimal::from(i),
+                                _ => return Err(EvaluationError::General("ACCRUE: rate must evaluate to a number".into())),
+                            };
+                            evaluated.insert(inputs, rate);
+                            rate
+                        }
+                    }),
+                    None => None,
+                };
+                schedule.push((date, rate));
+            }
+            let schedule = Arc::new(schedule);
+            shared.insert(applicable, schedule.clone());
+            schedules.push(schedule);
         }
+        Ok(schedules)
+    }
+
+    fn distribute(&self, context: &ExecutionContext, cmd: &DistributeCommand) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let mut result = ExecutionResult::new();
 
         let total_amount = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.amount)? {
             DataValue::Money(d) => d,
@@ -478,53 +1031,468 @@ impl StatementExecutor {
             dims
         };
 
-        let periods = generate_periods(start_date, end_date, &cmd.period);
-        let num_periods = periods.len();
+        // Journal dates with the weight of each in the total
+        let (dates, weights): (Vec<Date>, Vec<Decimal>) = match &cmd.schedule {
+            DistributeSchedule::Periods { start_date, end_date, period, anchored, prorate } => {
+                let start_date = match self.expression_evaluator.evaluate_expression(&eval_ctx, start_date)? {
+                    DataValue::Date(d) => d,
+                    _ => return Err(EvaluationError::InvalidType),
+                };
+
+                let end_date = match self.expression_evaluator.evaluate_expression(&eval_ctx, end_date)? {
+                    DataValue::Date(d) => d,
+                    _ => return Err(EvaluationError::InvalidType),
+                };
 
-        let amounts = if cmd.prorate {
-            // Allocate by day count
-            let total_days: i64 = periods.iter().map(|(s, e)| (*e - *s).whole_days() + 1).sum();
-            let mut allocated = Decimal::ZERO;
-            let mut period_amounts = Vec::with_capacity(num_periods);
-            for (i, (ps, pe)) in periods.iter().enumerate() {
-                if i == num_periods - 1 {
-                    period_amounts.push(total_amount - allocated);
+                if end_date < start_date {
+                    return Err(EvaluationError::General("DISTRIBUTE: end date must be on or after start date".into()));
+                }
+
+                let periods = if *anchored {
+                    generate_anchored_periods(start_date, end_date, period)?
                 } else {
-                    let days = (*pe - *ps).whole_days() + 1;
-                    let amt = (total_amount * Decimal::from(days) / Decimal::from(total_days)).round_dp(2);
-                    allocated += amt;
-                    period_amounts.push(amt);
+                    generate_periods(start_date, end_date, period)
+                };
+                periods.into_iter()
+                    .map(|(ps, pe)| {
+                        // Allocate by day count, or evenly
+                        let weight = if *prorate { Decimal::from((pe - ps).whole_days() + 1) } else { Decimal::ONE };
+                        (pe, weight)
+                    })
+                    .unzip()
+            }
+            DistributeSchedule::Dates { dates, weights } => {
+                let dates = match self.expression_evaluator.evaluate_expression(&eval_ctx, dates)? {
+                    DataValue::List(items) => items.into_iter()
+                        .map(|item| match item {
+                            DataValue::Date(d) => Ok(d),
+                            _ => Err(EvaluationError::InvalidType),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?,
+                    _ => return Err(EvaluationError::InvalidType),
+                };
+                if dates.is_empty() || dates.windows(2).any(|pair| pair[0] >= pair[1]) {
+                    return Err(EvaluationError::General("DISTRIBUTE: schedule dates must be in ascending order".into()));
+                }
+
+                let weights = match weights {
+                    Some(weights) => match self.expression_evaluator.evaluate_expression(&eval_ctx, weights)? {
+                        DataValue::List(items) => items.into_iter()
+                            .map(|item| match item {
+                                DataValue::Money(d) => Ok(d),
+                                DataValue::Int(i) => Ok(Decimal::from(i)),
+                                _ => Err(EvaluationError::InvalidType),
+                            })
+                            .collect::<Result<Vec<_>, _>>()?,
+                        _ => return Err(EvaluationError::InvalidType),
+                    },
+                    None => vec![Decimal::ONE; dates.len()],
+                };
+                if weights.len() != dates.len() {
+                    return Err(EvaluationError::General("DISTRIBUTE: there must be one weight per date".into()));
+                }
+                if weights.iter().any(|w| *w <= Decimal::ZERO) {
+                    return Err(EvaluationError::General("DISTRIBUTE: weights must be positive".into()));
                 }
+                (dates, weights)
             }
-            period_amounts
-        } else {
-            // Even split, remainder to last period
-            let per_period = (total_amount / Decimal::from(num_periods as i64)).round_dp(2);
-            let mut period_amounts = vec![per_period; num_periods];
-            let allocated = per_period * Decimal::from((num_periods - 1) as i64);
-            period_amounts[num_periods - 1] = total_amount - allocated;
-            period_amounts
         };
 
-        for (i, (_ps, pe)) in periods.iter().enumerate() {
-            let period_amount = amounts[i];
+        if let Some(first) = dates.first() {
+            self.ensure_open(context, *first)?;
+        }
+
+        for (date, period_amount) in dates.iter().zip(split_by_weights(total_amount, &weights)) {
             let mut period_eval_ctx = eval_ctx.clone();
-            period_eval_ctx.set_effective_date(*pe);
+            period_eval_ctx.set_effective_date(*date);
 
             let journal = CreateJournalCommand {
-                date: *pe,
+                date: *date,
                 description: description.clone(),
                 amount: period_amount,
                 ledger_entries: self.build_ledger_entries(&period_eval_ctx, &cmd.operations, period_amount)?,
                 dimensions: dimensions.clone(),
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
+            };
+            self.post_journal(context, &journal)?;
+            result.journals_created += 1;
+        }
+
+        Ok(result)
+    }
+
+    /// Split the amount over the dimension's values in proportion to their weights and post
+    /// one journal with the debit legs repeated per value, each tagged with its value and
+    /// share, against credits of the whole amount. Shares are rounded by largest remainder so
+    /// they add up to the amount.
+    fn allocate(&self, context: &ExecutionContext, cmd: &AllocateCommand) -> Result<ExecutionResult, EvaluationError> {
+        let mut eval_ctx: ExpressionEvaluationContext = context.into();
+        let mut result = ExecutionResult::new();
+
+        let total_amount = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.amount)? {
+            DataValue::Money(d) => d,
+            DataValue::Int(i) => Decimal::from(i),
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        if total_amount == Decimal::ZERO {
+            return Err(EvaluationError::General("ALLOCATE: amount must not be zero".into()));
+        }
+
+        let date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.date)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+        eval_ctx.set_effective_date(date);
+
+        let description = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.description)? {
+            DataValue::String(s) => s,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        if cmd.dimensions.contains_key(&cmd.dimension) {
+            return Err(EvaluationError::General(format!("ALLOCATE: {} is set by ACROSS and cannot be used in FOR", cmd.dimension)));
+        }
+        let mut dimensions = BTreeMap::new();
+        for (k, v) in cmd.dimensions.iter() {
+            dimensions.insert(k.clone(), Arc::new(self.expression_evaluator.evaluate_expression(&eval_ctx, v)?));
+        }
+
+        let weights: Vec<(Arc<DataValue>, Decimal)> = match &cmd.driver {
+            AllocationDriver::Weights(pairs) => {
+                let mut weights = Vec::with_capacity(pairs.len());
+                for (value, weight) in pairs {
+                    let value = Arc::new(self.expression_evaluator.evaluate_expression(&eval_ctx, value)?);
+                    let weight = match self.expression_evaluator.evaluate_expression(&eval_ctx, weight)? {
+                        DataValue::Money(d) => d,
+                        DataValue::Int(i) => Decimal::from(i),
+                        _ => return Err(EvaluationError::InvalidType),
+                    };
+                    if weights.iter().any(|(v, _)| *v == value) {
+                        return Err(EvaluationError::General("ALLOCATE: each value can only be weighted once".into()));
+                    }
+                    weights.push((value, weight));
+                }
+                weights
+            }
+            AllocationDriver::Expression(driver) => match self.expression_evaluator.evaluate_expression(&eval_ctx, driver)? {
+                DataValue::DimensionBalances(items) => {
+                    if let Some(item) = items.iter().find(|item| item.dimension != cmd.dimension) {
+                        return Err(EvaluationError::General(format!("ALLOCATE: the driver is by {}, not {}", item.dimension, cmd.dimension)));
+                    }
+                    items.into_iter().map(|item| (item.value, item.amount)).collect()
+                }
+                _ => return Err(EvaluationError::General("ALLOCATE: BY needs WEIGHTS or a per-value driver such as balance_by()".into())),
+            },
+        };
+
+        if weights.iter().any(|(_, weight)| *weight < Decimal::ZERO) {
+            return Err(EvaluationError::General("ALLOCATE: weights must not be negative".into()));
+        }
+        let weights: Vec<_> = weights.into_iter().filter(|(_, weight)| !weight.is_zero()).collect();
+        if weights.is_empty() {
+            return Err(EvaluationError::General("ALLOCATE: no value has a positive weight".into()));
+        }
+
+        self.ensure_open(context, date)?;
+
+        let (debits, credits): (Vec<LedgerOperation>, Vec<LedgerOperation>) = cmd.operations.iter()
+            .cloned()
+            .partition(|op| matches!(op, LedgerOperation::Debit(_)));
+
+        let shares = largest_remainder_split(total_amount, &weights.iter().map(|(_, weight)| *weight).collect::<Vec<_>>())?;
+        let mut ledger_entries = Vec::new();
+        let mut leg_dimensions = BTreeMap::new();
+        for ((value, _), share) in weights.iter().zip(shares) {
+            if share.is_zero() {
+                continue;
+            }
+            let mut value_dimensions = dimensions.clone();
+            value_dimensions.insert(cmd.dimension.clone(), value.clone());
+            for entry in self.build_ledger_entries(&eval_ctx, &debits, share)? {
+                leg_dimensions.insert(ledger_entries.len(), value_dimensions.clone());
+                ledger_entries.push(entry);
+            }
+        }
+        // Credits relieve the shared pool, which belongs to no single value
+        ledger_entries.extend(self.build_ledger_entries(&eval_ctx, &credits, total_amount)?);
+
+        let journal = CreateJournalCommand {
+            date,
+            description,
+            amount: total_amount,
+            ledger_entries,
+            dimensions,
+            leg_dimensions,
+            reverses: None,
+        };
+        self.post_journal(context, &journal)?;
+        result.journals_created += 1;
+
+        Ok(result)
+    }
+
+    fn amortize(&self, context: &ExecutionContext, cmd: &AmortizeCommand) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let mut result = ExecutionResult::new();
+
+        let principal = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.principal)? {
+            DataValue::Money(d) => d,
+            DataValue::Int(i) => Decimal::from(i),
+            _ => return Err(EvaluationError::InvalidType),
+        };
+        if principal <= Decimal::ZERO {
+            return Err(EvaluationError::General("AMORTIZE: principal must be positive".into()));
+        }
+
+        let payments = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.payments)? {
+            DataValue::Int(n) if n > 0 && n <= MAX_AMORTIZATION_PAYMENTS as i64 => n as u32,
+            _ => return Err(EvaluationError::General(format!(
+                "AMORTIZE: number of payments must be a positive integer of at most {}", MAX_AMORTIZATION_PAYMENTS))),
+        };
+
+        let start_date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.start_date)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        // Rates named in the expression are read as of the loan start
+        let mut rate_ctx = eval_ctx.clone();
+        let annual_rate = loop {
+            match self.expression_evaluator.evaluate_expression(&rate_ctx, &cmd.rate) {
+                Ok(DataValue::Percentage(r)) | Ok(DataValue::Money(r)) => break r,
+                Ok(DataValue::Int(i)) => break Decimal::from(i),
+                Ok(_) => return Err(EvaluationError::General("AMORTIZE: rate must evaluate to a number".into())),
+                Err(EvaluationError::UnknownIdentifier(id)) => {
+                    let rate = self.storage.get_rate(&context.entity_id, &id, start_date)?;
+                    let mut variables = rate_ctx.clone_variables();
+                    variables.insert(Arc::from(id), DataValue::Percentage(rate));
+                    rate_ctx.replace_variables(variables);
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let schedule = amortization_schedule(principal, annual_rate, payments, &cmd.period, start_date)?;
+        result.variables.insert(Arc::from("schedule"), DataValue::Amortization(schedule.clone()));
+        if cmd.preview {
+            return Ok(result);
+        }
+
+        let (description, operations) = match &cmd.journal {
+            Some(journal) => journal,
+            None => return Err(EvaluationError::General("AMORTIZE: DESCRIPTION and ledger operations are required unless PREVIEW".into())),
+        };
+        let description = match self.expression_evaluator.evaluate_expression(&eval_ctx, description)? {
+            DataValue::String(s) => s,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        let mut dimensions = BTreeMap::new();
+        for (k, v) in cmd.dimensions.iter() {
+            dimensions.insert(k.clone(), Arc::new(self.expression_evaluator.evaluate_expression(&eval_ctx, v)?));
+        }
+
+        if let Some(first) = schedule.first() {
+            self.ensure_open(context, first.date)?;
+        }
+
+        for item in &schedule {
+            let mut payment_ctx = eval_ctx.clone();
+            payment_ctx.set_effective_date(item.date);
+            let mut variables = payment_ctx.clone_variables();
+            variables.insert(Arc::from("payment"), DataValue::Money(item.payment));
+            variables.insert(Arc::from("interest"), DataValue::Money(item.interest));
+            variables.insert(Arc::from("principal"), DataValue::Money(item.principal));
+            payment_ctx.replace_variables(variables);
+
+            let journal = CreateJournalCommand {
+                date: item.date,
+                description: description.clone(),
+                amount: item.payment,
+                ledger_entries: self.build_ledger_entries(&payment_ctx, operations, item.payment)?,
+                dimensions: dimensions.clone(),
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
             };
-            self.storage.create_journal(&context.entity_id, &journal)?;
+            self.post_journal(context, &journal)?;
             result.journals_created += 1;
         }
 
         Ok(result)
     }
 
+    /// Post the depreciation of every month ending on or before the date that is not posted yet,
+    /// for one asset or the whole register.
+    fn depreciate(&self, context: &ExecutionContext, cmd: &DepreciateCommand) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let mut result = ExecutionResult::new();
+
+        let through = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.through)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        let assets = match &cmd.asset {
+            Some(id) => vec![self.storage.get_fixed_asset(&context.entity_id, id)?],
+            None => self.storage.list_fixed_assets(&context.entity_id)?,
+        };
+        let mut due: Vec<(FixedAsset, Vec<DepreciationItem>)> = Vec::new();
+        for asset in assets.into_iter().filter(|asset| asset.disposed_on.is_none()) {
+            let items = unposted_depreciation(&asset, through)?;
+            if !items.is_empty() {
+                due.push((asset, items));
+            }
+        }
+
+        if let Some(first) = due.iter().filter_map(|(_, items)| items.first()).map(|item| item.period_end).min() {
+            self.ensure_open(context, first)?;
+        }
+        for (asset, items) in &due {
+            result.journals_created += self.post_depreciation(context, asset, items)?;
+        }
+
+        Ok(result)
+    }
+
+    /// One journal per month moving its depreciation from the expense account to the
+    /// accumulated depreciation, then record how far the asset is depreciated.
+    fn post_depreciation(&self, context: &ExecutionContext, asset: &FixedAsset, items: &[DepreciationItem]) -> Result<usize, EvaluationError> {
+        let mut posted = 0;
+        for item in items.iter().filter(|item| !item.depreciation.is_zero()) {
+            let journal = CreateJournalCommand {
+                date: item.period_end,
+                description: Arc::from(format!("Depreciation - {}", asset.id)),
+                amount: item.depreciation,
+                ledger_entries: vec![
+                    LedgerEntryCommand::Debit {
+                        account_id: asset.expense_account.clone(),
+                        amount: item.depreciation,
+                        units: None,
+                        currency_amount: None,
+                    },
+                    LedgerEntryCommand::Credit {
+                        account_id: asset.accumulated_account.clone(),
+                        amount: item.depreciation,
+                        units: None,
+                        currency_amount: None,
+                    },
+                ],
+                dimensions: BTreeMap::new(),
+                leg_dimensions: BTreeMap::new(),
+                reverses: None,
+            };
+            self.post_journal(context, &journal)?;
+            posted += 1;
+        }
+        if let Some(last) = items.last() {
+            self.storage.update_fixed_asset(&context.entity_id, &asset.id, Some(last.period_end), asset.disposed_on)?;
+        }
+        Ok(posted)
+    }
+
+    /// Catch up the asset's depreciation to the disposal date, then take it off the books:
+    /// the cost and accumulated depreciation are cleared and the difference between the
+    /// proceeds and the book value is the gain or loss.
+    fn dispose(&self, context: &ExecutionContext, cmd: &DisposeCommand) -> Result<ExecutionResult, EvaluationError> {
+        let eval_ctx: ExpressionEvaluationContext = context.into();
+        let mut result = ExecutionResult::new();
+
+        let proceeds = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.proceeds)? {
+            DataValue::Money(d) => d,
+            DataValue::Int(i) => Decimal::from(i),
+            _ => return Err(EvaluationError::InvalidType),
+        };
+        if proceeds < Decimal::ZERO {
+            return Err(EvaluationError::General("DISPOSE ASSET: proceeds must not be negative".into()));
+        }
+
+        let date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.date)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        let description = match self.expression_evaluator.evaluate_expression(&eval_ctx, &cmd.description)? {
+            DataValue::String(s) => s,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
+        let asset = self.storage.get_fixed_asset(&context.entity_id, &cmd.asset)?;
+        if let Some(disposed_on) = asset.disposed_on {
+            return Err(EvaluationError::General(format!("DISPOSE ASSET: {} was disposed of on {}", asset.id, disposed_on)));
+        }
+        if date < asset.in_service || asset.depreciated_through.is_some_and(|through| date < through) {
+            return Err(EvaluationError::General(format!("DISPOSE ASSET: {} cannot be disposed of before it is in service or depreciated", asset.id)));
+        }
+
+        let items = unposted_depreciation(&asset, date)?;
+        self.ensure_open(context, items.first().map_or(date, |item| item.period_end))?;
+        result.journals_created += self.post_depreciation(context, &asset, &items)?;
+
+        let accumulated = depreciation_schedule(&asset)?.iter()
+            .take_while(|item| item.period_end <= date)
+            .last()
+            .map_or(Decimal::ZERO, |item| item.accumulated);
+        let gain_or_loss = proceeds - (asset.cost - accumulated);
+
+        let mut entries = Vec::new();
+        if !proceeds.is_zero() {
+            entries.push(LedgerEntryCommand::Debit {
+                account_id: cmd.proceeds_account.clone(),
+                amount: proceeds,
+                units: None,
+                currency_amount: None,
+            });
+        }
+        if !accumulated.is_zero() {
+            entries.push(LedgerEntryCommand::Debit {
+                account_id: asset.accumulated_account.clone(),
+                amount: accumulated,
+                units: None,
+                currency_amount: None,
+            });
+        }
+        entries.push(LedgerEntryCommand::Credit {
+            account_id: asset.asset_account.clone(),
+            amount: asset.cost,
+            units: None,
+            currency_amount: None,
+        });
+        if gain_or_loss > Decimal::ZERO {
+            entries.push(LedgerEntryCommand::Credit {
+                account_id: cmd.gain_loss_account.clone(),
+                amount: gain_or_loss,
+                units: None,
+                currency_amount: None,
+            });
+        } else if gain_or_loss < Decimal::ZERO {
+            entries.push(LedgerEntryCommand::Debit {
+                account_id: cmd.gain_loss_account.clone(),
+                amount: gain_or_loss.abs(),
+                units: None,
+                currency_amount: None,
+            });
+        }
+
+        let journal = CreateJournalCommand {
+            date,
+            description,
+            amount: asset.cost,
+            ledger_entries: entries,
+            dimensions: BTreeMap::new(),
+            leg_dimensions: BTreeMap::new(),
+            reverses: None,
+        };
+        self.post_journal(context, &journal)?;
+        result.journals_created += 1;
+
+        let depreciated_through = items.last().map(|item| item.period_end).or(asset.depreciated_through);
+        self.storage.update_fixed_asset(&context.entity_id, &asset.id, depreciated_through, Some(date))?;
+
+        Ok(result)
+    }
+
     fn sell(&self, context: &ExecutionContext, sell: &SellCommand) -> Result<ExecutionResult, EvaluationError> {
         let eval_ctx: ExpressionEvaluationContext = context.into();
 
@@ -559,7 +1527,7 @@ impl StatementExecutor {
             dim_map.insert(key.clone(), Arc::new(val));
         }
 
-        let cost_basis = self.storage.deplete_lots(&context.entity_id, &sell.account, units, &sell.method, &dim_map)?;
+        let cost_basis = self.storage.deplete_lots(&context.entity_id, &sell.account, date, units, &sell.method, &dim_map)?;
         let gain_or_loss = proceeds - cost_basis;
 
         let mut entries = vec![
@@ -567,11 +1535,13 @@ impl StatementExecutor {
                 account_id: sell.proceeds_account.clone(),
                 amount: proceeds,
                 units: None,
+                currency_amount: None,
             },
             LedgerEntryCommand::Credit {
                 account_id: sell.account.clone(),
                 amount: cost_basis,
                 units: None, // lots already depleted by sell
+                currency_amount: None,
             },
         ];
 
@@ -580,12 +1550,14 @@ impl StatementExecutor {
                 account_id: sell.gain_loss_account.clone(),
                 amount: gain_or_loss,
                 units: None,
+                currency_amount: None,
             });
         } else if gain_or_loss < dec!(0) {
             entries.push(LedgerEntryCommand::Debit {
                 account_id: sell.gain_loss_account.clone(),
                 amount: gain_or_loss.abs(),
                 units: None,
+                currency_amount: None,
             });
         }
 
@@ -595,9 +1567,11 @@ impl StatementExecutor {
             amount: proceeds,
             dimensions: dim_map,
             ledger_entries: entries,
+            leg_dimensions: BTreeMap::new(),
+            reverses: None,
         };
 
-        self.storage.create_journal(&context.entity_id, &command)?;
+        self.post_journal(context, &command)?;
 
         let mut result = ExecutionResult::new();
         result.journals_created += 1;
@@ -623,20 +1597,30 @@ impl StatementExecutor {
             return Err(EvaluationError::DivideByZero);
         }
 
+        let date = match self.expression_evaluator.evaluate_expression(&eval_ctx, &split.date)? {
+            DataValue::Date(d) => d,
+            _ => return Err(EvaluationError::InvalidType),
+        };
+
         let ratio = new_units / old_units;
-        self.storage.split_lots(&context.entity_id, &split.account, ratio, None)?;
+        self.storage.split_lots(&context.entity_id, &split.account, date, ratio, &DimensionFilter::new())?;
 
         Ok(ExecutionResult::new())
     }
 }
 
 /// Generate a list of (period_start, period_end) date tuples for the given range and frequency.
-fn generate_periods(start: Date, end: Date, period: &Period) -> Vec<(Date, Date)> {
+pub(crate) fn generate_periods(start: Date, end: Date, period: &Period) -> Vec<(Date, Date)> {
     let mut periods = Vec::new();
     let mut cursor = start;
 
     while cursor <= end {
         let period_end = match period {
+            Period::Daily => cursor,
+            Period::Weekly => {
+                // Weeks run Monday to Sunday
+                cursor + time::Duration::days(6 - cursor.weekday().number_days_from_monday() as i64)
+            }
             Period::Monthly => {
                 let m = cursor.month().next();
                 let (y, next_month) = if m == time::Month::January {
@@ -662,6 +1646,10 @@ fn generate_periods(start: Date, end: Date, period: &Period) -> Vec<(Date, Date)
                 let first_of_next = Date::from_calendar_date(y, next_month, 1).unwrap();
                 first_of_next.previous_day().unwrap()
             }
+            Period::SemiAnnually => {
+                let half_end = if (cursor.month() as u8) <= 6 { time::Month::June } else { time::Month::December };
+                Date::from_calendar_date(cursor.year(), half_end, time::util::days_in_year_month(cursor.year(), half_end)).unwrap()
+            }
             Period::Yearly => {
                 let first_of_next_year = Date::from_calendar_date(cursor.year() + 1, time::Month::January, 1).unwrap();
                 first_of_next_year.previous_day().unwrap()
@@ -682,10 +1670,340 @@ fn generate_periods(start: Date, end: Date, period: &Period) -> Vec<(Date, Date)
     periods
 }
 
-fn calc_daily_accural_amount(rate: Decimal, pv: Decimal, compounding: &Option<Compounding>) -> Decimal {
+/// Like [`generate_periods`], but each period runs from the start date's anniversary to the
+/// day before the next one (monthly from the 15th covers the 15th to the 14th).
+pub(crate) fn generate_anchored_periods(start: Date, end: Date, period: &Period) -> Result<Vec<(Date, Date)>, EvaluationError> {
+    let mut periods = Vec::new();
+    let mut cursor = start;
+    let mut n = 0;
+
+    while cursor <= end {
+        n += 1;
+        let next = add_periods(start, period, n)?;
+        let period_end = next.previous_day().unwrap();
+        periods.push((cursor, period_end.min(end)));
+        cursor = next;
+    }
+
+    Ok(periods)
+}
+
+/// Split `total` in proportion to `weights`, rounding each share to cents; the last share
+/// takes the remainder so the shares add up to `total`.
+fn split_by_weights(total: Decimal, weights: &[Decimal]) -> Vec<Decimal> {
+    let total_weight: Decimal = weights.iter().sum();
+    let mut allocated = Decimal::ZERO;
+    let mut amounts = Vec::with_capacity(weights.len());
+    for (i, weight) in weights.iter().enumerate() {
+        if i == weights.len() - 1 {
+            amounts.push(total - allocated);
+        } else {
+            let amount = (total * weight / total_weight).round_dp(2);
+            allocated += amount;
+            amounts.push(amount);
+        }
+    }
+    amounts
+}
+
+/// Split `total` in proportion to `weights` by the largest remainder method: every share is
+/// cut down to whole cents and the cents left over go one each to the shares that lost the
+/// most, so the shares add up to `total` exactly. `total` must be in whole cents.
+fn largest_remainder_split(total: Decimal, weights: &[Decimal]) -> Result<Vec<Decimal>, EvaluationError> {
+    let overflow = || EvaluationError::General("ALLOCATE: weights are too large to split the amount".into());
+    if total.normalize().scale() > 2 {
+        return Err(EvaluationError::General(format!("ALLOCATE: amount {} has fractions of a cent", total)));
+    }
+    let total_weight = weights.iter().try_fold(Decimal::ZERO, |sum, weight| sum.checked_add(*weight)).ok_or_else(overflow)?;
+    let cents = total.abs().checked_mul(Decimal::ONE_HUNDRED).ok_or_else(overflow)?;
+    let exact = weights.iter()
+        .map(|weight| cents.checked_mul(*weight).and_then(|share| share.checked_div(total_weight)).ok_or_else(overflow))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut shares: Vec<Decimal> = exact.iter().map(|share| share.floor()).collect();
+
+    let left_over = (cents - shares.iter().sum::<Decimal>()).to_usize().unwrap_or(0);
+    let mut by_remainder: Vec<usize> = (0..shares.len()).collect();
+    by_remainder.sort_by(|a, b| (exact[*b] - shares[*b]).cmp(&(exact[*a] - shares[*a])));
+    for i in by_remainder.into_iter().take(left_over) {
+        shares[i] += Decimal::ONE;
+    }
+
+    let sign = if total < Decimal::ZERO { -Decimal::ONE } else { Decimal::ONE };
+    Ok(shares.into_iter().map(|share| sign * share / Decimal::ONE_HUNDRED).collect())
+}
+
+/// Longest AMORTIZE schedule: 27 years of daily or 833 years of monthly payments.
+const MAX_AMORTIZATION_PAYMENTS: u32 = 10_000;
+
+/// Level payments of `principal` over `payments` periods at `annual_rate`, the first one
+/// period after `start`. Each payment is rounded to cents and the last one absorbs the
+/// rounding so the balance ends at zero.
+pub(crate) fn amortization_schedule(principal: Decimal, annual_rate: Decimal, payments: u32, period: &Period, start: Date) -> Result<Vec<AmortizationItem>, EvaluationError> {
+    let overflow = || EvaluationError::General("AMORTIZE: payment overflows at this rate and number of payments".into());
+    let rate = annual_rate / Decimal::from(periods_per_year(period));
+    let payment = if rate.is_zero() {
+        principal / Decimal::from(payments)
+    } else {
+        let growth = (Decimal::ONE + rate).checked_powi(payments as i64).ok_or_else(overflow)?;
+        principal.checked_mul(rate)
+            .and_then(|interest| interest.checked_mul(growth))
+            .and_then(|numerator| numerator.checked_div(growth - Decimal::ONE))
+            .ok_or_else(overflow)?
+    }.round_dp(2);
+
+    let mut balance = principal;
+    let mut schedule = Vec::with_capacity(payments as usize);
+    for number in 1..=payments {
+        let interest = (balance * rate).round_dp(2);
+        let principal_paid = if number == payments { balance } else { (payment - interest).min(balance) };
+        balance -= principal_paid;
+        schedule.push(AmortizationItem {
+            number,
+            date: add_periods(start, period, number)?,
+            payment: interest + principal_paid,
+            interest,
+            principal: principal_paid,
+            balance,
+        });
+    }
+    Ok(schedule)
+}
+
+/// Longest useful life CREATE ASSET accepts.
+const MAX_ASSET_LIFE_YEARS: u32 = 100;
+
+/// Monthly depreciation over the asset's life, starting with the month it enters service.
+/// Each year's depreciation follows the method and is spread evenly over its twelve months;
+/// the last month of a year and the last year absorb rounding, so the asset ends at salvage.
+pub(crate) fn depreciation_schedule(asset: &FixedAsset) -> Result<Vec<DepreciationItem>, EvaluationError> {
+    let depreciable = asset.cost - asset.salvage;
+    let years = Decimal::from(asset.life_years);
+    let mut taken = Decimal::ZERO;
+    let mut items = Vec::with_capacity(asset.life_years as usize * 12);
+    for year in 0..asset.life_years {
+        let remaining_years = Decimal::from(asset.life_years - year);
+        let annual = if year + 1 == asset.life_years {
+            depreciable - taken
+        } else {
+            match &asset.method {
+                DepreciationMethod::StraightLine => depreciable / years,
+                DepreciationMethod::SumOfYears => depreciable * remaining_years * Decimal::TWO / (years * (years + Decimal::ONE)),
+                DepreciationMethod::DecliningBalance(factor) => {
+                    let declining = (asset.cost - taken) * factor / years;
+                    let straight = (depreciable - taken) / remaining_years;
+                    declining.max(straight).min(depreciable - taken)
+                }
+            }.round_dp(2)
+        };
+
+        let monthly = (annual / Decimal::from(12)).round_dp(2);
+        for month in 0..12 {
+            let depreciation = if month == 11 { annual - monthly * Decimal::from(11) } else { monthly };
+            taken += depreciation;
+            items.push(DepreciationItem {
+                period_end: month_end(add_periods(asset.in_service, &Period::Monthly, year * 12 + month)?),
+                depreciation,
+                accumulated: taken,
+                book_value: asset.cost - taken,
+            });
+        }
+    }
+    Ok(items)
+}
+
+/// Scheduled months ending on or before `through` whose depreciation has not been posted.
+fn unposted_depreciation(asset: &FixedAsset, through: Date) -> Result<Vec<DepreciationItem>, EvaluationError> {
+    Ok(depreciation_schedule(asset)?.into_iter()
+        .filter(|item| item.period_end <= through && asset.depreciated_through.is_none_or(|posted| item.period_end > posted))
+        .collect())
+}
+
+fn month_end(date: Date) -> Date {
+    Date::from_calendar_date(date.year(), date.month(), time::util::days_in_year_month(date.year(), date.month())).unwrap()
+}
+
+fn periods_per_year(period: &Period) -> u32 {
+    match period {
+        Period::Daily => 365,
+        Period::Weekly => 52,
+        Period::Monthly => 12,
+        Period::Quarterly => 4,
+        Period::SemiAnnually => 2,
+        Period::Yearly => 1,
+    }
+}
+
+/// `date` moved `n` periods on, keeping its day of month where the target month has it and
+/// falling back to the month's last day otherwise.
+fn add_periods(date: Date, period: &Period, n: u32) -> Result<Date, EvaluationError> {
+    let out_of_range = || EvaluationError::General(format!("date {} periods after {} is out of range", n, date));
+    let months = match period {
+        Period::Daily => return date.checked_add(time::Duration::days(n as i64)).ok_or_else(out_of_range),
+        Period::Weekly => return date.checked_add(time::Duration::weeks(n as i64)).ok_or_else(out_of_range),
+        Period::Monthly => n as i64,
+        Period::Quarterly => 3 * n as i64,
+        Period::SemiAnnually => 6 * n as i64,
+        Period::Yearly => 12 * n as i64,
+    };
+    let index = date.year() as i64 * 12 + date.month() as i64 - 1 + months;
+    let year = i32::try_from(index.div_euclid(12)).map_err(|_| out_of_range())?;
+    let month = time::Month::try_from((index.rem_euclid(12) + 1) as u8).unwrap();
+    Date::from_calendar_date(year, month, date.day().min(time::util::days_in_year_month(year, month)))
+        .map_err(|_| out_of_range())
+}
+
+/// Rate in effect from each date on; `None` while some rate in the expression has no value.
+type RateSchedule = Vec<(Date, Option<Decimal>)>;
+/// A dimension key with one of its values, as rates are set `FOR`.
+type DimensionValue = (Arc<str>, Arc<DataValue>);
+/// Dimension value combination with the amount accrued on it.
+type CombinationAmount = (Vec<Arc<DataValue>>, Decimal);
+
+/// Interest per dimension value combination over `start..=end`. Balances are carried forward
+/// from the movements and rates from their schedules, so each day costs no storage reads.
+fn accrue_amounts(
+    accrue: &AccrueCommand,
+    combinations: Vec<Vec<Arc<DataValue>>>,
+    schedules: &[Arc<RateSchedule>],
+    movements: &[DimensionMovement],
+    start: Date,
+    end: Date,
+) -> Result<Vec<CombinationAmount>, EvaluationError> {
+    let day_count = accrue.day_count.clone().unwrap_or(DayCount::Act365Fixed);
+    let index: HashMap<&[Arc<DataValue>], usize> = combinations.iter()
+        .enumerate()
+        .map(|(i, combination)| (combination.as_slice(), i))
+        .collect();
+    // Combinations each posted value combination counts towards, matching hierarchically
+    let mut targets: HashMap<&[Arc<DataValue>], Vec<usize>> = HashMap::new();
+
+    let mut balances = vec![Decimal::ZERO; combinations.len()];
+    let mut accrued = vec![Decimal::ZERO; combinations.len()];
+    // Interest accrued so far that compounding has already added to the principal
+    let mut capitalized = vec![Decimal::ZERO; combinations.len()];
+    let mut rate_years = vec![Decimal::ZERO; combinations.len()];
+    let mut days = 0u32;
+
+    let mut movements = movements.iter().peekable();
+    // Position in each combination's schedule and the rate it gives for the current day
+    let mut cursors = vec![0usize; combinations.len()];
+    let mut rates: Vec<Option<Decimal>> = vec![None; combinations.len()];
+    let mut dt = start;
+    loop {
+        while let Some((_, posted, amount)) = movements.next_if(|(date, _, _)| *date <= dt) {
+            let matched = targets.entry(posted.as_slice()).or_insert_with(|| {
+                ancestor_combinations(posted).iter()
+                    .filter_map(|combination| index.get(combination.as_slice()).copied())
+                    .collect()
+            });
+            for &i in matched.iter() {
+                balances[i] += *amount;
+            }
+        }
+
+        let fraction = year_fraction(&day_count, dt);
+        let capitalize = capitalizes_on(&accrue.compounding, dt);
+        days += 1;
+        for i in 0..combinations.len() {
+            let schedule = &schedules[i];
+            while let Some((_, rate)) = schedule.get(cursors[i]).filter(|(date, _)| *date <= dt) {
+                rates[i] = *rate;
+                cursors[i] += 1;
+            }
+            let rate = rates[i].ok_or(StorageError::NoRateFound)?;
+
+            if accrue.average_balance {
+                // Simple interest: average daily balance × Σ (daily rate × year fraction), so
+                // mid-period rate changes are weighted by day count
+                rate_years[i] += rate * fraction;
+                accrued[i] += balances[i];
+            } else {
+                accrued[i] += calc_daily_accural_amount(rate, balances[i] + capitalized[i], fraction, &accrue.compounding)?;
+                if capitalize {
+                    capitalized[i] = accrued[i];
+                }
+            }
+        }
+
+        if dt >= end {
+            break;
+        }
+        dt = match dt.next_day() {
+            Some(d) => d,
+            None => break,
+        };
+    }
+
+    if accrue.average_balance {
+        for (sum, rate_years) in accrued.iter_mut().zip(&rate_years) {
+            *sum = *sum / Decimal::from(days) * rate_years;
+        }
+    }
+    Ok(combinations.into_iter().zip(accrued).collect())
+}
+
+/// The combination itself and every combination formed from ancestors of its `/`-separated values.
+fn ancestor_combinations(combination: &[Arc<DataValue>]) -> Vec<Vec<Arc<DataValue>>> {
+    let mut result = vec![Vec::with_capacity(combination.len())];
+    for value in combination {
+        let mut candidates = vec![value.clone()];
+        if let DataValue::String(path) = value.as_ref() {
+            candidates.extend(path.match_indices('/').map(|(i, _)| Arc::new(DataValue::String(Arc::from(&path[..i])))));
+        }
+        result = result.into_iter()
+            .flat_map(|prefix| candidates.iter().map(move |candidate| {
+                let mut extended = prefix.clone();
+                extended.push(candidate.clone());
+                extended
+            }))
+            .collect();
+    }
+    result
+}
+
+/// Interest for one day on `principal`. Without a `COMPOUND` clause the rate is a daily rate.
+fn calc_daily_accural_amount(rate: Decimal, principal: Decimal, year_fraction: Decimal, compounding: &Option<Compounding>) -> Result<Decimal, EvaluationError> {
+    Ok(match compounding {
+        None => principal * rate,
+        Some(Compounding::Continuous) => {
+            (rate * year_fraction).checked_exp()
+                .and_then(|growth| principal.checked_mul(growth - Decimal::ONE))
+                .ok_or_else(|| EvaluationError::General(format!("ACCRUE: continuous compounding overflows at rate {}", rate)))?
+        }
+        Some(_) => principal * rate * year_fraction,
+    })
+}
+
+/// Whether interest accrued up to the end of `date` is added to the principal.
+fn capitalizes_on(compounding: &Option<Compounding>, date: Date) -> bool {
+    let month_end = date.next_day().is_none_or(|next| next.month() != date.month());
     match compounding {
-        Some(Compounding::Continuous) => pv * rate,
-        Some(Compounding::Daily) => pv * rate / dec!(365),
-        None => pv * rate,
+        None | Some(Compounding::Daily) | Some(Compounding::Continuous) => true,
+        Some(Compounding::Monthly) => month_end,
+        Some(Compounding::Quarterly) => month_end && matches!(date.month(), time::Month::March | time::Month::June | time::Month::September | time::Month::December),
+        Some(Compounding::Annually) => month_end && date.month() == time::Month::December,
+        Some(Compounding::Simple) => false,
     }
-}
\ No newline at end of file
+}
+
+/// The share of a year that the single day `date` represents under `day_count`.
+fn year_fraction(day_count: &DayCount, date: Date) -> Decimal {
+    match day_count {
+        DayCount::Act365Fixed => Decimal::ONE / dec!(365),
+        DayCount::Act360 => Decimal::ONE / dec!(360),
+        DayCount::ActAct => Decimal::ONE / Decimal::from(time::util::days_in_year(date.year())),
+        DayCount::Thirty360 => {
+            // 30/360 (bond basis) from this day to the next: the 31st counts for nothing
+            // and the last day of February makes up the rest of a 30-day month
+            let Some(next) = date.next_day() else {
+                return Decimal::ZERO;
+            };
+            let d1 = date.day().min(30) as i64;
+            let d2 = if d1 == 30 && next.day() == 31 { 30 } else { next.day() as i64 };
+            let days = 360 * (next.year() - date.year()) as i64
+                + 30 * (next.month() as i64 - date.month() as i64)
+                + (d2 - d1);
+            Decimal::from(days) / dec!(360)
+        }
+    }
+}
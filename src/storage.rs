@@ -1,4 +1,4 @@
-pub use dblentry_core::storage::{StorageBackend, StorageError, TransactionId};
+pub use dblentry_core::storage::{ensure_period_open, DimensionMovement, StorageBackend, StorageError, TransactionId};
 pub use dblentry_memory::InMemoryStorage;
 
 /// Default entity used when no entity is specified
@@ -1,12 +1,16 @@
 use std::sync::Arc;
 
-use dblentry::evaluator::{ExpressionEvaluator, QueryVariables};
+use dblentry::auth::{self, Role};
+use dblentry::evaluator::{EvaluationError, ExpressionEvaluator, QueryVariables};
 use dblentry::function_registry::{FunctionRegistry, Function};
-use dblentry::functions::{Balance, Statement, TrialBalance, IncomeStatement, AccountCount, Convert, FxRate, Round, Abs, Min, Max, Units, MarketValue, UnrealizedGain, CostBasis, Lots};
-use dblentry::ast::{CreateCommand, Expression, UnaryExpression, Literal};
+use dblentry::functions::{format_journal_id, Balance, Statement, TrialBalance, IncomeStatement, BalanceSheet, CashFlowStatement, AccountCount, Convert, FxRate, Round, Abs, Min, Max, Units, MarketValue, UnrealizedGain, CostBasis, Lots, Journal, Journals, BalanceBy, ActivityBy, Debits, Credits, Activity, BalanceSeries, AverageBalance, DepreciationSchedule};
+use dblentry::ast::{AccountClass, AccountType, CreateCommand, Expression, UnaryExpression, Literal};
+use dblentry::dblentry_core::params::{bind_params, FqlParam, FqlParams};
+use dblentry::display::format_data_value;
 use dblentry::lexer;
 use dblentry::models::DataValue;
 use dblentry::statement_executor::{ExecutionContext, StatementExecutor};
+use dblentry::storage::StorageError;
 use dblentry_memory::InMemoryStorage;
 
 /// Generate the same test body against memory, SQLite, and PostgreSQL backends.
@@ -43,6 +47,8 @@ fn register_functions(registry: &FunctionRegistry, storage: &Arc<dyn dblentry::s
     registry.register_function("statement", Function::Scalar(Arc::new(Statement::new(storage.clone()))));
     registry.register_function("trial_balance", Function::Scalar(Arc::new(TrialBalance::new(storage.clone()))));
     registry.register_function("income_statement", Function::Scalar(Arc::new(IncomeStatement::new(storage.clone()))));
+    registry.register_function("balance_sheet", Function::Scalar(Arc::new(BalanceSheet::new(storage.clone()))));
+    registry.register_function("cash_flow_statement", Function::Scalar(Arc::new(CashFlowStatement::new(storage.clone()))));
     registry.register_function("account_count", Function::Scalar(Arc::new(AccountCount::new(storage.clone()))));
     registry.register_function("convert", Function::Scalar(Arc::new(Convert::new(storage.clone()))));
     registry.register_function("fx_rate", Function::Scalar(Arc::new(FxRate::new(storage.clone()))));
@@ -55,6 +61,23 @@ fn register_functions(registry: &FunctionRegistry, storage: &Arc<dyn dblentry::s
     registry.register_function("unrealized_gain", Function::Scalar(Arc::new(UnrealizedGain::new(storage.clone()))));
     registry.register_function("cost_basis", Function::Scalar(Arc::new(CostBasis::new(storage.clone()))));
     registry.register_function("lots", Function::Scalar(Arc::new(Lots::new(storage.clone()))));
+    registry.register_function("journal", Function::Scalar(Arc::new(Journal::new(storage.clone()))));
+    registry.register_function("journals", Function::Scalar(Arc::new(Journals::new(storage.clone()))));
+    registry.register_function("balance_by", Function::Scalar(Arc::new(BalanceBy::new(storage.clone()))));
+    registry.register_function("activity_by", Function::Scalar(Arc::new(ActivityBy::new(storage.clone()))));
+    registry.register_function("debits", Function::Scalar(Arc::new(Debits::new(storage.clone()))));
+    registry.register_function("credits", Function::Scalar(Arc::new(Credits::new(storage.clone()))));
+    registry.register_function("activity", Function::Scalar(Arc::new(Activity::new(storage.clone()))));
+    registry.register_function("balance_series", Function::Scalar(Arc::new(BalanceSeries::new(storage.clone()))));
+    registry.register_function("average_balance", Function::Scalar(Arc::new(AverageBalance::new(storage.clone()))));
+    registry.register_function("depreciation_schedule", Function::Scalar(Arc::new(DepreciationSchedule::new(storage.clone()))));
+}
+
+/// Tests set up their ledgers themselves, so they run as admin unless they narrow the role.
+fn admin_context() -> ExecutionContext {
+    let mut context = ExecutionContext::new(time::OffsetDateTime::now_utc().date(), QueryVariables::new());
+    context.role = Role::Admin;
+    context
 }
 
 fn setup() -> (StatementExecutor, ExecutionContext) {
@@ -63,9 +86,7 @@ fn setup() -> (StatementExecutor, ExecutionContext) {
     register_functions(&function_registry, &storage);
     let expression_evaluator = Arc::new(ExpressionEvaluator::new(Arc::new(function_registry), storage.clone()));
     let exec = StatementExecutor::new(expression_evaluator, storage);
-    let eff_date = time::OffsetDateTime::now_utc().date();
-    let context = ExecutionContext::new(eff_date, QueryVariables::new());
-    (exec, context)
+    (exec, admin_context())
 }
 
 fn execute_script(exec: &StatementExecutor, context: &mut ExecutionContext, script: &str) -> Vec<dblentry::statement_executor::ExecutionResult> {
@@ -1780,9 +1801,7 @@ fn setup_sqlite() -> (StatementExecutor, ExecutionContext) {
     register_functions(&function_registry, &storage);
     let expression_evaluator = Arc::new(ExpressionEvaluator::new(Arc::new(function_registry), storage.clone()));
     let exec = StatementExecutor::new(expression_evaluator, storage);
-    let eff_date = time::OffsetDateTime::now_utc().date();
-    let context = ExecutionContext::new(eff_date, QueryVariables::new());
-    (exec, context)
+    (exec, admin_context())
 }
 
 #[test]
@@ -1923,9 +1942,7 @@ fn setup_postgres() -> (StatementExecutor, ExecutionContext) {
         storage.clone(),
     ));
     let exec = StatementExecutor::new(expression_evaluator, storage);
-    let eff_date = time::OffsetDateTime::now_utc().date();
-    let context = ExecutionContext::new(eff_date, QueryVariables::new());
-    (exec, context)
+    (exec, admin_context())
 }
 
 fn postgres_available() -> bool {
@@ -2159,6 +2176,24 @@ fn test_modulo_by_zero_returns_error() {
     assert!(result.is_err(), "Modulo by zero should return error");
 }
 
+/// Test that `%` directly followed by an operand is read as modulo, not a percentage.
+#[test]
+fn test_percent_before_operand_is_modulo() {
+    let (exec, mut ctx) = setup();
+
+    let results = execute_script(&exec, &mut ctx, "
+        LET a = 10;
+        LET b = 4;
+        LET x = 3;
+        GET a%b AS ident_mod, 10%x AS literal_mod, 10%(x) AS paren_mod, 10% AS percent
+    ");
+    let vars = &results[3].variables;
+    assert_eq!(vars["ident_mod"], DataValue::Int(2));
+    assert_eq!(vars["literal_mod"], DataValue::Int(1));
+    assert_eq!(vars["paren_mod"], DataValue::Int(1));
+    assert_eq!(vars["percent"], DataValue::Percentage(rust_decimal::Decimal::new(10, 2)));
+}
+
 /// Test that normal division still works correctly after the fix.
 #[test]
 fn test_division_still_works() {
@@ -2632,6 +2667,108 @@ fn test_distribute_negative_amount() {
     }
 }
 
+#[test]
+fn test_distribute_weekly_and_semiannually() {
+    let (exec, mut ctx) = setup();
+    let results = execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @deferred_revenue LIABILITY;
+        CREATE ACCOUNT @subscription_revenue INCOME;
+        DISTRIBUTE 700
+            FROM 2024-01-03 TO 2024-01-21
+            PERIOD WEEKLY
+            DESCRIPTION 'Weekly plan'
+            DEBIT @deferred_revenue,
+            CREDIT @subscription_revenue;
+        DISTRIBUTE 1000
+            FROM 2024-01-01 TO 2024-12-31
+            PERIOD SEMIANNUALLY
+            DESCRIPTION 'Semi-annual plan'
+            DEBIT @deferred_revenue,
+            CREDIT @subscription_revenue;
+    ");
+    // Weeks end on Sunday: Jan 7, 14 and 21
+    assert_eq!(results[2].journals_created, 3);
+    assert_eq!(results[3].journals_created, 2);
+
+    let results = execute_script(&exec, &mut ctx, "
+        GET balance(@subscription_revenue, 2024-01-06) AS before,
+            balance(@subscription_revenue, 2024-01-07) AS week1,
+            balance(@subscription_revenue, 2024-06-30) AS half
+    ");
+    assert_eq!(results[0].variables["before"], DataValue::Money(rust_decimal::Decimal::ZERO));
+    assert_eq!(results[0].variables["week1"], DataValue::Money(rust_decimal_macros::dec!(233.33)));
+    assert_eq!(results[0].variables["half"], DataValue::Money(rust_decimal_macros::dec!(1200)));
+}
+
+#[test]
+fn test_distribute_anchored_periods() {
+    let (exec, mut ctx) = setup();
+    let results = execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @deferred_revenue LIABILITY;
+        CREATE ACCOUNT @subscription_revenue INCOME;
+        DISTRIBUTE 1200
+            FROM 2024-01-15 TO 2025-01-14
+            PERIOD MONTHLY ANCHORED
+            DESCRIPTION 'Annual plan'
+            DEBIT @deferred_revenue,
+            CREDIT @subscription_revenue;
+    ");
+    // Billing months run from the 15th to the 14th
+    assert_eq!(results[2].journals_created, 12);
+
+    let results = execute_script(&exec, &mut ctx, "
+        GET balance(@subscription_revenue, 2024-02-13) AS before,
+            balance(@subscription_revenue, 2024-02-14) AS first,
+            balance(@subscription_revenue, 2025-01-14) AS total
+    ");
+    assert_eq!(results[0].variables["before"], DataValue::Money(rust_decimal::Decimal::ZERO));
+    assert_eq!(results[0].variables["first"], DataValue::Money(rust_decimal_macros::dec!(100)));
+    assert_eq!(results[0].variables["total"], DataValue::Money(rust_decimal_macros::dec!(1200)));
+}
+
+#[test]
+fn test_distribute_explicit_schedule() {
+    let (exec, mut ctx) = setup();
+    let results = execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @deferred_revenue LIABILITY;
+        CREATE ACCOUNT @project_revenue INCOME;
+        DISTRIBUTE 10000
+            ON [2024-03-31, 2024-06-30, 2024-09-30] WEIGHTS [20, 30, 50]
+            FOR Project='Apollo'
+            DESCRIPTION 'Milestone revenue'
+            DEBIT @deferred_revenue,
+            CREDIT @project_revenue;
+    ");
+    assert_eq!(results[2].journals_created, 3);
+
+    let results = execute_script(&exec, &mut ctx, "
+        GET balance(@project_revenue, 2024-03-31) AS design,
+            balance(@project_revenue, 2024-06-30) AS build,
+            balance(@project_revenue, 2024-09-30, Project='Apollo') AS delivery
+    ");
+    assert_eq!(results[0].variables["design"], DataValue::Money(rust_decimal_macros::dec!(2000)));
+    assert_eq!(results[0].variables["build"], DataValue::Money(rust_decimal_macros::dec!(5000)));
+    assert_eq!(results[0].variables["delivery"], DataValue::Money(rust_decimal_macros::dec!(10000)));
+
+    // Without weights the dates share the amount evenly
+    let results = execute_script(&exec, &mut ctx, "
+        DISTRIBUTE 100 ON [2024-10-31, 2024-11-30, 2024-12-31]
+            DESCRIPTION 'Even' DEBIT @deferred_revenue, CREDIT @project_revenue;
+        GET balance(@project_revenue, 2024-10-31) AS oct
+    ");
+    assert_eq!(results[0].journals_created, 3);
+    assert_eq!(results[1].variables["oct"], DataValue::Money(rust_decimal_macros::dec!(10033.33)));
+
+    for fql in [
+        "DISTRIBUTE 100 ON [2025-01-31, 2025-02-28] WEIGHTS [1] DESCRIPTION 'x' DEBIT @deferred_revenue, CREDIT @project_revenue",
+        "DISTRIBUTE 100 ON [2025-02-28, 2025-01-31] DESCRIPTION 'x' DEBIT @deferred_revenue, CREDIT @project_revenue",
+        "DISTRIBUTE 100 ON [2025-01-31, 2025-02-28] WEIGHTS [1, 0] DESCRIPTION 'x' DEBIT @deferred_revenue, CREDIT @project_revenue",
+    ] {
+        let stmts = dblentry::lexer::parse(fql).unwrap();
+        assert!(exec.execute(&mut ctx, &stmts[0]).is_err(), "{}", fql);
+    }
+}
+
 #[test]
 fn test_distribute_zero_amount_error() {
     let (exec, mut ctx) = setup();
@@ -3287,6 +3424,1008 @@ backend_test!(cross_hierarchical_lots, |exec: &StatementExecutor, ctx: &mut Exec
     assert_eq!(results[0].variables["u"], DataValue::Money(100.into()));
 });
 
+backend_test!(cross_multi_dimension_filters, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE RATE AAPL;
+        SET RATE AAPL 150 2024-01-01;
+        CREATE ACCOUNT @receivables ASSET;
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE ACCOUNT @aapl ASSET UNITS 'AAPL';
+        CREATE ACCOUNT @bank ASSET;
+        CREATE JOURNAL 2024-01-10, 1000, 'Acme US West' FOR Customer='Acme', Region='US/West' DEBIT @receivables, CREDIT @revenue;
+        CREATE JOURNAL 2024-01-12, 300, 'Acme EU' FOR Customer='Acme', Region='EU' DEBIT @receivables, CREDIT @revenue;
+        CREATE JOURNAL 2024-01-15, 200, 'Beta US East' FOR Customer='Beta', Region='US/East' DEBIT @receivables, CREDIT @revenue;
+        CREATE JOURNAL 2024-01-20, 1500, 'Acme US lots' FOR Customer='Acme', Region='US/East' DEBIT @aapl 10 UNITS AT 150, CREDIT @bank;
+        CREATE JOURNAL 2024-01-21, 300, 'Beta US lots' FOR Customer='Beta', Region='US/East' DEBIT @aapl 2 UNITS AT 150, CREDIT @bank;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET balance(@receivables, 2024-12-31, Customer='Acme', Region='US') AS acme_us,
+            balance(@receivables, 2024-12-31, Customer='Acme') AS acme,
+            balance(@receivables, 2024-12-31, Customer='Beta', Region='EU') AS beta_eu,
+            units(@aapl, 2024-12-31, Customer='Acme', Region='US') AS acme_units,
+            statement(@receivables, 2024-01-01, 2024-12-31, Region='US', Customer='Acme') AS stmt,
+            journals(2024-01-01, 2024-12-31, Customer='Beta', Region='US/East') AS beta_journals
+    ");
+    assert_eq!(results[0].variables["acme_us"], DataValue::Money(1000.into()));
+    assert_eq!(results[0].variables["acme"], DataValue::Money(1300.into()));
+    assert_eq!(results[0].variables["beta_eu"], DataValue::Money(0.into()));
+    assert_eq!(results[0].variables["acme_units"], DataValue::Money(10.into()));
+    match &results[0].variables["stmt"] {
+        DataValue::Statement(txns) => {
+            assert_eq!(txns.len(), 1);
+            assert_eq!(txns[0].description.as_ref(), "Acme US West");
+        }
+        other => panic!("expected Statement, got {:?}", other),
+    }
+    match &results[0].variables["beta_journals"] {
+        DataValue::Journals(journals) => {
+            let descriptions: Vec<&str> = journals.iter().map(|j| j.description.as_ref()).collect();
+            assert_eq!(descriptions, vec!["Beta US East", "Beta US lots"]);
+        }
+        other => panic!("expected Journals, got {:?}", other),
+    }
+});
+
+backend_test!(balance_by_and_activity_by_group_per_dimension_value, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @receivables ASSET;
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE ACCOUNT @bank ASSET;
+        CREATE JOURNAL 2024-01-10, 1000, 'Acme invoice' FOR Customer='Acme' DEBIT @receivables, CREDIT @revenue;
+        CREATE JOURNAL 2024-01-20, 400, 'Beta invoice' FOR Customer='Beta' DEBIT @receivables, CREDIT @revenue;
+        CREATE JOURNAL 2024-02-05, 250, 'Acme payment' FOR Customer='Acme' DEBIT @bank, CREDIT @receivables;
+        CREATE JOURNAL 2024-02-06, 75, 'Untagged' DEBIT @receivables, CREDIT @revenue;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET balance_by(@receivables, 2024-12-31, Customer) AS by_customer,
+            activity_by(@receivables, 2024-02-01, 2024-02-28, 'Customer') AS february
+    ");
+    let as_pairs = |value: &DataValue| match value {
+        DataValue::DimensionBalances(items) => items.iter()
+            .map(|item| (format_data_value(&item.value), item.amount))
+            .collect::<Vec<_>>(),
+        other => panic!("expected DimensionBalances, got {:?}", other),
+    };
+    assert_eq!(as_pairs(&results[0].variables["by_customer"]), vec![
+        ("Acme".to_string(), rust_decimal::Decimal::from(750)),
+        ("Beta".to_string(), rust_decimal::Decimal::from(400)),
+    ]);
+    assert_eq!(as_pairs(&results[0].variables["february"]), vec![
+        ("Acme".to_string(), rust_decimal::Decimal::from(-250)),
+    ]);
+});
+
+backend_test!(account_hierarchy_rolls_up_children, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @cash ASSET;
+        CREATE ACCOUNT @checking ASSET UNDER @cash;
+        CREATE ACCOUNT @savings ASSET UNDER @cash;
+        CREATE ACCOUNT @equity EQUITY;
+        CREATE JOURNAL 2024-01-01, 1000, 'Fund checking' DEBIT @checking, CREDIT @equity;
+        CREATE JOURNAL 2024-01-02, 500, 'Fund savings' DEBIT @savings, CREDIT @equity;
+        CREATE JOURNAL 2024-02-01, 200, 'Move to savings' DEBIT @savings, CREDIT @checking;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET balance(@cash, 2024-12-31) AS cash,
+            balance(@checking, 2024-12-31) AS checking,
+            statement(@cash, 2024-01-01, 2024-12-31) AS stmt,
+            trial_balance(2024-12-31) AS flat,
+            trial_balance(2024-12-31, tree) AS tree
+    ");
+    let vars = &results[0].variables;
+    assert_eq!(vars["cash"], DataValue::Money(1500.into()));
+    assert_eq!(vars["checking"], DataValue::Money(800.into()));
+    match &vars["stmt"] {
+        DataValue::Statement(txns) => assert_eq!(txns.len(), 4),
+        other => panic!("expected Statement, got {:?}", other),
+    }
+    let rows = |value: &DataValue| match value {
+        DataValue::TrialBalance(items) => items.iter()
+            .map(|item| (item.account_id.to_string(), item.depth, item.subtotal))
+            .collect::<Vec<_>>(),
+        other => panic!("expected TrialBalance, got {:?}", other),
+    };
+    assert_eq!(rows(&vars["flat"]), vec![
+        ("checking".to_string(), 0, false),
+        ("savings".to_string(), 0, false),
+        ("equity".to_string(), 0, false),
+    ]);
+    assert_eq!(rows(&vars["tree"]), vec![
+        ("cash".to_string(), 0, true),
+        ("checking".to_string(), 1, false),
+        ("savings".to_string(), 1, false),
+        ("equity".to_string(), 0, false),
+    ]);
+
+    for script in [
+        "CREATE JOURNAL 2024-03-01, 50, 'Parent posting' DEBIT @cash, CREDIT @equity",
+        "CREATE ACCOUNT @loan LIABILITY UNDER @cash",
+        "CREATE ACCOUNT @petty ASSET UNDER @missing",
+        "CREATE ACCOUNT @capital EQUITY UNDER @equity",
+    ] {
+        let statements = lexer::parse(script).unwrap();
+        assert!(exec.execute_script(ctx, &statements).is_err(), "{} should fail", script);
+    }
+});
+
+backend_test!(period_lock_rejects_backdated_changes, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE RATE prime;
+        SET RATE prime 0.05 2024-01-01;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @interest INCOME;
+        CREATE JOURNAL 2024-01-01, 1000, 'Loan' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        LOCK PERIOD THROUGH 2024-01-31;
+    ");
+
+    let is_locked = |script: &str, ctx: &mut ExecutionContext| {
+        let statements = lexer::parse(script).unwrap();
+        matches!(
+            exec.execute_script(ctx, &statements),
+            Err(EvaluationError::StorageError(StorageError::PeriodLocked(_)))
+        )
+    };
+    assert!(is_locked("CREATE JOURNAL 2024-01-31, 10, 'Backdated' DEBIT @bank, CREDIT @interest", ctx));
+    assert!(is_locked("SET RATE prime 0.06 2024-01-15", ctx));
+    assert!(is_locked("LOCK PERIOD THROUGH 2023-12-31", ctx));
+    // The first generated journal falls inside the lock, so nothing is posted
+    assert!(is_locked("DISTRIBUTE 300 FROM 2024-01-01 TO 2024-03-31 PERIOD MONTHLY DESCRIPTION 'Fee' DEBIT @bank, CREDIT @interest", ctx));
+    assert!(is_locked("ACCRUE @loans FROM 2024-01-01 TO 2024-01-31 WITH RATE prime BY Customer INTO JOURNAL 2024-01-31, 'Interest' DEBIT @loans, CREDIT @interest", ctx));
+
+    let results = execute_script(exec, ctx, "
+        CREATE JOURNAL 2024-02-01, 10, 'Open period' DEBIT @bank, CREDIT @interest;
+        GET balance(@interest, 2024-12-31) AS interest;
+        UNLOCK;
+        CREATE JOURNAL 2024-01-15, 5, 'Reopened' DEBIT @bank, CREDIT @interest;
+        GET balance(@interest, 2024-12-31) AS reopened
+    ");
+    assert_eq!(results[1].variables["interest"], DataValue::Money(10.into()));
+    assert_eq!(results[4].variables["reopened"], DataValue::Money(15.into()));
+});
+
+backend_test!(close_year_moves_net_income_to_equity, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @sales INCOME;
+        CREATE ACCOUNT @rent EXPENSE;
+        CREATE ACCOUNT @retained EQUITY;
+        CREATE JOURNAL 2024-03-01, 1000, 'EU sale' FOR Region='EU' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-04-01, 600, 'US sale' FOR Region='US' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-05-01, 400, 'Rent' DEBIT @rent, CREDIT @bank;
+    ");
+    let results = execute_script(exec, ctx, "CLOSE YEAR ENDING 2024-12-31 INTO @retained BY Region");
+    assert_eq!(results[0].journals_created, 3);
+    let results = execute_script(exec, ctx, "CLOSE YEAR ENDING 2024-12-31 INTO @retained BY Region");
+    assert_eq!(results[0].journals_created, 0, "closing twice must not post again");
+
+    let statements = lexer::parse("CREATE JOURNAL 2024-06-01, 50, 'Late sale' DEBIT @bank, CREDIT @sales").unwrap();
+    assert!(matches!(exec.execute_script(ctx, &statements), Err(EvaluationError::StorageError(StorageError::PeriodLocked(_)))));
+
+    execute_script(exec, ctx, "CREATE JOURNAL 2025-02-01, 300, 'New year sale' DEBIT @bank, CREDIT @sales");
+    let results = execute_script(exec, ctx, "
+        GET balance(@sales, 2024-12-31) AS sales,
+            balance(@rent, 2024-12-31) AS rent,
+            balance(@retained, 2024-12-31) AS retained,
+            balance_by(@retained, 2024-12-31, Region) AS by_region,
+            income_statement(2023-12-31, 2024-12-31) AS fy2024,
+            income_statement(2024-12-31, 2025-12-31) AS fy2025
+    ");
+    let vars = &results[0].variables;
+    assert_eq!(vars["sales"], DataValue::Money(rust_decimal::Decimal::ZERO));
+    assert_eq!(vars["rent"], DataValue::Money(rust_decimal::Decimal::ZERO));
+    assert_eq!(vars["retained"], DataValue::Money(1200.into()));
+    match &vars["by_region"] {
+        DataValue::DimensionBalances(items) => {
+            let amounts: Vec<_> = items.iter().map(|item| (format_data_value(&item.value), item.amount)).collect();
+            assert_eq!(amounts, vec![("EU".to_string(), 1000.into()), ("US".to_string(), 600.into())]);
+        }
+        other => panic!("expected DimensionBalances, got {:?}", other),
+    }
+    let net_income = |value: &DataValue| match value {
+        DataValue::TrialBalance(items) => items.iter().find(|item| &*item.account_id == "NET_INCOME").unwrap().balance,
+        other => panic!("expected TrialBalance, got {:?}", other),
+    };
+    assert_eq!(net_income(&vars["fy2024"]), 1200.into());
+    assert_eq!(net_income(&vars["fy2025"]), 300.into());
+});
+
+backend_test!(balance_sheet_classifies_and_balances, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @cash ASSET CURRENT;
+        CREATE ACCOUNT @fixed ASSET NONCURRENT;
+        CREATE ACCOUNT @buildings ASSET UNDER @fixed;
+        CREATE ACCOUNT @payables LIABILITY CURRENT;
+        CREATE ACCOUNT @mortgage LIABILITY NONCURRENT;
+        CREATE ACCOUNT @capital EQUITY;
+        CREATE ACCOUNT @sales INCOME;
+        CREATE ACCOUNT @rent EXPENSE;
+        CREATE JOURNAL 2024-01-01, 5000, 'Capital' DEBIT @cash, CREDIT @capital;
+        CREATE JOURNAL 2024-01-02, 8000, 'Building' DEBIT @buildings, CREDIT @mortgage;
+        CREATE JOURNAL 2024-02-01, 1200, 'Sale' FOR Region='EU' DEBIT @cash, CREDIT @sales;
+        CREATE JOURNAL 2024-02-15, 300, 'Rent' DEBIT @rent, CREDIT @payables;
+    ");
+    let statements = lexer::parse("CREATE ACCOUNT @retained EQUITY CURRENT").unwrap();
+    assert!(exec.execute_script(ctx, &statements).is_err(), "only assets and liabilities are classified");
+
+    let results = execute_script(exec, ctx, "
+        GET balance_sheet(2024-12-31) AS bs,
+            balance_sheet(2024-12-31, Region='EU') AS eu
+    ");
+    let vars = &results[0].variables;
+    let sheet = match &vars["bs"] {
+        DataValue::BalanceSheet(sheet) => sheet,
+        other => panic!("expected BalanceSheet, got {:?}", other),
+    };
+    let sections: Vec<_> = sheet.sections.iter()
+        .map(|s| (s.account_type.clone(), s.class, s.subtotal))
+        .collect();
+    assert_eq!(sections, vec![
+        (AccountType::Asset, Some(AccountClass::Current), 6200.into()),
+        (AccountType::Asset, Some(AccountClass::NonCurrent), 8000.into()),
+        (AccountType::Liability, Some(AccountClass::Current), 300.into()),
+        (AccountType::Liability, Some(AccountClass::NonCurrent), 8000.into()),
+        (AccountType::Equity, None, 5900.into()),
+    ]);
+    assert_eq!(&*sheet.sections[1].lines[0].account_id, "buildings", "children inherit the parent's class");
+    let net_income = sheet.sections[4].lines.iter().find(|l| &*l.account_id == "NET_INCOME").unwrap();
+    assert_eq!(net_income.balance, 900.into());
+    assert_eq!(sheet.total_assets, 14200.into());
+    assert!(sheet.balanced);
+
+    match &vars["eu"] {
+        DataValue::BalanceSheet(sheet) => {
+            assert_eq!(sheet.total_assets, 1200.into());
+            assert_eq!(sheet.total_equity, 1200.into());
+            assert!(sheet.balanced);
+        }
+        other => panic!("expected BalanceSheet, got {:?}", other),
+    }
+    assert!(format_data_value(&vars["bs"]).contains("Non-current assets"));
+});
+
+backend_test!(cash_flow_statement_reconciles_to_cash, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET CASHFLOW CASH;
+        CREATE ACCOUNT @receivables ASSET;
+        CREATE ACCOUNT @equipment ASSET NONCURRENT CASHFLOW INVESTING;
+        CREATE ACCOUNT @loan LIABILITY CASHFLOW FINANCING;
+        CREATE ACCOUNT @capital EQUITY;
+        CREATE ACCOUNT @retained EQUITY;
+        CREATE ACCOUNT @sales INCOME;
+        CREATE ACCOUNT @rent EXPENSE;
+        CREATE JOURNAL 2023-12-01, 1000, 'Capital' DEBIT @bank, CREDIT @capital;
+        CREATE JOURNAL 2024-01-10, 500, 'Sale on credit' DEBIT @receivables, CREDIT @sales;
+        CREATE JOURNAL 2024-02-01, 300, 'Collection' DEBIT @bank, CREDIT @receivables;
+        CREATE JOURNAL 2024-03-01, 100, 'Rent' DEBIT @rent, CREDIT @bank;
+        CREATE JOURNAL 2024-04-01, 700, 'Equipment' DEBIT @equipment, CREDIT @bank;
+        CREATE JOURNAL 2024-05-01, 2000, 'Loan' DEBIT @bank, CREDIT @loan;
+        CLOSE YEAR ENDING 2024-12-31 INTO @retained;
+    ");
+    let statements = lexer::parse("CREATE ACCOUNT @fees INCOME CASHFLOW OPERATING").unwrap();
+    assert!(exec.execute_script(ctx, &statements).is_err(), "income flows through net income");
+
+    let results = execute_script(exec, ctx, "GET cash_flow_statement(2023-12-31, 2024-12-31) AS cf");
+    let statement = match &results[0].variables["cf"] {
+        DataValue::CashFlowStatement(statement) => statement,
+        other => panic!("expected CashFlowStatement, got {:?}", other),
+    };
+    assert_eq!(statement.net_income, 400.into(), "the year-end close does not hide net income");
+    let lines = |i: usize| -> Vec<(String, rust_decimal::Decimal)> {
+        statement.sections[i].lines.iter().map(|l| (l.account_id.to_string(), l.amount)).collect()
+    };
+    assert_eq!(lines(0), vec![("NET_INCOME".to_string(), 400.into()), ("receivables".to_string(), (-200).into())]);
+    assert_eq!(lines(1), vec![("equipment".to_string(), (-700).into())]);
+    assert_eq!(lines(2), vec![("loan".to_string(), 2000.into())]);
+    assert_eq!(statement.net_change, 1500.into());
+    assert_eq!(statement.opening_cash, 1000.into());
+    assert_eq!(statement.closing_cash, 2500.into());
+    assert!(statement.reconciled);
+    assert!(format_data_value(&results[0].variables["cf"]).contains("Net cash from investing activities"));
+});
+
+backend_test!(turnover_splits_debits_and_credits, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @sales INCOME;
+        CREATE ACCOUNT @rent EXPENSE;
+        CREATE JOURNAL 2024-01-05, 1000, 'EU sale' FOR Region='EU' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-01-10, 400, 'US sale' FOR Region='US' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-01-20, 1800, 'Rent' DEBIT @rent, CREDIT @bank;
+        CREATE JOURNAL 2024-02-01, 100, 'Late sale' DEBIT @bank, CREDIT @sales;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET debits(@bank, 2024-01-01, 2024-01-31) AS bank_dr,
+            credits(@bank, 2024-01-01, 2024-01-31) AS bank_cr,
+            activity(@bank, 2024-01-01, 2024-01-31) AS bank_net,
+            debits(@bank, 2024-01-01, 2024-01-31, Region='EU') AS eu_dr,
+            debits(@sales, 2024-01-01, 2024-12-31) AS sales_dr,
+            credits(@sales, 2024-01-01, 2024-12-31) AS sales_cr,
+            activity(@sales, 2024-01-06, 2024-02-01) AS sales_net,
+            trial_balance(2024-01-31) AS tb
+    ");
+    let vars = &results[0].variables;
+    assert_eq!(vars["bank_dr"], DataValue::Money(1400.into()));
+    assert_eq!(vars["bank_cr"], DataValue::Money(1800.into()));
+    assert_eq!(vars["bank_net"], DataValue::Money((-400).into()));
+    assert_eq!(vars["eu_dr"], DataValue::Money(1000.into()));
+    assert_eq!(vars["sales_dr"], DataValue::Money(rust_decimal::Decimal::ZERO));
+    assert_eq!(vars["sales_cr"], DataValue::Money(1500.into()));
+    assert_eq!(vars["sales_net"], DataValue::Money(500.into()));
+
+    // The overdrawn bank account lands in the credit column and the columns agree
+    let items = match &vars["tb"] {
+        DataValue::TrialBalance(items) => items,
+        other => panic!("expected TrialBalance, got {:?}", other),
+    };
+    let bank = items.iter().find(|item| &*item.account_id == "bank").unwrap();
+    assert_eq!(bank.debit_credit(), (None, Some(400.into())));
+    let (debits, credits) = items.iter().map(|item| item.debit_credit()).fold(
+        (rust_decimal::Decimal::ZERO, rust_decimal::Decimal::ZERO),
+        |(d, c), (dr, cr)| (d + dr.unwrap_or_default(), c + cr.unwrap_or_default()),
+    );
+    assert_eq!(debits, credits);
+});
+
+backend_test!(turnover_counts_each_leg_on_its_side, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @fees EXPENSE;
+        CREATE ACCOUNT @sales INCOME;
+        CREATE JOURNAL 2024-01-05, 100, 'Sale less refund' FOR Region='EU' DEBIT @bank 100, CREDIT @bank 30, CREDIT @sales 70;
+        CREATE JOURNAL 2024-01-10, 90, 'Sale net of fee rebate' FOR Region='EU' DEBIT @bank 100, DEBIT @fees -10, CREDIT @sales 90;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET debits(@bank, 2024-01-01, 2024-01-31, Region='EU') AS bank_dr,
+            credits(@bank, 2024-01-01, 2024-01-31, Region='EU') AS bank_cr,
+            debits(@fees, 2024-01-01, 2024-01-31) AS fees_dr,
+            credits(@fees, 2024-01-01, 2024-01-31, Region='EU') AS fees_cr
+    ");
+    let vars = &results[0].variables;
+    // Both legs on @bank count, rather than their net
+    assert_eq!(vars["bank_dr"], DataValue::Money(200.into()));
+    assert_eq!(vars["bank_cr"], DataValue::Money(30.into()));
+    // A negative debit stays on the debit side
+    assert_eq!(vars["fees_dr"], DataValue::Money((-10).into()));
+    assert_eq!(vars["fees_cr"], DataValue::Money(rust_decimal::Decimal::ZERO));
+});
+
+backend_test!(balance_series_returns_period_end_balances, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @sales INCOME;
+        CREATE JOURNAL 2023-12-15, 50, 'Opening' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-01-10, 100, 'Jan' FOR Region='EU' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-03-05, 30, 'Mar' FOR Region='US' DEBIT @bank, CREDIT @sales;
+        CREATE JOURNAL 2024-03-31, 20, 'Quarter end' FOR Region='EU' DEBIT @bank, CREDIT @sales;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET balance_series(@bank, 2024-01-01, 2024-03-15, MONTHLY) AS monthly,
+            balance_series(@bank, 2024-01-01, 2024-06-30, quarterly, Region='EU') AS eu
+    ");
+    let series = |name: &str| match &results[0].variables[name] {
+        DataValue::BalanceSeries(items) => items.iter()
+            .map(|item| (item.period_end.to_string(), item.balance, item.change))
+            .collect::<Vec<_>>(),
+        other => panic!("expected BalanceSeries, got {:?}", other),
+    };
+    assert_eq!(series("monthly"), vec![
+        ("2024-01-31".to_string(), 150.into(), 100.into()),
+        ("2024-02-29".to_string(), 150.into(), rust_decimal::Decimal::ZERO),
+        ("2024-03-15".to_string(), 180.into(), 30.into()),
+    ]);
+    assert_eq!(series("eu"), vec![
+        ("2024-03-31".to_string(), 120.into(), 120.into()),
+        ("2024-06-30".to_string(), 120.into(), rust_decimal::Decimal::ZERO),
+    ]);
+
+    let statements = lexer::parse("GET balance_series(@bank, 2024-01-01, 2024-03-31, WEEKLY) AS w").unwrap();
+    assert!(exec.execute_script(ctx, &statements).is_err());
+});
+
+backend_test!(accrue_on_average_balance, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+        CREATE RATE prime;
+        SET RATE prime 0.0365 2024-01-01;
+        CREATE JOURNAL 2024-01-01, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-16, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+    ");
+    // 15 days at 1000 and 16 days at 2000
+    let results = execute_script(exec, ctx, "
+        GET round(average_balance(@loans, 2024-01-01, 2024-01-31, Customer='Acme'), 2) AS adb,
+            average_balance(@loans, 2024-01-16, 2024-01-31) AS second_half
+    ");
+    assert_eq!(results[0].variables["adb"], DataValue::Money(rust_decimal::Decimal::new(151613, 2)));
+    assert_eq!(results[0].variables["second_half"], DataValue::Money(2000.into()));
+
+    execute_script(exec, ctx, "
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-31 WITH RATE prime ON AVERAGE BALANCE BY Customer
+        INTO JOURNAL 2024-01-31, 'January interest'
+        DEBIT @interest_receivable, CREDIT @interest_income
+    ");
+    let results = execute_script(exec, ctx, "GET balance(@interest_income, 2024-01-31) AS interest");
+    // 47000 / 31 × 0.0365 × 31 / 365
+    assert_eq!(results[0].variables["interest"], DataValue::Money(rust_decimal::Decimal::new(470, 2)));
+});
+
+backend_test!(accrue_day_count_and_compounding, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loan_a ASSET;
+        CREATE ACCOUNT @loan_b ASSET;
+        CREATE ACCOUNT @loan_c ASSET;
+        CREATE ACCOUNT @loan_d ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @income_a INCOME;
+        CREATE ACCOUNT @income_b INCOME;
+        CREATE ACCOUNT @income_c INCOME;
+        CREATE ACCOUNT @income_d INCOME;
+        CREATE RATE five;
+        SET RATE five 0.05 2023-01-01;
+        CREATE RATE low;
+        SET RATE low 0.0365 2023-01-01;
+        CREATE JOURNAL 2023-01-01, 36000, 'Drawdown' FOR Customer='Acme' DEBIT @loan_a, CREDIT @bank;
+        CREATE JOURNAL 2023-01-01, 36000, 'Drawdown' FOR Customer='Acme' DEBIT @loan_b, CREDIT @bank;
+        CREATE JOURNAL 2024-01-01, 36500, 'Drawdown' FOR Customer='Acme' DEBIT @loan_c, CREDIT @bank;
+        CREATE JOURNAL 2023-01-01, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loan_d, CREDIT @bank;
+
+        ACCRUE @loan_a FROM 2023-01-01 TO 2023-01-31 WITH RATE five COMPOUND SIMPLE DAYCOUNT ACT/360 BY Customer
+        INTO JOURNAL 2023-01-31, 'Interest' DEBIT @interest_receivable, CREDIT @income_a;
+        ACCRUE @loan_b FROM 2023-01-01 TO 2023-01-31 WITH RATE five COMPOUND SIMPLE DAYCOUNT 30/360 BY Customer
+        INTO JOURNAL 2023-01-31, 'Interest' DEBIT @interest_receivable, CREDIT @income_b;
+        ACCRUE @loan_c FROM 2024-01-01 TO 2024-02-29 WITH RATE low COMPOUND MONTHLY BY Customer
+        INTO JOURNAL 2024-02-29, 'Interest' DEBIT @interest_receivable, CREDIT @income_c;
+        ACCRUE @loan_d FROM 2023-01-01 TO 2023-12-31 WITH RATE five COMPOUND CONTINUOUS BY Customer
+        INTO JOURNAL 2023-12-31, 'Interest' DEBIT @interest_receivable, CREDIT @income_d;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET balance(@income_a, 2024-12-31) AS act_360,
+            balance(@income_b, 2024-12-31) AS thirty_360,
+            balance(@income_c, 2024-12-31) AS monthly,
+            balance(@income_d, 2024-12-31) AS continuous
+    ");
+    // 36000 × 0.05 × 31 / 360
+    assert_eq!(results[0].variables["act_360"], DataValue::Money(rust_decimal::Decimal::new(15500, 2)));
+    // The 31st adds nothing, so January is 30 days: 36000 × 0.05 × 30 / 360
+    assert_eq!(results[0].variables["thirty_360"], DataValue::Money(rust_decimal::Decimal::new(15000, 2)));
+    // January's 113.15 is capitalized before February accrues on 36613.15
+    assert_eq!(results[0].variables["monthly"], DataValue::Money(rust_decimal::Decimal::new(21933, 2)));
+    // 1000 × (e^0.05 − 1)
+    assert_eq!(results[0].variables["continuous"], DataValue::Money(rust_decimal::Decimal::new(5127, 2)));
+
+    // a day count means nothing for a daily rate; e^(rate × yf) overflows
+    for fql in [
+        "ACCRUE @loan_a FROM 2024-01-01 TO 2024-01-31 WITH RATE five DAYCOUNT ACT/360 BY Customer INTO JOURNAL 2024-01-31, 'Interest' DEBIT @interest_receivable, CREDIT @income_a",
+        "ACCRUE @loan_d FROM 2023-01-01 TO 2023-01-31 WITH RATE 1000000 COMPOUND CONTINUOUS BY Customer INTO JOURNAL 2023-01-31, 'Interest' DEBIT @interest_receivable, CREDIT @income_d",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{} should be rejected", fql);
+    }
+});
+
+backend_test!(accrue_without_by_and_by_several_dimensions, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @deposits LIABILITY;
+        CREATE ACCOUNT @interest_expense EXPENSE;
+        CREATE ACCOUNT @interest_payable LIABILITY;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+        CREATE RATE prime;
+        SET RATE prime 0.365 2024-01-01;
+        CREATE JOURNAL 2023-12-31, 1000, 'Deposit' DEBIT @bank, CREDIT @deposits;
+        CREATE JOURNAL 2024-01-01, 1000, 'Drawdown' FOR Customer='Acme', Facility='A' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-01, 2000, 'Drawdown' FOR Customer='Acme', Facility='B' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-01, 3000, 'Drawdown' FOR Customer='Beta', Facility='A' DEBIT @loans, CREDIT @bank;
+    ");
+
+    // No BY: the whole balance accrues, even without postings in the period
+    let results = execute_script(exec, ctx, "
+        ACCRUE @deposits FROM 2024-01-01 TO 2024-01-10 WITH RATE prime COMPOUND SIMPLE
+        INTO JOURNAL 2024-01-10, 'Deposit interest'
+        DEBIT @interest_expense, CREDIT @interest_payable
+    ");
+    assert_eq!(results[0].journals_created, 1);
+
+    let results = execute_script(exec, ctx, "
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-10 WITH RATE prime COMPOUND SIMPLE BY Customer, Facility
+        INTO JOURNAL 2024-01-10, 'Loan interest'
+        DEBIT @interest_receivable, CREDIT @interest_income
+    ");
+    assert_eq!(results[0].journals_created, 3);
+
+    let results = execute_script(exec, ctx, "
+        GET balance(@interest_payable, 2024-01-10) AS deposit_interest,
+            balance(@interest_income, 2024-01-10, Customer='Acme', Facility='B') AS acme_b,
+            balance(@interest_income, 2024-01-10, Customer='Acme') AS acme,
+            balance(@interest_income, 2024-01-10) AS total
+    ");
+    assert_eq!(results[0].variables["deposit_interest"], DataValue::Money(rust_decimal::Decimal::new(1000, 2)));
+    assert_eq!(results[0].variables["acme_b"], DataValue::Money(rust_decimal::Decimal::new(2000, 2)));
+    assert_eq!(results[0].variables["acme"], DataValue::Money(rust_decimal::Decimal::new(3000, 2)));
+    assert_eq!(results[0].variables["total"], DataValue::Money(rust_decimal::Decimal::new(6000, 2)));
+});
+
+backend_test!(accrue_by_dimension_on_a_parent_account, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @term_loans ASSET UNDER @loans;
+        CREATE ACCOUNT @overdrafts ASSET UNDER @loans;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+        CREATE RATE prime;
+        SET RATE prime 36.5% 2024-01-01;
+        CREATE JOURNAL 2024-01-01, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @term_loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-01, 2000, 'Drawdown' FOR Customer='Beta' DEBIT @overdrafts, CREDIT @bank;
+    ");
+    // the combinations come from the child accounts' postings
+    let results = execute_script(exec, ctx, "
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-10 WITH RATE prime COMPOUND SIMPLE BY Customer
+        INTO JOURNAL 2024-01-10, 'Loan interest'
+        DEBIT @interest_receivable, CREDIT @interest_income;
+        GET balance(@interest_income, 2024-01-10, Customer='Acme') AS acme,
+            balance(@interest_income, 2024-01-10, Customer='Beta') AS beta
+    ");
+    assert_eq!(results[0].journals_created, 2);
+    assert_eq!(results[1].variables["acme"], DataValue::Money(rust_decimal::Decimal::new(1000, 2)));
+    assert_eq!(results[1].variables["beta"], DataValue::Money(rust_decimal::Decimal::new(2000, 2)));
+});
+
+backend_test!(accrue_opens_at_the_balance_before_the_period, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+        CREATE RATE prime;
+        SET RATE prime 36.5% 2023-01-01;
+        CREATE JOURNAL 2023-11-01, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2023-12-15, 500, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-01, 100, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-06, 600, 'Repayment' FOR Customer='Acme' DEBIT @bank, CREDIT @loans;
+        CREATE JOURNAL 2024-01-20, 5000, 'Later drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+    ");
+    // 1600 for five days, then 1000 for five days, at 0.1% a day
+    let results = execute_script(exec, ctx, "
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-10 WITH RATE prime COMPOUND SIMPLE BY Customer
+        INTO JOURNAL 2024-01-10, 'Loan interest'
+        DEBIT @interest_receivable, CREDIT @interest_income;
+        GET balance(@interest_income, 2024-01-10, Customer='Acme') AS acme
+    ");
+    assert_eq!(results[0].journals_created, 1);
+    assert_eq!(results[1].variables["acme"], DataValue::Money(rust_decimal::Decimal::new(1300, 2)));
+});
+
+backend_test!(accrue_follows_rate_changes_and_postings_within_period, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+        CREATE RATE prime;
+        SET RATE prime 0.365 2023-12-01;
+        SET RATE prime 0.73 2024-01-11;
+        CREATE JOURNAL 2023-12-15, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-06, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-13, 500, 'Repayment' FOR Customer='Acme' DEBIT @bank, CREDIT @loans;
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-15 WITH RATE prime COMPOUND SIMPLE BY Customer
+        INTO JOURNAL 2024-01-15, 'Interest' DEBIT @interest_receivable, CREDIT @interest_income;
+    ");
+    let results = execute_script(exec, ctx, "GET balance(@interest_income, 2024-01-15) AS interest");
+    // 5 days at 1000 and 5 at 2000 on 0.1% a day, then 2 at 2000 and 3 at 1500 on 0.2% a day: 5 + 10 + 8 + 9
+    assert_eq!(results[0].variables["interest"], DataValue::Money(rust_decimal::Decimal::new(3200, 2)));
+});
+
+backend_test!(accrue_with_rate_spread_and_per_dimension_rates, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loans ASSET;
+        CREATE ACCOUNT @interest_receivable ASSET;
+        CREATE ACCOUNT @interest_income INCOME;
+        CREATE ACCOUNT @fee_income INCOME;
+        CREATE RATE prime;
+        CREATE RATE margin;
+        SET RATE prime 36.5% 2024-01-01;
+        SET RATE margin 0 2024-01-01;
+        SET RATE margin 36.5% 2024-01-01 FOR Customer='Acme';
+        CREATE JOURNAL 2024-01-01, 1000, 'Drawdown' FOR Customer='Acme' DEBIT @loans, CREDIT @bank;
+        CREATE JOURNAL 2024-01-01, 1000, 'Drawdown' FOR Customer='Beta' DEBIT @loans, CREDIT @bank;
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-10 WITH RATE prime + margin COMPOUND SIMPLE BY Customer
+        INTO JOURNAL 2024-01-10, 'Interest' DEBIT @interest_receivable, CREDIT @interest_income;
+        ACCRUE @loans FROM 2024-01-01 TO 2024-01-10 WITH RATE prime + 36.5% COMPOUND SIMPLE
+        INTO JOURNAL 2024-01-10, 'Fees' DEBIT @interest_receivable, CREDIT @fee_income;
+    ");
+    let results = execute_script(exec, ctx, "
+        GET balance(@interest_income, 2024-01-10, Customer='Acme') AS acme,
+            balance(@interest_income, 2024-01-10, Customer='Beta') AS beta,
+            balance(@fee_income, 2024-01-10) AS fees,
+            2.5% AS spread
+    ");
+    // 10 days on 1000 at 0.2% a day for Acme's margin, 0.1% for Beta on prime alone
+    assert_eq!(results[0].variables["acme"], DataValue::Money(rust_decimal::Decimal::new(2000, 2)));
+    assert_eq!(results[0].variables["beta"], DataValue::Money(rust_decimal::Decimal::new(1000, 2)));
+    assert_eq!(results[0].variables["fees"], DataValue::Money(rust_decimal::Decimal::new(4000, 2)));
+    assert_eq!(results[0].variables["spread"], DataValue::Percentage(rust_decimal::Decimal::new(25, 3)));
+});
+
+backend_test!(amortize_level_payments_with_interest_and_principal_legs, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @loan LIABILITY;
+        CREATE ACCOUNT @interest_expense EXPENSE;
+        CREATE RATE loan_rate;
+        SET RATE loan_rate 12% 2024-01-01;
+        CREATE JOURNAL 2024-01-31, 1000, 'Drawdown' DEBIT @bank, CREDIT @loan;
+    ");
+    let results = execute_script(exec, ctx, "AMORTIZE 1000 AT RATE loan_rate OVER 3 MONTHLY FROM 2024-01-31 PREVIEW");
+    assert_eq!(results[0].journals_created, 0);
+    let DataValue::Amortization(schedule) = &results[0].variables["schedule"] else { panic!("expected a schedule") };
+    let rows: Vec<_> = schedule.iter().map(|item| (item.date.to_string(), item.payment.to_string(), item.interest.to_string(), item.principal.to_string(), item.balance.to_string())).collect();
+    // 1% a month; the last payment takes the rounding so the balance ends at zero
+    assert_eq!(rows, vec![
+        ("2024-02-29".to_string(), "340.02".to_string(), "10.00".to_string(), "330.02".to_string(), "669.98".to_string()),
+        ("2024-03-31".to_string(), "340.02".to_string(), "6.70".to_string(), "333.32".to_string(), "336.66".to_string()),
+        ("2024-04-30".to_string(), "340.03".to_string(), "3.37".to_string(), "336.66".to_string(), "0.00".to_string()),
+    ]);
+
+    let results = execute_script(exec, ctx, "
+        AMORTIZE 1000 AT RATE 12% OVER 3 MONTHLY FROM 2024-01-31
+        DESCRIPTION 'Loan payment'
+        DEBIT @loan principal, DEBIT @interest_expense interest, CREDIT @bank;
+        GET balance(@loan, 2024-03-31) AS owed, balance(@loan, 2024-04-30) AS repaid,
+            balance(@interest_expense, 2024-04-30) AS interest
+    ");
+    assert_eq!(results[0].journals_created, 3);
+    assert_eq!(results[1].variables["owed"], DataValue::Money(rust_decimal::Decimal::new(33666, 2)));
+    assert_eq!(results[1].variables["repaid"], DataValue::Money(rust_decimal::Decimal::ZERO));
+    assert_eq!(results[1].variables["interest"], DataValue::Money(rust_decimal::Decimal::new(2007, 2)));
+});
+
+backend_test!(amortize_rejects_schedules_that_overflow, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    // the payment overflows, the payment count is over the cap, or the dates run past year 9999
+    for fql in [
+        "AMORTIZE 1000 AT RATE 120% OVER 1000 MONTHLY FROM 2024-01-31 PREVIEW",
+        "AMORTIZE 1000 AT RATE 12% OVER 100000 YEARLY FROM 2024-01-31 PREVIEW",
+        "AMORTIZE 1000 AT RATE 12% OVER 10000000 DAILY FROM 2024-01-31 PREVIEW",
+        "AMORTIZE 1000 AT RATE 0% OVER 10000 YEARLY FROM 2024-01-31 PREVIEW",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{} should be rejected", fql);
+    }
+});
+
+backend_test!(depreciate_and_dispose_fixed_assets, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @equipment ASSET;
+        CREATE ACCOUNT @accumulated_depreciation ASSET;
+        CREATE ACCOUNT @depreciation_expense EXPENSE;
+        CREATE ACCOUNT @disposals INCOME;
+        CREATE JOURNAL 2024-01-15, 6000, 'Buy truck' DEBIT @equipment, CREDIT @bank;
+        CREATE ASSET truck IN SERVICE 2024-01-15 COST 6000 LIFE 5 YEARS
+            ACCOUNT @equipment ACCUMULATED @accumulated_depreciation EXPENSE @depreciation_expense;
+    ");
+    let results = execute_script(exec, ctx, "
+        DEPRECIATE THROUGH 2024-03-31;
+        DEPRECIATE truck THROUGH 2024-03-31;
+        GET balance(@depreciation_expense, 2024-03-31) AS expense
+    ");
+    // straight line: 1200 a year, 100 a month from the in-service month; posted months are skipped
+    assert_eq!(results[0].journals_created, 3);
+    assert_eq!(results[1].journals_created, 0);
+    assert_eq!(results[2].variables["expense"], DataValue::Money(300.into()));
+
+    // disposal catches up April to June, then clears cost 6000 against 600 accumulated
+    let results = execute_script(exec, ctx, "
+        DISPOSE ASSET truck AT 5000 ON 2024-07-15 PROCEEDS @bank GAIN_LOSS @disposals DESCRIPTION 'Sell truck';
+        GET balance(@equipment, 2024-07-31) AS cost, balance(@accumulated_depreciation, 2024-07-31) AS accumulated,
+            balance(@disposals, 2024-07-31) AS loss, depreciation_schedule(truck) AS schedule
+    ");
+    assert_eq!(results[0].journals_created, 4);
+    assert_eq!(results[1].variables["cost"], DataValue::Money(0.into()));
+    assert_eq!(results[1].variables["accumulated"], DataValue::Money(0.into()));
+    assert_eq!(results[1].variables["loss"], DataValue::Money((-400).into()));
+    let DataValue::DepreciationSchedule(schedule) = &results[1].variables["schedule"] else { panic!("expected a schedule") };
+    assert_eq!(schedule.len(), 6);
+    assert_eq!(schedule[5].book_value, rust_decimal::Decimal::from(5400));
+
+    assert!(exec.execute(ctx, &lexer::parse("DEPRECIATE truck THROUGH 2024-12-31").unwrap()[0]).is_ok());
+    assert!(exec.execute(ctx, &lexer::parse("DISPOSE ASSET truck AT 1 ON 2024-08-01 PROCEEDS @bank GAIN_LOSS @disposals DESCRIPTION 'again'").unwrap()[0]).is_err());
+});
+
+backend_test!(depreciation_schedule_by_method, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @equipment ASSET;
+        CREATE ACCOUNT @accumulated_depreciation ASSET;
+        CREATE ACCOUNT @depreciation_expense EXPENSE;
+        CREATE ASSET press IN SERVICE 2024-01-01 COST 1600 LIFE 3 YEARS SALVAGE 100 METHOD SUM_OF_YEARS
+            ACCOUNT @equipment ACCUMULATED @accumulated_depreciation EXPENSE @depreciation_expense;
+        CREATE ASSET lathe IN SERVICE 2024-01-01 COST 1000 LIFE 4 YEARS METHOD DECLINING_BALANCE
+            ACCOUNT @equipment ACCUMULATED @accumulated_depreciation EXPENSE @depreciation_expense;
+    ");
+    let results = execute_script(exec, ctx, "GET depreciation_schedule(press) AS press, depreciation_schedule(lathe) AS lathe");
+    let year_ends = |name: &str| {
+        let DataValue::DepreciationSchedule(schedule) = &results[0].variables[name] else { panic!("expected a schedule") };
+        schedule.iter().skip(11).step_by(12).map(|item| (item.period_end.to_string(), item.accumulated.to_string())).collect::<Vec<_>>()
+    };
+    // sum of years: 3/6, 2/6 and 1/6 of the 1500 depreciable amount
+    assert_eq!(year_ends("press"), vec![
+        ("2024-12-31".to_string(), "750.00".to_string()),
+        ("2025-12-31".to_string(), "1250.00".to_string()),
+        ("2026-12-31".to_string(), "1500.00".to_string()),
+    ]);
+    // double declining: 500, 250, then straight line takes over for the last two years
+    assert_eq!(year_ends("lathe"), vec![
+        ("2024-12-31".to_string(), "500.00".to_string()),
+        ("2025-12-31".to_string(), "750.00".to_string()),
+        ("2026-12-31".to_string(), "875.00".to_string()),
+        ("2027-12-31".to_string(), "1000.00".to_string()),
+    ]);
+
+    for fql in [
+        "CREATE ASSET mill IN SERVICE 2024-01-01 COST 1000 LIFE 0 YEARS ACCOUNT @equipment ACCUMULATED @accumulated_depreciation EXPENSE @depreciation_expense",
+        "CREATE ASSET mill IN SERVICE 2024-01-01 COST 1000 LIFE 101 YEARS ACCOUNT @equipment ACCUMULATED @accumulated_depreciation EXPENSE @depreciation_expense",
+        "CREATE ASSET mill IN SERVICE 2024-01-01 COST 1000 LIFE 4000000000 YEARS ACCOUNT @equipment ACCUMULATED @accumulated_depreciation EXPENSE @depreciation_expense",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{} should be rejected", fql);
+    }
+});
+
+backend_test!(allocate_across_dimension_by_weights_and_driver, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @receivables ASSET;
+        CREATE ACCOUNT @rent EXPENSE;
+        CREATE ACCOUNT @allocated_rent EXPENSE;
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE JOURNAL 2024-01-05, 1000, 'January rent' DEBIT @rent, CREDIT @bank;
+        CREATE JOURNAL 2024-01-10, 600, 'Sales revenue' FOR Department='Sales' DEBIT @receivables, CREDIT @revenue;
+        CREATE JOURNAL 2024-01-12, 400, 'Ops revenue' FOR Department='Ops' DEBIT @receivables, CREDIT @revenue;
+    ");
+
+    // Thirds of 1000: the left over cent goes to the first value
+    let results = execute_script(exec, ctx, "
+        ALLOCATE balance(@rent, 2024-01-31) ON 2024-01-31 ACROSS Department
+            BY WEIGHTS {'Sales': 1, 'Ops': 1, 'IT': 1}
+            DESCRIPTION 'Rent allocation'
+            DEBIT @allocated_rent, CREDIT @rent;
+        GET balance(@allocated_rent, 2024-01-31, Department='Sales') AS sales,
+            balance(@allocated_rent, 2024-01-31, Department='IT') AS it,
+            balance(@rent, 2024-01-31) AS pool,
+            balance(@rent, 2024-01-31, Department='Sales') AS pool_sales,
+            statement(@allocated_rent, 2024-01-31, 2024-01-31) AS allocated,
+            statement(@allocated_rent, 2024-01-31, 2024-01-31, Department='Sales') AS allocated_sales,
+            journals(2024-01-31, 2024-01-31) AS journal
+    ");
+    assert_eq!(results[0].journals_created, 1);
+    assert_eq!(results[1].variables["sales"], DataValue::Money(rust_decimal::Decimal::new(33334, 2)));
+    assert_eq!(results[1].variables["it"], DataValue::Money(rust_decimal::Decimal::new(33333, 2)));
+    assert_eq!(results[1].variables["pool"], DataValue::Money(0.into()));
+    // the pool credit is not tagged with the department
+    assert_eq!(results[1].variables["pool_sales"], DataValue::Money(0.into()));
+    let DataValue::Statement(allocated) = &results[1].variables["allocated"] else { panic!("expected a statement") };
+    assert_eq!(allocated.len(), 3);
+    let DataValue::Statement(allocated_sales) = &results[1].variables["allocated_sales"] else { panic!("expected a statement") };
+    let amounts: Vec<_> = allocated_sales.iter().map(|t| t.amount).collect();
+    assert_eq!(amounts, vec![rust_decimal::Decimal::new(33334, 2)]);
+
+    // One journal: a debit per department and a single credit of the whole amount
+    let DataValue::Journals(journal) = &results[1].variables["journal"] else { panic!("expected Journals") };
+    assert_eq!(journal.len(), 1);
+    let legs: Vec<_> = journal[0].lines.iter()
+        .map(|line| (
+            line.account_id.to_string(),
+            line.dimensions.as_ref().map(|dimensions| format_data_value(&dimensions["Department"])),
+            line.debit,
+            line.credit,
+        ))
+        .collect();
+    assert_eq!(legs, vec![
+        ("allocated_rent".to_string(), Some("Sales".to_string()), rust_decimal::Decimal::new(33334, 2), rust_decimal::Decimal::ZERO),
+        ("allocated_rent".to_string(), Some("Ops".to_string()), rust_decimal::Decimal::new(33333, 2), rust_decimal::Decimal::ZERO),
+        ("allocated_rent".to_string(), Some("IT".to_string()), rust_decimal::Decimal::new(33333, 2), rust_decimal::Decimal::ZERO),
+        ("rent".to_string(), None, rust_decimal::Decimal::ZERO, rust_decimal::Decimal::from(1000)),
+    ]);
+
+    // Reversing it undoes every share, and the pool credit stays untagged
+    let results = execute_script(exec, ctx, &format!("
+        REVERSE JOURNAL '{}' ON 2024-01-31;
+        GET balance(@allocated_rent, 2024-01-31, Department='Sales') AS sales,
+            balance(@allocated_rent, 2024-01-31) AS allocated,
+            balance(@rent, 2024-01-31, Department='Sales') AS pool_sales,
+            balance(@rent, 2024-01-31) AS pool
+    ", format_journal_id(journal[0].id)));
+    assert_eq!(results[1].variables["sales"], DataValue::Money(0.into()));
+    assert_eq!(results[1].variables["allocated"], DataValue::Money(0.into()));
+    assert_eq!(results[1].variables["pool_sales"], DataValue::Money(0.into()));
+    assert_eq!(results[1].variables["pool"], DataValue::Money(1000.into()));
+
+    // Weighted by each department's revenue
+    let results = execute_script(exec, ctx, "
+        ALLOCATE 500 ON 2024-02-29 ACROSS Department
+            BY balance_by(@revenue, 2024-01-31, Department)
+            FOR Cost='IT'
+            DESCRIPTION 'IT allocation'
+            DEBIT @allocated_rent, CREDIT @bank;
+        GET balance(@allocated_rent, 2024-02-29, Cost='IT', Department='Sales') AS sales,
+            balance(@allocated_rent, 2024-02-29, Cost='IT', Department='Ops') AS ops,
+            activity(@bank, 2024-02-29, 2024-02-29, Cost='IT') AS paid
+    ");
+    assert_eq!(results[0].journals_created, 1);
+    assert_eq!(results[1].variables["sales"], DataValue::Money(300.into()));
+    assert_eq!(results[1].variables["ops"], DataValue::Money(200.into()));
+    assert_eq!(results[1].variables["paid"], DataValue::Money((-500).into()));
+
+    for fql in [
+        "ALLOCATE 100 ON 2024-02-29 ACROSS Region BY balance_by(@revenue, 2024-01-31, Department) DESCRIPTION 'x' DEBIT @allocated_rent, CREDIT @bank",
+        "ALLOCATE 100 ON 2024-02-29 ACROSS Department BY WEIGHTS {'Sales': 1} FOR Department='Ops' DESCRIPTION 'x' DEBIT @allocated_rent, CREDIT @bank",
+        "ALLOCATE 100 ON 2024-02-29 ACROSS Department BY WEIGHTS {'Sales': 1, 'Ops': -1} DESCRIPTION 'x' DEBIT @allocated_rent, CREDIT @bank",
+        "ALLOCATE 100.005 ON 2024-02-29 ACROSS Department BY WEIGHTS {'Sales': 1, 'Ops': 1} DESCRIPTION 'x' DEBIT @allocated_rent, CREDIT @bank",
+        "ALLOCATE 1000 ON 2024-02-29 ACROSS Department BY WEIGHTS {'Sales': 10000000000000000000000000000.0, 'Ops': 1} DESCRIPTION 'x' DEBIT @allocated_rent, CREDIT @bank",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{}", fql);
+    }
+});
+
+backend_test!(statistical_accounts_hold_quantities_outside_the_ledger, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @capital EQUITY;
+        CREATE ACCOUNT @rent EXPENSE;
+        CREATE ACCOUNT @allocated_rent EXPENSE;
+        CREATE ACCOUNT @headcount STATISTICAL;
+        CREATE ACCOUNT @floor_space STATISTICAL;
+        CREATE JOURNAL 2024-01-01, 5000, 'Capital' DEBIT @bank, CREDIT @capital;
+        CREATE JOURNAL 2024-01-01, 12, 'Sales team' FOR Department='Sales' DEBIT @headcount;
+        CREATE JOURNAL 2024-01-01, 8, 'Ops team' FOR Department='Ops' DEBIT @headcount;
+        CREATE JOURNAL 2024-01-15, 2, 'Ops leavers' FOR Department='Ops' CREDIT @headcount;
+        CREATE JOURNAL 2024-01-05, 1000, 'Office lease' DEBIT @rent 1000, CREDIT @bank 1000, DEBIT @floor_space 250;
+    ");
+
+    let results = execute_script(exec, ctx, "
+        GET balance(@headcount, 2024-01-31) AS headcount,
+            balance(@headcount, 2024-01-31, Department='Ops') AS ops,
+            balance(@floor_space, 2024-01-31) AS floor_space,
+            debits(@headcount, 2024-01-01, 2024-01-31) AS joiners,
+            credits(@headcount, 2024-01-01, 2024-01-31) AS leavers,
+            trial_balance(2024-01-31) AS tb,
+            balance_sheet(2024-01-31) AS bs
+    ");
+    assert_eq!(results[0].variables["headcount"], DataValue::Money(18.into()));
+    assert_eq!(results[0].variables["joiners"], DataValue::Money(20.into()));
+    assert_eq!(results[0].variables["leavers"], DataValue::Money(2.into()));
+    assert_eq!(results[0].variables["ops"], DataValue::Money(6.into()));
+    assert_eq!(results[0].variables["floor_space"], DataValue::Money(250.into()));
+    let DataValue::TrialBalance(items) = &results[0].variables["tb"] else { panic!("expected a trial balance") };
+    assert!(items.iter().all(|item| item.account_type != dblentry::ast::AccountType::Statistical));
+    assert_trial_balance_balanced(&results[0].variables["tb"], "statistical");
+    let DataValue::BalanceSheet(sheet) = &results[0].variables["bs"] else { panic!("expected a balance sheet") };
+    assert_eq!(sheet.total_assets, rust_decimal::Decimal::from(4000));
+    assert!(sheet.balanced);
+
+    // Headcount drives the rent allocation: 12 to 6
+    let results = execute_script(exec, ctx, "
+        ALLOCATE 900 ON 2024-01-31 ACROSS Department
+            BY balance_by(@headcount, 2024-01-31, Department)
+            DESCRIPTION 'Rent by headcount'
+            DEBIT @allocated_rent, CREDIT @rent;
+        GET balance(@allocated_rent, 2024-01-31, Department='Sales') AS sales
+    ");
+    assert_eq!(results[1].variables["sales"], DataValue::Money(600.into()));
+
+    for fql in [
+        "CREATE JOURNAL 2024-01-31, 10, 'Unbalanced' DEBIT @rent 10, CREDIT @bank 5, CREDIT @headcount 5",
+        "CREATE ACCOUNT @units_shipped STATISTICAL CASHFLOW OPERATING",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{}", fql);
+    }
+});
+
+backend_test!(currency_accounts_post_in_two_currencies, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE RATE EUR;
+        CREATE RATE GBP;
+        SET RATE EUR 1.10 2024-01-01;
+        SET RATE EUR 1.20 2024-03-01;
+        CREATE ACCOUNT @eur_cash ASSET CURRENCY 'EUR';
+        CREATE ACCOUNT @bank_eur ASSET UNDER @eur_cash;
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE ACCOUNT @fees EXPENSE;
+        CREATE JOURNAL 2024-01-10, 1100, 'EUR sale' DEBIT @bank_eur 1000, CREDIT @revenue 1100;
+        CREATE JOURNAL 2024-03-05, 240, 'Bank fees' DEBIT @fees, CREDIT @bank_eur;
+    ");
+
+    let results = execute_script(exec, ctx, "
+        GET balance(@bank_eur, 2024-01-31) AS january,
+            balance(@bank_eur, 2024-01-31, transaction) AS january_eur,
+            balance(@eur_cash, 2024-03-31, functional) AS march,
+            balance(@eur_cash, 2024-03-31, transaction) AS march_eur,
+            balance(@revenue, 2024-03-31, transaction) AS revenue,
+            statement(@bank_eur, 2024-01-01, 2024-03-31, transaction) AS stmt,
+            trial_balance(2024-03-31) AS tb
+    ");
+    assert_eq!(results[0].variables["january"], DataValue::Money(1100.into()));
+    assert_eq!(results[0].variables["january_eur"], DataValue::Money(1000.into()));
+    assert_eq!(results[0].variables["march"], DataValue::Money(860.into()));
+    // 240 of fees at 1.20 is 200 EUR
+    assert_eq!(results[0].variables["march_eur"], DataValue::Money(800.into()));
+    // Functional-currency accounts read the same in either mode
+    assert_eq!(results[0].variables["revenue"], DataValue::Money(1100.into()));
+    let DataValue::Statement(txns) = &results[0].variables["stmt"] else { panic!("expected a statement") };
+    let amounts: Vec<_> = txns.iter().map(|t| (t.amount, t.balance)).collect();
+    assert_eq!(amounts, vec![(1000.into(), 1000.into()), ((-200).into(), 800.into())]);
+    assert_trial_balance_balanced(&results[0].variables["tb"], "currency");
+
+    for fql in [
+        // 1000 EUR is 1100 in the functional currency
+        "CREATE JOURNAL 2024-01-10, 1000, 'Unbalanced' DEBIT @bank_eur 1000, CREDIT @revenue 1000",
+        "CREATE ACCOUNT @eur_sales INCOME CURRENCY 'EUR'",
+        "CREATE ACCOUNT @gbp_cash ASSET CURRENCY 'GBP' UNDER @eur_cash",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{}", fql);
+    }
+});
+
+backend_test!(currency_transfer_with_functional_amounts, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE RATE EUR;
+        CREATE RATE GBP;
+        SET RATE EUR 1.10 2024-01-01;
+        SET RATE GBP 1.30 2024-01-01;
+        CREATE ACCOUNT @bank_eur ASSET CURRENCY 'EUR';
+        CREATE ACCOUNT @bank_gbp ASSET CURRENCY 'GBP';
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE JOURNAL 2024-01-05, 1100, 'EUR sale' DEBIT @bank_eur 1000, CREDIT @revenue 1100;
+        CREATE JOURNAL 2024-01-10, 546, 'EUR to GBP' DEBIT @bank_gbp 420 FUNCTIONAL 546, CREDIT @bank_eur 500 FUNCTIONAL 546;
+    ");
+
+    let results = execute_script(exec, ctx, "
+        GET balance(@bank_eur, 2024-01-31) AS eur,
+            balance(@bank_eur, 2024-01-31, transaction) AS eur_eur,
+            balance(@bank_gbp, 2024-01-31) AS gbp,
+            balance(@bank_gbp, 2024-01-31, transaction) AS gbp_gbp,
+            statement(@bank_gbp, 2024-01-01, 2024-01-31) AS transfer
+    ");
+    assert_eq!(results[0].variables["eur"], DataValue::Money(554.into()));
+    assert_eq!(results[0].variables["eur_eur"], DataValue::Money(500.into()));
+    assert_eq!(results[0].variables["gbp"], DataValue::Money(546.into()));
+    assert_eq!(results[0].variables["gbp_gbp"], DataValue::Money(420.into()));
+
+    // The reversal takes both amounts of each leg from the stored journal
+    let jid = first_journal_id(&results[0].variables["transfer"]);
+    execute_script(exec, ctx, &format!("REVERSE JOURNAL '{}' ON 2024-01-20", uuid::Uuid::from_u128(jid)));
+    let results = execute_script(exec, ctx, "
+        GET balance(@bank_eur, 2024-01-31, transaction) AS eur_eur,
+            balance(@bank_gbp, 2024-01-31) AS gbp,
+            balance(@bank_gbp, 2024-01-31, transaction) AS gbp_gbp
+    ");
+    assert_eq!(results[0].variables["eur_eur"], DataValue::Money(1000.into()));
+    assert_eq!(results[0].variables["gbp"], DataValue::Money(0.into()));
+    assert_eq!(results[0].variables["gbp_gbp"], DataValue::Money(0.into()));
+
+    for fql in [
+        // 420 GBP is 546 and 500 EUR is 550 at the day's rates
+        "CREATE JOURNAL 2024-01-10, 546, 'EUR to GBP' DEBIT @bank_gbp 420, CREDIT @bank_eur 500",
+        "CREATE JOURNAL 2024-01-10, 100, 'Not a currency leg' DEBIT @bank_gbp 77 FUNCTIONAL 100, CREDIT @revenue 100 FUNCTIONAL 100",
+        "CREATE JOURNAL 2024-01-10, 100, 'Percentage' DEBIT @bank_gbp 100% FUNCTIONAL 100, CREDIT @revenue",
+        "CREATE JOURNAL 2024-01-10, 100, 'Opposite signs' DEBIT @bank_gbp -77 FUNCTIONAL 100, CREDIT @revenue",
+        "CREATE ACCOUNT @bank_usd ASSET CURRENCY 'USD'",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        assert!(exec.execute(ctx, &stmts[0]).is_err(), "{}", fql);
+    }
+});
+
 backend_test!(cross_sell_with_dimensions, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
     execute_script(exec, ctx, "
         CREATE RATE AAPL;
@@ -3344,7 +4483,7 @@ fn test_list_rates_memory() {
     register_functions(&function_registry, &storage);
     let evaluator = Arc::new(ExpressionEvaluator::new(Arc::new(function_registry), storage.clone()));
     let exec = StatementExecutor::new(evaluator, storage.clone());
-    let mut ctx = ExecutionContext::new(time::OffsetDateTime::now_utc().date(), QueryVariables::new());
+    let mut ctx = admin_context();
     execute_script(&exec, &mut ctx, "CREATE RATE usd_eur; CREATE RATE usd_gbp;");
 
     let rates = storage.list_rates("default");
@@ -3362,7 +4501,7 @@ fn test_list_rates_sqlite() {
     register_functions(&function_registry, &storage);
     let evaluator = Arc::new(ExpressionEvaluator::new(Arc::new(function_registry), storage.clone()));
     let exec = StatementExecutor::new(evaluator, storage.clone());
-    let mut ctx = ExecutionContext::new(time::OffsetDateTime::now_utc().date(), QueryVariables::new());
+    let mut ctx = admin_context();
     // SQLite create_rate is a no-op; rows appear after SET RATE
     execute_script(&exec, &mut ctx, "
         CREATE RATE alpha; SET RATE alpha 1.0 2024-01-01;
@@ -3389,7 +4528,7 @@ fn test_list_accounts_memory() {
     register_functions(&function_registry, &storage);
     let evaluator = Arc::new(ExpressionEvaluator::new(Arc::new(function_registry), storage.clone()));
     let exec = StatementExecutor::new(evaluator, storage.clone());
-    let mut ctx = ExecutionContext::new(time::OffsetDateTime::now_utc().date(), QueryVariables::new());
+    let mut ctx = admin_context();
     execute_script(&exec, &mut ctx, "CREATE ACCOUNT @bank ASSET; CREATE ACCOUNT @equity EQUITY;");
 
     let accounts = storage.list_accounts("default");
@@ -3408,7 +4547,7 @@ fn test_list_functions() {
     register_functions(&registry, &storage);
 
     let funcs = registry.list_functions();
-    assert_eq!(funcs.len(), 16);
+    assert_eq!(funcs.len(), 28);
     // Verify sorted
     let mut sorted = funcs.clone();
     sorted.sort();
@@ -3419,6 +4558,8 @@ fn test_list_functions() {
     assert!(funcs.contains(&"lots".to_string()));
     assert!(funcs.contains(&"income_statement".to_string()));
     assert!(funcs.contains(&"convert".to_string()));
+    assert!(funcs.contains(&"journals".to_string()));
+    assert!(funcs.contains(&"balance_by".to_string()));
 }
 
 // ===== Batch FQL Tests =====
@@ -3444,7 +4585,7 @@ fn test_batch_execute_script_rollback_on_error() {
     assert!(result.is_err());
 
     // Cash should still be 0 (rolled back)
-    let mut fresh_ctx = ExecutionContext::new(ctx.effective_date, QueryVariables::new());
+    let mut fresh_ctx = admin_context();
     let results = execute_script(&exec, &mut fresh_ctx, "GET balance(@cash, 2024-12-31) AS bal");
     assert_eq!(results[0].variables["bal"], DataValue::Money(rust_decimal::Decimal::ZERO));
 }
@@ -3810,3 +4951,373 @@ backend_test!(cross_entity_hierarchical_dims, |exec: &StatementExecutor, ctx: &m
     let results = execute_script(exec, ctx, "GET balance(@revenue, 2024-12-31, Category='Electronics') AS b");
     assert_eq!(results[0].variables["b"], DataValue::Money(rust_decimal::Decimal::ZERO));
 });
+
+// --- Role enforcement ---
+
+#[test]
+fn test_reader_role_can_only_get() {
+    let (exec, mut ctx) = setup();
+    execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @equity EQUITY;
+        CREATE JOURNAL 2024-01-01, 100, 'Seed' DEBIT @bank, CREDIT @equity;
+    ");
+    ctx.role = Role::Reader;
+
+    let results = execute_script(&exec, &mut ctx, "GET balance(@bank, 2024-12-31) AS b");
+    assert_eq!(results[0].variables["b"], DataValue::Money(rust_decimal::Decimal::from(100)));
+
+    for fql in [
+        "CREATE JOURNAL 2024-01-02, 50, 'Nope' DEBIT @bank, CREDIT @equity",
+        "CREATE RATE fx",
+        "SET RATE fx 1.5 2024-01-01",
+        "CREATE ACCOUNT @other ASSET",
+        "CREATE ENTITY 'other'",
+        "LET x = 1",
+        "USE ENTITY 'default'",
+        "BEGIN",
+        "COMMIT",
+        "ROLLBACK",
+    ] {
+        let stmts = lexer::parse(fql).unwrap();
+        let result = exec.execute(&mut ctx, &stmts[0]);
+        assert!(matches!(result, Err(EvaluationError::PermissionDenied(_))), "reader should not run: {}", fql);
+    }
+
+    ctx.role = Role::Admin;
+    let results = execute_script(&exec, &mut ctx, "GET balance(@bank, 2024-12-31) AS b");
+    assert_eq!(results[0].variables["b"], DataValue::Money(rust_decimal::Decimal::from(100)));
+}
+
+#[test]
+fn test_writer_role_can_post_but_not_create_accounts() {
+    let (exec, mut ctx) = setup();
+    execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @equity EQUITY;
+    ");
+    ctx.role = Role::Writer;
+
+    let results = execute_script(&exec, &mut ctx, "
+        CREATE RATE fx;
+        SET RATE fx 1.5 2024-01-01;
+        CREATE JOURNAL 2024-01-01, 100, 'Posted by writer' DEBIT @bank, CREDIT @equity;
+    ");
+    assert_eq!(results[2].journals_created, 1);
+
+    for fql in ["CREATE ACCOUNT @other ASSET", "CREATE ENTITY 'other'"] {
+        let stmts = lexer::parse(fql).unwrap();
+        let result = exec.execute(&mut ctx, &stmts[0]);
+        assert!(matches!(result, Err(EvaluationError::PermissionDenied(_))), "writer should not run: {}", fql);
+    }
+}
+
+#[test]
+fn test_permission_denied_rolls_back_script() {
+    let (exec, mut ctx) = setup();
+    execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @equity EQUITY;
+    ");
+    ctx.role = Role::Writer;
+
+    let stmts = lexer::parse("
+        CREATE JOURNAL 2024-01-01, 100, 'Posted' DEBIT @bank, CREDIT @equity;
+        CREATE ACCOUNT @sneaky ASSET;
+    ").unwrap();
+    let result = exec.execute_script(&mut ctx, &stmts);
+    assert!(matches!(result, Err(EvaluationError::PermissionDenied(_))));
+
+    let results = execute_script(&exec, &mut ctx, "GET balance(@bank, 2024-12-31) AS b");
+    assert_eq!(results[0].variables["b"], DataValue::Money(rust_decimal::Decimal::ZERO));
+}
+
+#[test]
+fn test_required_role_by_statement() {
+    let cases = [
+        ("GET balance(@bank, 2024-01-01) AS b", Role::Reader),
+        ("USE ENTITY 'default'", Role::Writer),
+        ("LET x = 1", Role::Writer),
+        ("BEGIN", Role::Writer),
+        ("COMMIT", Role::Writer),
+        ("ROLLBACK", Role::Writer),
+        ("CREATE JOURNAL 2024-01-01, 1, 'j' DEBIT @a, CREDIT @b", Role::Writer),
+        ("CREATE RATE fx", Role::Writer),
+        ("SET RATE fx 1 2024-01-01", Role::Writer),
+        ("REVERSE JOURNAL '1' ON 2024-01-02", Role::Writer),
+        ("ALLOCATE 100 ON 2024-01-31 ACROSS Department BY WEIGHTS {'Sales': 1} DESCRIPTION 'a' DEBIT @a, CREDIT @b", Role::Writer),
+        ("AMORTIZE 1000 AT RATE 12% OVER 3 MONTHLY FROM 2024-01-31 DESCRIPTION 'p' DEBIT @a, CREDIT @b", Role::Writer),
+        ("AMORTIZE 1000 AT RATE 12% OVER 3 MONTHLY FROM 2024-01-31 PREVIEW", Role::Reader),
+        ("CREATE ASSET truck IN SERVICE 2024-01-01 COST 6000 LIFE 5 YEARS ACCOUNT @a ACCUMULATED @b EXPENSE @c", Role::Writer),
+        ("DEPRECIATE THROUGH 2024-12-31", Role::Writer),
+        ("DISPOSE ASSET truck AT 100 ON 2024-12-31 PROCEEDS @a GAIN_LOSS @b DESCRIPTION 'd'", Role::Writer),
+        ("CREATE ACCOUNT @a ASSET", Role::Admin),
+        ("CREATE ENTITY 'e'", Role::Admin),
+        ("LOCK PERIOD THROUGH 2024-12-31", Role::Admin),
+        ("UNLOCK", Role::Admin),
+        ("CLOSE YEAR ENDING 2024-12-31 INTO @retained", Role::Admin),
+    ];
+    for (fql, expected) in cases {
+        let stmts = lexer::parse(fql).unwrap();
+        assert_eq!(auth::required_role(&stmts[0]), expected, "{}", fql);
+    }
+    assert_eq!(Role::parse("WRITER"), Role::Writer);
+    assert_eq!(Role::parse("unknown"), Role::Reader);
+    // Contexts start as readers until an entry point grants more
+    assert_eq!(ExecutionContext::new(time::OffsetDateTime::now_utc().date(), QueryVariables::new()).role, Role::Reader);
+}
+
+// --- Named parameters ---
+
+#[test]
+fn test_bound_params_in_journal_and_query() {
+    let (exec, mut ctx) = setup();
+    execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @equity EQUITY;
+    ");
+
+    let params = FqlParams::from([
+        ("date".to_string(), FqlParam::Date("2024-03-01".to_string())),
+        ("amount".to_string(), FqlParam::Money("250.75".to_string())),
+        ("description".to_string(), FqlParam::String("O'Brien's deposit".to_string())),
+        ("investor".to_string(), FqlParam::String("O'Brien".to_string())),
+        ("account".to_string(), FqlParam::Account("@bank".to_string())),
+    ]);
+    ctx.variables = bind_params(&params).unwrap();
+
+    let results = execute_script(&exec, &mut ctx, "
+        CREATE JOURNAL $date, $amount, $description FOR Investor=$investor DEBIT @bank, CREDIT @equity;
+        GET balance($account, $date, Investor=$investor) AS b, statement($account, $date, $date) AS s;
+    ");
+    assert_eq!(results[0].journals_created, 1);
+    assert_eq!(results[1].variables["b"], DataValue::Money(rust_decimal::Decimal::new(25075, 2)));
+    match &results[1].variables["s"] {
+        DataValue::Statement(txns) => assert_eq!(txns[0].description.as_ref(), "O'Brien's deposit"),
+        other => panic!("expected Statement, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_bind_params_rejects_bad_values() {
+    for (param, fragment) in [
+        (FqlParam::Money("ten".to_string()), "invalid money"),
+        (FqlParam::Date("2024-02-30".to_string()), "invalid date"),
+        (FqlParam::Account("bank; DROP".to_string()), "invalid account"),
+        (FqlParam::Percentage("x%".to_string()), "invalid percentage"),
+    ] {
+        let err = bind_params(&FqlParams::from([("p".to_string(), param)])).unwrap_err();
+        assert!(err.contains("$p") && err.contains(fragment), "unexpected error: {}", err);
+    }
+    // a percentage is a fraction of one, like a `5%` literal
+    for value in ["5%", "5"] {
+        assert_eq!(
+            FqlParam::from_parts("PERCENTAGE", value).unwrap().to_data_value().unwrap(),
+            DataValue::Percentage(rust_decimal::Decimal::new(5, 2))
+        );
+    }
+    assert!(FqlParam::from_parts("float", "1.0").is_err());
+}
+
+// --- LET ---
+
+backend_test!(let_balance_feeds_journal, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @receivable ASSET;
+        CREATE ACCOUNT @income INCOME;
+        CREATE JOURNAL 2024-01-10, 120, 'Interest earned' DEBIT @receivable, CREDIT @income;
+    ");
+
+    let stmts = lexer::parse("
+        LET owed = balance(@receivable, 2024-01-31);
+        LET half = owed / 2;
+        CREATE JOURNAL 2024-01-31, owed, 'Sweep interest' DEBIT @bank, CREDIT @receivable;
+        GET balance(@bank, 2024-01-31) AS bank, $half AS half, balance(@receivable, 2024-01-31) AS left;
+    ").unwrap();
+    let results = exec.execute_script(ctx, &stmts).unwrap();
+    let get = &results[3].variables;
+    assert_money(&get["bank"], "120", "bank");
+    assert_money(&get["half"], "60", "half");
+    assert_money(&get["left"], "0", "left");
+});
+
+#[test]
+fn test_let_unknown_identifier_errors() {
+    let (exec, mut ctx) = setup();
+    let stmts = lexer::parse("LET x = missing + 1").unwrap();
+    let result = exec.execute(&mut ctx, &stmts[0]);
+    assert!(matches!(result, Err(EvaluationError::UnknownIdentifier(_))));
+}
+
+// --- Journal reversals ---
+
+fn first_journal_id(stmt: &DataValue) -> u128 {
+    match stmt {
+        DataValue::Statement(txns) => txns[0].journal_id,
+        other => panic!("expected Statement, got {:?}", other),
+    }
+}
+
+backend_test!(reverse_journal_mirrors_original, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @expense EXPENSE;
+        CREATE ACCOUNT @accrued LIABILITY;
+        CREATE JOURNAL 2024-01-31, 500, 'Utilities accrual' FOR Dept='Ops' DEBIT @expense, CREDIT @accrued;
+    ");
+    let results = execute_script(exec, ctx, "GET statement(@expense, 2024-01-01, 2024-01-31) AS s");
+    let jid = first_journal_id(&results[0].variables["s"]);
+
+    let results = execute_script(exec, ctx, &format!("REVERSE JOURNAL '{}' ON 2024-02-01", jid));
+    assert_eq!(results[0].journals_created, 1);
+
+    let results = execute_script(exec, ctx, "
+        GET balance(@expense, 2024-01-31) AS jan,
+            balance(@expense, 2024-02-01) AS feb,
+            balance(@accrued, 2024-02-01, Dept='Ops') AS accrued,
+            statement(@accrued, 2024-02-01, 2024-02-01) AS s
+    ");
+    let get = &results[0].variables;
+    assert_money(&get["jan"], "500", "jan");
+    assert_money(&get["feb"], "0", "feb");
+    assert_money(&get["accrued"], "0", "accrued");
+    match &get["s"] {
+        DataValue::Statement(txns) => assert_eq!(txns[0].description.as_ref(), "Reversal of Utilities accrual"),
+        other => panic!("expected Statement, got {:?}", other),
+    }
+
+    let stmts = lexer::parse(&format!("REVERSE JOURNAL '{}' ON 2024-02-02", jid)).unwrap();
+    let err = exec.execute_script(ctx, &stmts).unwrap_err();
+    assert!(err.to_string().contains("already been reversed"), "unexpected error: {}", err);
+});
+
+backend_test!(reverse_journal_keeps_sides_of_negative_legs, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @fees EXPENSE;
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE JOURNAL 2024-01-10, 100, 'Sale net of fee rebate' DEBIT @bank 100, DEBIT @fees -10, CREDIT @revenue 90;
+    ");
+    let results = execute_script(exec, ctx, "GET statement(@bank, 2024-01-01, 2024-01-31) AS s");
+    let jid = first_journal_id(&results[0].variables["s"]);
+    execute_script(exec, ctx, &format!("REVERSE JOURNAL '{}' ON 2024-01-20", jid));
+
+    let results = execute_script(exec, ctx, "GET journals(2024-01-01, 2024-01-31) AS j");
+    let DataValue::Journals(journals) = &results[0].variables["j"] else { panic!("expected Journals") };
+    let fees = |journal: &dblentry::dblentry_core::JournalEntry| journal.lines.iter()
+        .find(|l| l.account_id.as_ref() == "fees")
+        .map(|l| (l.debit, l.credit))
+        .unwrap();
+    // A negative debit stays a debit, and the reversal credits it
+    assert_eq!(fees(&journals[0]), (rust_decimal::Decimal::from(-10), rust_decimal::Decimal::ZERO));
+    assert_eq!(fees(&journals[1]), (rust_decimal::Decimal::ZERO, rust_decimal::Decimal::from(-10)));
+});
+
+backend_test!(auto_reverse_posts_linked_reversal, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @expense EXPENSE;
+        CREATE ACCOUNT @accrued LIABILITY;
+    ");
+    let results = execute_script(exec, ctx, "
+        CREATE JOURNAL 2024-01-31, 300, 'Accrued wages' AUTO REVERSE ON 2024-02-01 DEBIT @expense, CREDIT @accrued;
+        GET balance(@accrued, 2024-01-31) AS jan, balance(@accrued, 2024-02-01) AS feb,
+            statement(@expense, 2024-01-31, 2024-01-31) AS s;
+    ");
+    assert_eq!(results[0].journals_created, 2);
+    assert_money(&results[1].variables["jan"], "300", "jan");
+    assert_money(&results[1].variables["feb"], "0", "feb");
+
+    // The reversal is linked, so the original cannot be reversed again
+    let jid = first_journal_id(&results[1].variables["s"]);
+    let stmts = lexer::parse(&format!("REVERSE JOURNAL '{}' ON 2024-02-05 DESCRIPTION 'again'", jid)).unwrap();
+    assert!(exec.execute_script(ctx, &stmts).is_err());
+});
+
+#[test]
+fn test_reverse_journal_rejections() {
+    let (exec, mut ctx) = setup();
+    execute_script(&exec, &mut ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @equity EQUITY;
+        CREATE JOURNAL 2024-03-01, 100, 'Deposit' DEBIT @bank, CREDIT @equity;
+    ");
+    let results = execute_script(&exec, &mut ctx, "GET statement(@bank, 2024-03-01, 2024-03-01) AS s");
+    let jid = first_journal_id(&results[0].variables["s"]);
+
+    let stmts = lexer::parse(&format!("REVERSE JOURNAL '{}' ON 2024-02-01", uuid::Uuid::from_u128(jid))).unwrap();
+    let err = exec.execute(&mut ctx, &stmts[0]).unwrap_err();
+    assert!(err.to_string().contains("before the journal date"), "unexpected error: {}", err);
+
+    let stmts = lexer::parse("REVERSE JOURNAL '00000000-0000-0000-0000-000000000001' ON 2024-03-02").unwrap();
+    let err = exec.execute(&mut ctx, &stmts[0]).unwrap_err();
+    assert!(matches!(err, EvaluationError::StorageError(StorageError::JournalNotFound(_))));
+
+    let stmts = lexer::parse("CREATE JOURNAL 2024-03-05, 10, 'x' AUTO REVERSE ON 2024-03-01 DEBIT @bank, CREDIT @equity").unwrap();
+    assert!(exec.execute(&mut ctx, &stmts[0]).is_err());
+}
+
+// --- Journal read-back ---
+
+backend_test!(journals_returns_legs_and_filters_by_dimension, |exec: &StatementExecutor, ctx: &mut ExecutionContext| {
+    execute_script(exec, ctx, "
+        CREATE ACCOUNT @bank ASSET;
+        CREATE ACCOUNT @revenue INCOME;
+        CREATE ACCOUNT @tax LIABILITY;
+        CREATE JOURNAL 2024-01-05, 100, 'US sale' FOR Region='Americas/US' DEBIT @bank, CREDIT @revenue 80, CREDIT @tax 20;
+        CREATE JOURNAL 2024-01-07, 50, 'EU sale' FOR Region='Europe' DEBIT @bank, CREDIT @revenue;
+        CREATE JOURNAL 2024-02-01, 10, 'Late sale' FOR Region='Americas/CA' DEBIT @bank, CREDIT @revenue;
+    ");
+
+    let results = execute_script(exec, ctx, "
+        GET journals(2024-01-01, 2024-01-31) AS jan,
+            journals(2024-01-01, 2024-12-31, Region='Americas') AS americas
+    ");
+    let jan = match &results[0].variables["jan"] {
+        DataValue::Journals(journals) => journals.clone(),
+        other => panic!("expected Journals, got {:?}", other),
+    };
+    assert_eq!(jan.len(), 2);
+    assert_eq!(jan[0].description.as_ref(), "US sale");
+    assert_eq!(jan[0].lines.len(), 3);
+    let tax = jan[0].lines.iter().find(|l| l.account_id.as_ref() == "tax").unwrap();
+    assert_eq!(tax.credit, rust_decimal::Decimal::from(20));
+    assert!(tax.debit.is_zero());
+    let bank = jan[0].lines.iter().find(|l| l.account_id.as_ref() == "bank").unwrap();
+    assert_eq!(bank.debit, rust_decimal::Decimal::from(100));
+    assert_eq!(format_data_value(&jan[0].dimensions["Region"]), "Americas/US");
+    // Legs and dimensions stay with their own journal when loaded together
+    assert_eq!(jan[1].lines.len(), 2);
+    assert!(jan[1].lines.iter().all(|l| l.dimensions.is_none()));
+    assert_eq!(format_data_value(&jan[1].dimensions["Region"]), "Europe");
+
+    match &results[0].variables["americas"] {
+        DataValue::Journals(journals) => {
+            let descriptions: Vec<&str> = journals.iter().map(|j| j.description.as_ref()).collect();
+            assert_eq!(descriptions, vec!["US sale", "Late sale"]);
+        }
+        other => panic!("expected Journals, got {:?}", other),
+    }
+
+    let results = execute_script(exec, ctx, &format!("GET journal('{}') AS j", format_journal_id(jan[1].id)));
+    match &results[0].variables["j"] {
+        DataValue::Journals(journals) => {
+            assert_eq!(journals.len(), 1);
+            assert_eq!(journals[0].id, jan[1].id);
+            assert_eq!(journals[0].amount, rust_decimal::Decimal::from(50));
+        }
+        other => panic!("expected Journals, got {:?}", other),
+    }
+});
+
+#[test]
+fn test_journal_function_errors() {
+    let (exec, mut ctx) = setup();
+    let stmts = lexer::parse("GET journal('not-an-id') AS j").unwrap();
+    assert!(matches!(exec.execute(&mut ctx, &stmts[0]), Err(EvaluationError::InvalidArgument(_))));
+
+    let stmts = lexer::parse("GET journal('42') AS j").unwrap();
+    assert!(matches!(
+        exec.execute(&mut ctx, &stmts[0]),
+        Err(EvaluationError::StorageError(StorageError::JournalNotFound(_)))
+    ));
+}